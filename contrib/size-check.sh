@@ -0,0 +1,27 @@
+#!/bin/sh
+# Builds the `size` example with and without the `small-hash` feature and
+# prints the size of each stripped binary. Fails if the `small-hash` build is
+# not the smaller one. Uses opt-level "s" unless the profile is overridden.
+set -e
+
+cd "$(dirname "$0")/.."
+export CARGO_PROFILE_RELEASE_OPT_LEVEL="${CARGO_PROFILE_RELEASE_OPT_LEVEL:-s}"
+TARGET_DIR=target/size-check
+
+size_of() {
+    cargo build -q --release --example size --target-dir "$TARGET_DIR/$1" $2
+    strip -o "$TARGET_DIR/$1/size" "$TARGET_DIR/$1/release/examples/size"
+    wc -c < "$TARGET_DIR/$1/size"
+}
+
+DEFAULT=$(size_of default "")
+SMALL=$(size_of small "--features small-hash")
+
+echo "default:    $DEFAULT bytes"
+echo "small-hash: $SMALL bytes"
+echo "saved:      $((DEFAULT - SMALL)) bytes"
+
+if [ "$SMALL" -ge "$DEFAULT" ]; then
+    echo "small-hash build is not smaller" >&2
+    exit 1
+fi
@@ -0,0 +1,58 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Logs hashes with `defmt`, as firmware would, without formatting them to
+//! a `String` first.
+//!
+//! `log_signing` is what goes in the firmware; there the global logger and
+//! timestamp come from e.g. `defmt-rtt` and the board's timer, and the host
+//! decodes the frames with `probe-rs` or `defmt-print`. So that this runs on
+//! the host, the logger here just prints each encoded frame as bytes:
+//!
+//! `DEFMT_LOG=info cargo run --example defmt --features defmt`
+
+extern crate bitcoin_hashes;
+extern crate defmt;
+
+use bitcoin_hashes::{hmac, sha256, sha256d, Hash, HashEngine};
+
+/// What a signer might log; nothing here allocates
+fn log_signing(tx: &[u8], key: &[u8]) {
+    // Shown in display order, like `Display`, so this matches a block explorer
+    let txid = sha256d::Hash::hash(tx);
+    defmt::info!("signing txid {}", txid);
+
+    let mut engine = hmac::HmacEngine::<sha256::Hash>::new(key);
+    engine.input(tx);
+    let mac = hmac::Hmac::from_engine(engine);
+    defmt::debug!("request mac {}", mac);
+}
+
+#[defmt::global_logger]
+struct StdoutLogger;
+
+unsafe impl defmt::Logger for StdoutLogger {
+    fn acquire() {}
+    unsafe fn flush() {}
+    unsafe fn release() {}
+    unsafe fn write(bytes: &[u8]) {
+        println!("{:02x?}", bytes);
+    }
+}
+
+defmt::timestamp!("{=u32}", 0);
+
+fn main() {
+    log_signing(b"not really a transaction", b"not really a key");
+}
@@ -0,0 +1,38 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Links the code affected by the `small-hash` feature, for measuring its
+//! size with `contrib/size-check.sh`.
+//!
+//! Hex-decodes each argument and prints its SHA1, SHA256 and RIPEMD160.
+
+extern crate bitcoin_hashes;
+
+use bitcoin_hashes::hex::FromHex;
+use bitcoin_hashes::{ripemd160, sha1, sha256, Hash};
+
+fn main() {
+    for arg in std::env::args().skip(1) {
+        let data = match Vec::<u8>::from_hex(&arg) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("{}: {}", arg, e);
+                std::process::exit(1);
+            }
+        };
+        println!("{}", sha1::Hash::hash(&data));
+        println!("{}", sha256::Hash::hash(&data));
+        println!("{}", ripemd160::Hash::hash(&data));
+    }
+}
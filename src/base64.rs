@@ -0,0 +1,263 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # Base64
+//!
+//! Encoding and decoding of hashes as base64, using the standard alphabet
+//! with padding (RFC 4648 section 4). This is for protocols which exchange
+//! digests and MACs in base64; `Display` and `FromStr` stay hex.
+//!
+//! Hashes are encoded in their internal byte order, i.e. as the raw bytes
+//! of the digest, even for types such as `sha256d` which display backwards.
+//!
+//! Parsing is strict, so that every hash has exactly one accepted encoding:
+//! the URL-safe characters `-` and `_` are rejected, as is input with
+//! missing padding or with nonzero unused bits in its last character.
+
+#[cfg(any(test, feature = "std"))]
+use core::fmt;
+
+use {Error, Hash};
+
+/// The standard base64 alphabet
+#[cfg(any(test, feature = "std"))]
+const CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Trait for objects that can be serialized as base64 strings
+#[cfg(any(test, feature = "std"))]
+pub trait ToBase64 {
+    /// Base64 representation of the object, with padding
+    fn to_base64(&self) -> String;
+}
+
+/// Trait for objects that can be deserialized from base64 strings
+pub trait FromBase64: Sized {
+    /// Produce an object from a padded base64 string
+    fn from_base64(s: &str) -> Result<Self, Error>;
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<T: Hash> ToBase64 for T {
+    fn to_base64(&self) -> String {
+        let mut ret = String::with_capacity(encoded_len(T::LEN));
+        encode_to_fmt(&self[..], &mut ret).expect("writing to a string");
+        ret
+    }
+}
+
+impl<T: Hash> FromBase64 for T where T::Inner: AsMut<[u8]> {
+    fn from_base64(s: &str) -> Result<Self, Error> {
+        let mut buf = T::default().into_inner();
+        let out = buf.as_mut();
+        decode_to_slice(s, out)?;
+        T::from_slice(out)
+    }
+}
+
+/// Length of the padded base64 encoding of `len` bytes
+#[allow(clippy::manual_div_ceil)] // `div_ceil` needs Rust 1.73
+pub fn encoded_len(len: usize) -> usize {
+    (len + 2) / 3 * 4
+}
+
+/// Writes `src` as padded base64 into an object implementing `fmt::Write`
+#[cfg(any(test, feature = "std"))]
+pub fn encode_to_fmt<W: fmt::Write + ?Sized>(src: &[u8], f: &mut W) -> fmt::Result {
+    for chunk in src.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        let mut buf = [b'='; 4];
+        for (i, c) in buf.iter_mut().enumerate().take(chunk.len() + 1) {
+            *c = CHARS[(n >> (18 - 6 * i) & 0x3f) as usize];
+        }
+        f.write_str(::core::str::from_utf8(&buf).expect("base64 characters are ASCII"))?;
+    }
+    Ok(())
+}
+
+/// Value of a character of the standard alphabet
+fn decode_char(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Error for the character at byte offset `pos` of `s`
+fn invalid_char(s: &str, pos: usize) -> Error {
+    let c = s[pos..].chars().next().expect("pos is within the string");
+    Error::InvalidBase64Char(c, pos)
+}
+
+/// Decodes padded base64 into a slice, which must be exactly the decoded
+/// length
+///
+/// A character which is valid base64 but has nonzero bits that the encoding
+/// of `out.len()` bytes leaves unused is reported as `InvalidBase64Char`.
+#[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs Rust 1.87
+pub fn decode_to_slice(s: &str, out: &mut [u8]) -> Result<(), Error> {
+    let bytes = s.as_bytes();
+    if bytes.len() % 4 != 0 {
+        return Err(Error::InvalidBase64Length(bytes.len()));
+    }
+    let padding = bytes.iter().rev().take(2).take_while(|&&c| c == b'=').count();
+    let decoded_len = bytes.len() / 4 * 3 - padding;
+    if decoded_len != out.len() {
+        return Err(Error::InvalidLength(out.len(), decoded_len));
+    }
+
+    let data_len = bytes.len() - padding;
+    for (i, quad) in bytes.chunks(4).enumerate() {
+        let mut n = 0u32;
+        for (j, &c) in quad.iter().enumerate() {
+            let pos = 4 * i + j;
+            let value = if pos < data_len {
+                decode_char(c).ok_or_else(|| invalid_char(s, pos))?
+            } else {
+                0
+            };
+            n |= value << (18 - 6 * j);
+        }
+        for (j, byte) in out[3 * i..].iter_mut().take(3).enumerate() {
+            *byte = (n >> (16 - 8 * j)) as u8;
+        }
+    }
+
+    // The last character before the padding may only carry decoded bits
+    if padding > 0 {
+        let last = decode_char(bytes[data_len - 1]).expect("checked above");
+        if last & ((1 << (2 * padding)) - 1) != 0 {
+            return Err(invalid_char(s, data_len - 1));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {hash160, hmac, ripemd160, sha256, sha256d, sha512};
+    use {Error, Hash, HashEngine};
+
+    #[test]
+    fn base64_vectors() {
+        let hash = ripemd160::Hash::hash(b"abc");
+        assert_eq!(hash.to_base64(), "jrII9+BdmHqbBEqOmMawh/FaC/w=");
+        assert_eq!(ripemd160::Hash::from_base64("jrII9+BdmHqbBEqOmMawh/FaC/w="), Ok(hash));
+
+        let hash = sha256::Hash::hash(b"abc");
+        assert_eq!(hash.to_base64(), "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=");
+        assert_eq!(sha256::Hash::from_base64("ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="), Ok(hash));
+
+        let hash = sha512::Hash::hash(b"abc");
+        let b64 = "3a81oZNherrMQXNJriBBMRLm+k6JqX6iCp7u5ktV05ohkpkqJ0/BqDa6PCOj/uu9RU1EI2Q86A4qmslPpUyknw==";
+        assert_eq!(hash.to_base64(), b64);
+        assert_eq!(sha512::Hash::from_base64(b64), Ok(hash));
+
+        let mut engine = hmac::HmacEngine::<sha256::Hash>::new(b"key");
+        engine.input(b"The quick brown fox jumps over the lazy dog");
+        let mac = hmac::Hmac::from_engine(engine);
+        assert_eq!(mac.to_base64(), "97yD9DBThCSxMpjmqm+xQ+9NWaFJRhdZl0edvC0aPNg=");
+        assert_eq!(hmac::Hmac::from_base64("97yD9DBThCSxMpjmqm+xQ+9NWaFJRhdZl0edvC0aPNg="), Ok(mac));
+
+        // Display is still hex
+        assert_eq!(mac.to_string(), "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        for len in 0..100 {
+            let hash = hash160::Hash::hash(&data[..len]);
+            assert_eq!(hash160::Hash::from_base64(&hash.to_base64()), Ok(hash));
+            let hash = sha256::Hash::hash(&data[..len]);
+            assert_eq!(sha256::Hash::from_base64(&hash.to_base64()), Ok(hash));
+            let hash = sha256d::Hash::hash(&data[..len]);
+            assert_eq!(sha256d::Hash::from_base64(&hash.to_base64()), Ok(hash));
+            let hash = sha512::Hash::hash(&data[..len]);
+            assert_eq!(sha512::Hash::from_base64(&hash.to_base64()), Ok(hash));
+        }
+
+        // Raw bytes, not display order
+        let hash = sha256d::Hash::from_inner(sha256::Hash::hash(b"abc").into_inner());
+        assert_eq!(hash.to_base64(), "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=");
+
+        // Every byte value at every position in a chunk
+        for offset in 0..3 {
+            let mut buf = vec![0; 256 + offset];
+            buf[offset..].copy_from_slice(&data);
+            let mut s = String::new();
+            encode_to_fmt(&buf, &mut s).unwrap();
+            assert_eq!(s.len(), encoded_len(buf.len()));
+            let mut out = vec![0; buf.len()];
+            decode_to_slice(&s, &mut out).unwrap();
+            assert_eq!(out, buf);
+        }
+    }
+
+    #[test]
+    fn base64_error() {
+        let b64 = "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=";
+
+        // Wrong decoded length
+        assert_eq!(ripemd160::Hash::from_base64(b64), Err(Error::InvalidLength(20, 32)));
+        assert_eq!(sha512::Hash::from_base64(b64), Err(Error::InvalidLength(64, 32)));
+        assert_eq!(sha256::Hash::from_base64(""), Err(Error::InvalidLength(32, 0)));
+        assert_eq!(sha256::Hash::from_base64(&b64[..40]), Err(Error::InvalidLength(32, 30)));
+
+        // Missing padding is rejected, as is extra padding
+        assert_eq!(sha256::Hash::from_base64(&b64[..43]), Err(Error::InvalidBase64Length(43)));
+        let b64_512 = "3a81oZNherrMQXNJriBBMRLm+k6JqX6iCp7u5ktV05ohkpkqJ0/BqDa6PCOj/uu9RU1EI2Q86A4qmslPpUyknw==";
+        assert_eq!(sha512::Hash::from_base64(&b64_512[..87]), Err(Error::InvalidBase64Length(87)));
+        assert_eq!(sha512::Hash::from_base64(&b64_512[..86]), Err(Error::InvalidBase64Length(86)));
+        assert_eq!(sha256::Hash::from_base64(&format!("{}====", b64)), Err(Error::InvalidLength(32, 34)));
+
+        // Bad characters, including the URL-safe alphabet and misplaced padding
+        assert_eq!(
+            sha256::Hash::from_base64(&b64.replace('+', "-")),
+            Err(Error::InvalidBase64Char('-', 9))
+        );
+        assert_eq!(
+            sha256::Hash::from_base64(&b64.replace('/', "_")),
+            Err(Error::InvalidBase64Char('_', 35))
+        );
+        assert_eq!(
+            sha256::Hash::from_base64(&b64.replacen('u', "=", 1)),
+            Err(Error::InvalidBase64Char('=', 0))
+        );
+        assert_eq!(
+            sha256::Hash::from_base64(&b64.replacen("ng", "é", 1)),
+            Err(Error::InvalidBase64Char('é', 1))
+        );
+
+        // Nonzero unused bits in the last character
+        assert_eq!(
+            sha256::Hash::from_base64(&b64.replace("a0=", "a1=")),
+            Err(Error::InvalidBase64Char('1', 42))
+        );
+        assert_eq!(
+            sha512::Hash::from_base64(&b64_512.replace("nw==", "nx==")),
+            Err(Error::InvalidBase64Char('x', 85))
+        );
+
+        assert_eq!(
+            Error::InvalidBase64Char('-', 9).to_string(),
+            "invalid base64 character '-' at position 9"
+        );
+        assert_eq!(Error::InvalidBase64Length(43).to_string(), "bad base64 string length 43 (not a multiple of 4)");
+    }
+}
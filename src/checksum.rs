@@ -0,0 +1,124 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # Checksums
+//!
+//! A common interface for checksum schemes, so that encodings can carry a
+//! typed checksum rather than a bare byte array.
+
+use core::fmt;
+
+use hex::format_hex;
+use sha256d;
+
+/// A checksum scheme, such as the SHA256d prefix of Base58Check
+pub trait Checksum {
+    /// The checksum value
+    type Output: Copy + Eq + fmt::Debug;
+
+    /// Computes the checksum of `data`
+    fn checksum(data: &[u8]) -> Self::Output;
+
+    /// Checks `data` against an expected checksum
+    fn verify(data: &[u8], expected: &Self::Output) -> bool {
+        Self::checksum(data) == *expected
+    }
+}
+
+/// A four-byte checksum, displayed as hex
+#[derive(Copy, Clone, PartialEq, Eq, Default, PartialOrd, Ord, Hash)]
+pub struct Checksum4(pub [u8; 4]);
+
+impl fmt::Debug for Checksum4 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_hex(&self.0, f)
+    }
+}
+
+impl fmt::Display for Checksum4 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_hex(&self.0, f)
+    }
+}
+
+impl fmt::LowerHex for Checksum4 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_hex(&self.0, f)
+    }
+}
+
+impl From<[u8; 4]> for Checksum4 {
+    fn from(bytes: [u8; 4]) -> Self {
+        Checksum4(bytes)
+    }
+}
+
+impl From<Checksum4> for [u8; 4] {
+    fn from(checksum: Checksum4) -> Self {
+        checksum.0
+    }
+}
+
+impl AsRef<[u8]> for Checksum4 {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// The first four bytes of the SHA256d of the data, as used by Base58Check
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Sha256dPrefix;
+
+impl Checksum for Sha256dPrefix {
+    type Output = Checksum4;
+
+    fn checksum(data: &[u8]) -> Checksum4 {
+        Checksum4(sha256d::checksum(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Checksum, Checksum4, Sha256dPrefix};
+    use sha256d;
+
+    #[test]
+    fn sha256d_prefix() {
+        // Version byte and HASH160 of address 1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH
+        let payload = [
+            0x00, 0x75, 0x1e, 0x76, 0xe8, 0x19, 0x91, 0x96, 0xd4, 0x54, 0x94,
+            0x1c, 0x45, 0xd1, 0xb3, 0xa3, 0x23, 0xf1, 0x43, 0x3b, 0xd6,
+        ];
+        let checksum = Sha256dPrefix::checksum(&payload);
+        assert_eq!(checksum, Checksum4([0x51, 0x0d, 0x16, 0x34]));
+        assert_eq!(checksum, Checksum4::from(sha256d::checksum(&payload)));
+        assert_eq!(<[u8; 4]>::from(checksum), [0x51, 0x0d, 0x16, 0x34]);
+        assert_eq!(checksum.as_ref(), &[0x51, 0x0d, 0x16, 0x34][..]);
+
+        assert!(Sha256dPrefix::verify(&payload, &checksum));
+        assert!(!Sha256dPrefix::verify(&payload[1..], &checksum));
+        assert!(!Sha256dPrefix::verify(&payload, &Checksum4([0x51, 0x0d, 0x16, 0x35])));
+    }
+
+    #[test]
+    fn fmt() {
+        let checksum = Checksum4([0x51, 0x0d, 0x16, 0x34]);
+        assert_eq!(format!("{}", checksum), "510d1634");
+        assert_eq!(format!("{:x}", checksum), "510d1634");
+        assert_eq!(format!("{:?}", checksum), "510d1634");
+        // Like hashes, width pads with zeros and precision truncates
+        assert_eq!(format!("{:10}", checksum), "00510d1634");
+        assert_eq!(format!("{:.4}", checksum), "510d");
+    }
+}
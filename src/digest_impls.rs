@@ -0,0 +1,125 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! `digest` Impls
+//!
+//! impls of the RustCrypto `digest` traits for the hash engines, so that
+//! they can be passed to code generic over `digest::Digest`. The blanket
+//! impl in `digest` provides `Digest` itself from these.
+//!
+//! Engines shared between several hash types (SHA256 is also used by
+//! `sha256d`, `sha256t` and `hash160`) produce the single-round hash.
+
+use digest::{self, consts, Output};
+
+use {sha1, sha256, sha512, sha512_256, ripemd160};
+use Hash;
+use HashEngine;
+
+macro_rules! digest_impl {
+    ($hash:ident, $size:ident) => {
+        impl digest::HashMarker for $hash::HashEngine {}
+
+        impl digest::OutputSizeUser for $hash::HashEngine {
+            type OutputSize = consts::$size;
+        }
+
+        impl digest::Update for $hash::HashEngine {
+            fn update(&mut self, data: &[u8]) {
+                self.input(data);
+            }
+        }
+
+        impl digest::FixedOutput for $hash::HashEngine {
+            fn finalize_into(self, out: &mut Output<Self>) {
+                out.copy_from_slice(&$hash::Hash::from_engine(self)[..]);
+            }
+        }
+
+        impl digest::Reset for $hash::HashEngine {
+            fn reset(&mut self) {
+                *self = Default::default();
+            }
+        }
+
+        impl digest::FixedOutputReset for $hash::HashEngine {
+            fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+                let engine = ::core::mem::take(self);
+                digest::FixedOutput::finalize_into(engine, out);
+            }
+        }
+    }
+}
+
+digest_impl!(sha1, U20);
+digest_impl!(sha256, U32);
+digest_impl!(sha512, U64);
+digest_impl!(sha512_256, U32);
+digest_impl!(ripemd160, U20);
+
+#[cfg(test)]
+mod tests {
+    use digest::{Digest, FixedOutputReset, Update};
+
+    use {sha1, sha256, sha512, sha512_256, ripemd160};
+    use Hash;
+
+    /// Hashes `data` knowing only that `D` is a RustCrypto digest
+    fn digest<D: Digest>(data: &[u8]) -> Vec<u8> {
+        let mut d = D::new();
+        d.update(data);
+        d.finalize().to_vec()
+    }
+
+    /// Inputs of various lengths, crossing the 64- and 128-byte block sizes
+    fn inputs() -> Vec<Vec<u8>> {
+        vec![
+            vec![],
+            b"abc".to_vec(),
+            b"The quick brown fox jumps over the lazy dog".to_vec(),
+            (0..64u8).collect(),
+            (0..200u8).collect(),
+            vec![0x5a; 1000],
+        ]
+    }
+
+    macro_rules! digest_test {
+        ($name:ident, $hash:ident, $len:expr) => {
+            #[test]
+            fn $name() {
+                assert_eq!(<$hash::HashEngine as Digest>::output_size(), $len);
+                for data in inputs() {
+                    let expected = $hash::Hash::hash(&data);
+                    assert_eq!(digest::<$hash::HashEngine>(&data), &expected[..]);
+                    assert_eq!(&<$hash::HashEngine as Digest>::digest(&data)[..], &expected[..]);
+
+                    // Split input, and reuse of a reset engine
+                    let mut engine = $hash::HashEngine::default();
+                    for chunk in data.chunks(7) {
+                        Update::update(&mut engine, chunk);
+                    }
+                    assert_eq!(&engine.finalize_fixed_reset()[..], &expected[..]);
+                    Update::update(&mut engine, &data);
+                    assert_eq!(&Digest::finalize(engine)[..], &expected[..]);
+                }
+            }
+        }
+    }
+
+    digest_test!(sha1, sha1, 20);
+    digest_test!(sha256, sha256, 32);
+    digest_test!(sha512, sha512, 64);
+    digest_test!(sha512_256, sha512_256, 32);
+    digest_test!(ripemd160, ripemd160, 20);
+}
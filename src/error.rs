@@ -19,15 +19,31 @@ use core::fmt;
 
 /// [bitcoin_hashes] error.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
     /// Tried to create a fixed-length hash from a slice with the wrong size (expected, got).
     InvalidLength(usize, usize),
+    /// Tried to hash a blob which is not a serialized public key (prefix byte, length).
+    InvalidPublicKey(u8, usize),
+    /// Character which is not standard base64 at the given byte offset of a base64 string.
+    InvalidBase64Char(char, usize),
+    /// Base64 string whose length is not a multiple of 4, e.g. because padding is missing.
+    InvalidBase64Length(usize),
+    /// Character which is not a hex digit at the given byte offset of a hex string.
+    InvalidHexChar(char, usize),
+    /// Hex string of odd length.
+    InvalidHexLength(usize),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::InvalidLength(ell, ell2) => write!(f, "bad slice length {} (expected {})", ell2, ell),
+            Error::InvalidPublicKey(prefix, len) => write!(f, "bad public key encoding: prefix 0x{:02x}, length {}", prefix, len),
+            Error::InvalidBase64Char(c, pos) => write!(f, "invalid base64 character {:?} at position {}", c, pos),
+            Error::InvalidBase64Length(len) => write!(f, "bad base64 string length {} (not a multiple of 4)", len),
+            Error::InvalidHexChar(c, pos) => write!(f, "invalid hex character {:?} at position {}", c, pos),
+            Error::InvalidHexLength(len) => write!(f, "odd hex string length {}", len),
         }
     }
 }
@@ -18,12 +18,15 @@
 // contents here as CC0.
 
 //! # HASH160 (SHA256 then RIPEMD160)
-
-use core::str;
+//!
+//! The engine for this hash is a plain SHA256 engine: only the inner SHA256
+//! is streamed. RIPEMD160 is applied once, to the 32-byte SHA256 output,
+//! when `from_engine` is called.
 
 use sha256;
 use ripemd160;
 use Hash as HashTrait;
+use HashEngine as EngineTrait;
 use Error;
 
 /// Output of the Bitcoin HASH160 hash function
@@ -35,12 +38,68 @@ hex_fmt_impl!(Display, Hash);
 hex_fmt_impl!(LowerHex, Hash);
 index_impl!(Hash);
 serde_impl!(Hash, 20);
+schemars_impl!(Hash, 20);
 borrow_slice_impl!(Hash);
+as_ref_array_impl!(Hash, 20);
+bytes_eq_impl!(Hash, 20);
+from_array_impl!(Hash, 20);
+arbitrary_impl!(Hash, 20);
+quickcheck_impl!(Hash, 20);
+borsh_impl!(Hash, 20);
+defmt_impl!(Hash);
+rand_impl!(Hash, 20);
+bitxor_impl!(Hash, 20);
+try_from_impl!(Hash);
+hex_from_str_impl!(Hash);
+subtle_impl!(Hash);
+all_bytes_impl!(Hash, 20);
+const_array_impl!(Hash, 20);
 
-impl str::FromStr for Hash {
-    type Err = ::hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        ::hex::FromHex::from_hex(s)
+impl Hash {
+    /// Computes the HASH160 of a serialized public key, checking that it
+    /// looks like one: 33 bytes with a 0x02 or 0x03 prefix (compressed) or
+    /// 65 bytes with a 0x04 prefix (uncompressed).
+    ///
+    /// Only the encoding is checked; the key is not validated as a curve point.
+    pub fn from_pubkey(pk: &[u8]) -> Result<Hash, Error> {
+        let prefix = pk.first().cloned().unwrap_or(0);
+        match (prefix, pk.len()) {
+            (0x02, 33) | (0x03, 33) | (0x04, 65) => Ok(<Hash as HashTrait>::hash(pk)),
+            (prefix, len) => Err(Error::InvalidPublicKey(prefix, len)),
+        }
+    }
+
+    /// Hashes many inputs, equivalent to calling `hash` on each of them.
+    ///
+    /// The whole SHA256 blocks of the prefix common to all inputs are hashed
+    /// only once, and each input then continues from a copy of that midstate.
+    /// Inputs shorter than a block, like serialized public keys, share no
+    /// work this way but still avoid setting up fresh engines.
+    #[cfg(any(test, feature = "std"))]
+    pub fn hash_batch(inputs: &[&[u8]]) -> Vec<Hash> {
+        let common = match inputs.split_first() {
+            Some((first, rest)) => rest.iter().fold(first.len(), |len, input| {
+                first[..len].iter().zip(input.iter()).take_while(|&(a, b)| a == b).count()
+            }),
+            None => 0,
+        };
+        let shared = common - common % <sha256::HashEngine as EngineTrait>::BLOCK_SIZE;
+
+        let mut prefix_engine = sha256::Hash::engine();
+        if let Some(first) = inputs.first() {
+            prefix_engine.input(&first[..shared]);
+        }
+        let ripemd_engine = ripemd160::Hash::engine();
+
+        inputs.iter().map(|input| {
+            let mut engine = prefix_engine.clone();
+            engine.input(&input[shared..]);
+            let sha2 = sha256::Hash::from_engine(engine);
+
+            let mut engine = ripemd_engine.clone();
+            engine.input(&sha2[..]);
+            Hash(ripemd160::Hash::from_engine(engine).into_inner())
+        }).collect()
     }
 }
 
@@ -48,17 +107,17 @@ impl HashTrait for Hash {
     type Engine = sha256::HashEngine;
     type Inner = [u8; 20];
 
+    /// Returns a SHA256 engine; see the module documentation.
     fn engine() -> sha256::HashEngine {
         sha256::Hash::engine()
     }
 
     fn from_engine(e: sha256::HashEngine) -> Hash {
+        // Both engines and the intermediate digest live on the stack
         let sha2 = sha256::Hash::from_engine(e);
-        let rmd = ripemd160::Hash::hash(&sha2[..]);
-
-        let mut ret = [0; 20];
-        ret.copy_from_slice(&rmd[..]);
-        Hash(ret)
+        let mut engine = ripemd160::Hash::engine();
+        engine.input(&sha2[..]);
+        Hash(ripemd160::Hash::from_engine(engine).into_inner())
     }
 
     const LEN: usize = 20;
@@ -85,10 +144,12 @@ impl HashTrait for Hash {
 #[cfg(test)]
 mod tests {
     use hash160;
+    #[cfg(feature = "hex")]
     use hex::{FromHex, ToHex};
     use Hash;
     use HashEngine;
 
+    #[cfg(feature = "hex")]
     #[derive(Clone)]
     struct Test {
         input: Vec<u8>,
@@ -96,6 +157,7 @@ mod tests {
         output_str: &'static str,
     }
 
+    #[cfg(feature = "hex")]
     #[test]
     fn test() {
         let tests = vec![
@@ -138,6 +200,112 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_engine_no_alloc() {
+        use {ripemd160, sha256};
+
+        // A compressed public key
+        let mut pubkey = [0x11; 33];
+        pubkey[0] = 0x02;
+        let expected = ripemd160::Hash::hash(&sha256::Hash::hash(&pubkey)[..]);
+        let _ = hash160::Hash::hash(&pubkey); // may allocate detecting CPU features
+
+        let before = ::tests::allocations();
+        let mut engine = hash160::Hash::engine();
+        engine.input(&pubkey);
+        let hash = hash160::Hash::from_engine(engine);
+        assert_eq!(::tests::allocations(), before);
+        assert_eq!(hash.into_inner(), expected.into_inner());
+    }
+
+    #[test]
+    fn eq_array() {
+        let hash = hash160::Hash::hash(&[]);
+        assert_eq!([
+            0xb4, 0x72, 0xa2, 0x66, 0xd0, 0xbd, 0x89, 0xc1, 0x37, 0x06,
+            0xa4, 0x13, 0x2c, 0xcf, 0xb1, 0x6f, 0x7c, 0x3b, 0x9f, 0xcb,
+        ], hash);
+        assert_eq!(&hash.into_inner()[..], hash);
+        assert!([0; 20] != hash);
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn from_pubkey() {
+        use Error;
+
+        // Generator point G
+        let compressed = Vec::<u8>::from_hex(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        ).unwrap();
+        let uncompressed = Vec::<u8>::from_hex(
+            "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+             483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"
+        ).unwrap();
+
+        assert_eq!(
+            hash160::Hash::from_pubkey(&compressed).unwrap().to_hex(),
+            "751e76e8199196d454941c45d1b3a323f1433bd6"
+        );
+        assert_eq!(
+            hash160::Hash::from_pubkey(&uncompressed).unwrap().to_hex(),
+            "91b24bf9f5288532960ac687abb035127b1d28a5"
+        );
+
+        assert_eq!(hash160::Hash::from_pubkey(&compressed[..32]), Err(Error::InvalidPublicKey(0x02, 32)));
+        assert_eq!(hash160::Hash::from_pubkey(&uncompressed[..33]), Err(Error::InvalidPublicKey(0x04, 33)));
+        assert_eq!(hash160::Hash::from_pubkey(&[]), Err(Error::InvalidPublicKey(0, 0)));
+        let mut bad_prefix = compressed.clone();
+        bad_prefix[0] = 0x04;
+        assert_eq!(hash160::Hash::from_pubkey(&bad_prefix), Err(Error::InvalidPublicKey(0x04, 33)));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hash_batch() {
+        // compressed encodings of G, 2G, 3G and 4G
+        let keys: Vec<Vec<u8>> = [
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+            "02e493dbf1c10d80f3581e4904930b1404cc6c13900ee0758474fa94abe8c4cd13",
+        ].iter().map(|h| Vec::<u8>::from_hex(h).unwrap()).collect();
+        let refs: Vec<&[u8]> = keys.iter().map(|k| &k[..]).collect();
+        let expected: Vec<hash160::Hash> = refs.iter().map(|k| hash160::Hash::hash(k)).collect();
+        assert_eq!(hash160::Hash::hash_batch(&refs), expected);
+        assert!(hash160::Hash::hash_batch(&[]).is_empty());
+
+        // inputs sharing two whole blocks, with one of them ending there
+        let long: Vec<u8> = (0..200).collect();
+        let mut other = long.clone();
+        other[150] ^= 1;
+        let inputs: Vec<&[u8]> = vec![&long, &other, &long[..128], &long[..130]];
+        let expected: Vec<hash160::Hash> = inputs.iter().map(|k| hash160::Hash::hash(k)).collect();
+        assert_eq!(hash160::Hash::hash_batch(&inputs), expected);
+        assert_eq!(hash160::Hash::hash_batch(&inputs[..1]), expected[..1].to_vec());
+    }
+
+    #[test]
+    fn chunked_input() {
+        use {ripemd160, sha256};
+
+        let mut msg = [0u8; 200];
+        for (i, b) in msg.iter_mut().enumerate() {
+            *b = (i * 7) as u8;
+        }
+        let expected = hash160::Hash::hash(&msg);
+        let rmd = ripemd160::Hash::hash(&sha256::Hash::hash(&msg)[..]);
+        assert_eq!(rmd.into_inner(), expected);
+
+        for &chunk_size in &[1, 7, 63, 64, 65] {
+            let mut engine = hash160::Hash::engine();
+            for chunk in msg.chunks(chunk_size) {
+                engine.input(chunk);
+            }
+            assert_eq!(hash160::Hash::from_engine(engine), expected, "chunk size {}", chunk_size);
+        }
+    }
+
     #[cfg(feature="serde")]
     #[test]
     fn ripemd_serde() {
@@ -195,4 +363,24 @@ mod benches {
         bh.bytes = bytes.len() as u64;
     }
 
+    #[bench]
+    pub fn hash160_from_engine(bh: & mut Bencher) {
+        // Finalization only: SHA256 padding, then RIPEMD160 of the digest
+        let mut engine = hash160::Hash::engine();
+        engine.input(&[2u8; 33]);
+        bh.iter( || {
+            hash160::Hash::from_engine(engine.clone())
+        });
+    }
+
+    #[bench]
+    pub fn hash160_33b(bh: & mut Bencher) {
+        // A compressed public key, finalizing both SHA256 and RIPEMD160
+        let bytes = [2u8; 33];
+        bh.iter( || {
+            hash160::Hash::hash(&bytes)
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
 }
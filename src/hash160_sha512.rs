@@ -0,0 +1,192 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # RIPEMD160 of SHA512
+//!
+//! The HASH160 composition with SHA512 as the inner hash, as computed by
+//! some non-Bitcoin tooling. This is *not* Bitcoin's HASH160 (which uses
+//! SHA256, see the `hash160` module) and produces different output.
+//!
+//! As with HASH160, only the inner SHA512 is streamed; RIPEMD160 is applied
+//! once, to the 64-byte SHA512 output, when `from_engine` is called.
+
+use sha512;
+use ripemd160;
+use Hash as HashTrait;
+use Error;
+
+/// Output of the RIPEMD160(SHA512) hash function
+#[derive(Copy, Clone, PartialEq, Eq, Default, PartialOrd, Ord, Hash)]
+pub struct Hash([u8; 20]);
+
+hex_fmt_impl!(Debug, Hash);
+hex_fmt_impl!(Display, Hash);
+hex_fmt_impl!(LowerHex, Hash);
+index_impl!(Hash);
+serde_impl!(Hash, 20);
+schemars_impl!(Hash, 20);
+borrow_slice_impl!(Hash);
+as_ref_array_impl!(Hash, 20);
+bytes_eq_impl!(Hash, 20);
+from_array_impl!(Hash, 20);
+arbitrary_impl!(Hash, 20);
+quickcheck_impl!(Hash, 20);
+borsh_impl!(Hash, 20);
+defmt_impl!(Hash);
+rand_impl!(Hash, 20);
+bitxor_impl!(Hash, 20);
+try_from_impl!(Hash);
+hex_from_str_impl!(Hash);
+subtle_impl!(Hash);
+all_bytes_impl!(Hash, 20);
+const_array_impl!(Hash, 20);
+
+impl HashTrait for Hash {
+    type Engine = sha512::HashEngine;
+    type Inner = [u8; 20];
+
+    /// Returns a SHA512 engine; see the module documentation.
+    fn engine() -> sha512::HashEngine {
+        sha512::Hash::engine()
+    }
+
+    fn from_engine(e: sha512::HashEngine) -> Hash {
+        let sha2 = sha512::Hash::from_engine(e);
+        let rmd = ripemd160::Hash::hash(&sha2[..]);
+
+        let mut ret = [0; 20];
+        ret.copy_from_slice(&rmd[..]);
+        Hash(ret)
+    }
+
+    const LEN: usize = 20;
+
+    fn from_slice(sl: &[u8]) -> Result<Hash, Error> {
+        if sl.len() != 20 {
+            Err(Error::InvalidLength(Self::LEN, sl.len()))
+        } else {
+            let mut ret = [0; 20];
+            ret.copy_from_slice(sl);
+            Ok(Hash(ret))
+        }
+    }
+
+    fn into_inner(self) -> Self::Inner {
+        self.0
+    }
+
+    fn from_inner(inner: Self::Inner) -> Self {
+        Hash(inner)
+    }
+}
+
+#[cfg(all(test, feature = "hex"))]
+mod tests {
+    use {hash160, hash160_sha512};
+    use hex::{FromHex, ToHex};
+    use Hash;
+    use HashEngine;
+
+    #[derive(Clone)]
+    struct Test {
+        input: &'static str,
+        output_str: &'static str,
+    }
+
+    #[test]
+    fn test() {
+        let tests = vec![
+            // Computed independently with Python's hashlib
+            Test {
+                input: "",
+                output_str: "4b3663b78d928f594eaa43376f6c4aebf2c9e719",
+            },
+            Test {
+                input: "abc",
+                output_str: "a1ee188f1a1e322ff95d436c1718d86632d62011",
+            },
+            Test {
+                input: "The quick brown fox jumps over the lazy dog",
+                output_str: "d29eea437cd2754256ffc3fcbdb4d2fb4d70befc",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = hash160_sha512::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, hash160_sha512::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash.to_hex(), &test.output_str);
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = hash160_sha512::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = hash160_sha512::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+
+            // Distinct from Bitcoin's HASH160
+            assert!(hash[..] != hash160::Hash::hash(test.input.as_bytes())[..]);
+        }
+    }
+}
+
+#[cfg(all(test, feature="unstable"))]
+mod benches {
+    use test::Bencher;
+
+    use hash160_sha512;
+    use Hash;
+    use HashEngine;
+
+    #[bench]
+    pub fn hash160_sha512_10(bh: & mut Bencher) {
+        let mut engine = hash160_sha512::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn hash160_sha512_1k(bh: & mut Bencher) {
+        let mut engine = hash160_sha512::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn hash160_sha512_64k(bh: & mut Bencher) {
+        let mut engine = hash160_sha512::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn hash160_sha512_33b(bh: & mut Bencher) {
+        // A compressed public key, finalizing both SHA512 and RIPEMD160
+        let bytes = [2u8; 33];
+        bh.iter( || {
+            hash160_sha512::Hash::hash(&bytes)
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
@@ -0,0 +1,124 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # Hash input
+//!
+//! `HashInput`, a message together with a split of it into chunks, which
+//! implements `arbitrary::Arbitrary` so that fuzz targets can check that
+//! feeding an engine piecewise gives the same hash as hashing in one go.
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use {Hash, HashEngine};
+
+/// A message and a split of it into chunks, possibly empty ones
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HashInput {
+    data: Vec<u8>,
+    /// Lengths of the chunks, summing to `data.len()`
+    chunk_lens: Vec<usize>,
+}
+
+impl HashInput {
+    /// The whole message
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The chunks of the message, in order
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        let data = &self.data[..];
+        self.chunk_lens.iter().scan(0, move |start, len| {
+            let chunk = &data[*start..*start + len];
+            *start += len;
+            Some(chunk)
+        })
+    }
+
+    /// Hashes the whole message with `Hash::hash`
+    pub fn hash_oneshot<H: Hash>(&self) -> H {
+        <H as Hash>::hash(&self.data)
+    }
+
+    /// Hashes the message by feeding an engine one chunk at a time
+    pub fn hash_chunked<H: Hash>(&self) -> H {
+        let mut engine = H::engine();
+        for chunk in self.chunks() {
+            engine.input(chunk);
+        }
+        H::from_engine(engine)
+    }
+}
+
+impl<'a> Arbitrary<'a> for HashInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ::arbitrary::Result<Self> {
+        let data: Vec<u8> = Arbitrary::arbitrary(u)?;
+        let mut chunk_lens = vec![];
+        let mut left = data.len();
+        while left > 0 {
+            // Once the input runs out, the rest is one chunk
+            let len = if u.is_empty() { left } else { u.int_in_range(0..=left)? };
+            chunk_lens.push(len);
+            left -= len;
+        }
+        Ok(HashInput { data, chunk_lens })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use {ripemd160, sha1, sha256, sha256d, sha512, siphash24};
+    use super::HashInput;
+
+    /// Pseudo-random bytes standing in for fuzzer input
+    fn fuzz_bytes(len: usize) -> Vec<u8> {
+        let mut state = 0x2545f491u32;
+        (0..len).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        }).collect()
+    }
+
+    #[test]
+    fn chunked_matches_oneshot() {
+        let bytes = fuzz_bytes(20_000);
+        let mut u = Unstructured::new(&bytes);
+        let mut inputs = 0;
+        while !u.is_empty() {
+            let input = HashInput::arbitrary(&mut u).unwrap();
+            assert_eq!(input.chunks().map(|c| c.len()).sum::<usize>(), input.data().len());
+            assert_eq!(input.chunks().flat_map(|c| c.iter()).cloned().collect::<Vec<_>>(), input.data());
+
+            assert_eq!(input.hash_chunked::<sha1::Hash>(), input.hash_oneshot::<sha1::Hash>());
+            assert_eq!(input.hash_chunked::<sha256::Hash>(), input.hash_oneshot::<sha256::Hash>());
+            assert_eq!(input.hash_chunked::<sha256d::Hash>(), input.hash_oneshot::<sha256d::Hash>());
+            assert_eq!(input.hash_chunked::<sha512::Hash>(), input.hash_oneshot::<sha512::Hash>());
+            assert_eq!(input.hash_chunked::<ripemd160::Hash>(), input.hash_oneshot::<ripemd160::Hash>());
+            assert_eq!(input.hash_chunked::<siphash24::Hash>(), input.hash_oneshot::<siphash24::Hash>());
+            inputs += 1;
+        }
+        assert!(inputs > 1);
+    }
+
+    #[test]
+    fn exhausted_input() {
+        let input = HashInput::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert!(input.data().is_empty());
+        assert_eq!(input.chunks().count(), 0);
+    }
+}
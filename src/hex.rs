@@ -14,39 +14,70 @@
 
 //! # Hex encoding and decoding
 //!
+//! Everything except the `format_hex` helpers, which back the `Debug`,
+//! `Display` and `LowerHex` impls of all hash types, and the `encode_to_fmt`
+//! and `encode_to_io` writers requires the `hex` feature.
+//!
 
 use core::{fmt, str};
-use Hash;
+#[cfg(feature = "hex")] use Hash;
+#[cfg(feature = "defmt")] use defmt;
 
 /// Hex decoding error
+#[cfg(feature = "hex")]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
-    /// non-hexadecimal character
-    InvalidChar(u8),
+    /// non-hexadecimal character `c` at byte offset `pos` of the string
+    InvalidChar {
+        /// the offending character
+        c: char,
+        /// byte offset of the character in the string
+        pos: usize,
+    },
     /// purported hex string had odd length
     OddLengthString(usize),
     /// tried to parse fixed-length hash from a string with the wrong type (expected, got)
     InvalidLength(usize, usize),
 }
 
+#[cfg(feature = "hex")]
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::InvalidChar(ch) => write!(f, "invalid hex character {}", ch),
+            Error::InvalidChar { c, pos } => write!(f, "invalid hex character {:?} at position {}", c, pos),
             Error::OddLengthString(ell) => write!(f, "odd hex string length {}", ell),
             Error::InvalidLength(ell, ell2) => write!(f, "bad hex string length {} (expected {})", ell2, ell),
         }
     }
 }
 
+/// Converts to the crate error, whose `InvalidLength` counts bytes rather
+/// than hex characters
+#[cfg(feature = "hex")]
+impl From<Error> for ::Error {
+    fn from(e: Error) -> ::Error {
+        match e {
+            Error::InvalidChar { c, pos } => ::Error::InvalidHexChar(c, pos),
+            Error::OddLengthString(len) => ::Error::InvalidHexLength(len),
+            Error::InvalidLength(ell, ell2) => ::Error::InvalidLength(ell / 2, ell2 / 2),
+        }
+    }
+}
+
 /// Trait for objects that can be serialized as hex strings
-#[cfg(any(test, feature = "std"))]
+///
+/// Output is always lowercase. Besides hash types (and anything else
+/// implementing `LowerHex`) this is implemented for `[u8]`, which also makes
+/// it available on byte arrays and `Vec<u8>` through auto-deref.
+#[cfg(all(feature = "hex", any(test, feature = "std")))]
 pub trait ToHex {
     /// Hex representation of the object
     fn to_hex(&self) -> String;
 }
 
 /// Trait for objects that can be deserialized from hex strings
+#[cfg(feature = "hex")]
 pub trait FromHex: Sized {
     /// Produce an object from a byte iterator
     fn from_byte_iter<I>(iter: I) -> Result<Self, Error>
@@ -58,9 +89,24 @@ pub trait FromHex: Sized {
     fn from_hex(s: &str) -> Result<Self, Error> {
         Self::from_byte_iter(HexIterator::new(s)?)
     }
+
+    /// Produce an object from a hex string which may start with `0x` or `0X`
+    ///
+    /// Positions in `Error::InvalidChar` are byte offsets into `s`, prefix
+    /// included. A bare prefix is treated as an empty hex string.
+    fn from_hex_prefixed(s: &str) -> Result<Self, Error> {
+        let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"));
+        match digits {
+            Some(digits) => Self::from_hex(digits).map_err(|e| match e {
+                Error::InvalidChar { c, pos } => Error::InvalidChar { c, pos: pos + 2 },
+                e => e,
+            }),
+            None => Self::from_hex(s),
+        }
+    }
 }
 
-#[cfg(any(test, feature = "std"))]
+#[cfg(all(feature = "hex", any(test, feature = "std")))]
 impl<T: fmt::LowerHex> ToHex for T {
     /// Outputs the hash in hexadecimal form
     fn to_hex(&self) -> String {
@@ -68,60 +114,130 @@ impl<T: fmt::LowerHex> ToHex for T {
     }
 }
 
-impl<T: Hash> FromHex for T {
+#[cfg(feature = "hex")]
+impl<T: Hash> FromHex for T where T::Inner: FromHex {
     fn from_byte_iter<I>(iter: I) -> Result<Self, Error>
         where I: Iterator<Item=Result<u8, Error>> +
             ExactSizeIterator +
             DoubleEndedIterator,
     {
-        let inner;
+        let inner = if Self::DISPLAY_BACKWARD {
+            T::Inner::from_byte_iter(iter.rev())?
+        } else {
+            T::Inner::from_byte_iter(iter)?
+        };
+        Ok(Hash::from_inner(inner))
+    }
+
+    fn from_hex(s: &str) -> Result<Self, Error> {
+        let inner = T::Inner::from_hex(s)?;
         if Self::DISPLAY_BACKWARD {
-            inner = T::Inner::from_byte_iter(iter.rev())?;
+            // Decoded front to back, so that a backward hash reports the same
+            // invalid character as a forward one, then reversed
+            let display = T::from_inner(inner);
+            T::Inner::from_byte_iter(display[..].iter().rev().map(|b| Ok(*b))).map(Hash::from_inner)
         } else {
-            inner = T::Inner::from_byte_iter(iter)?;
+            Ok(Hash::from_inner(inner))
         }
-        Ok(Hash::from_inner(inner))
     }
 }
 
 /// Iterator over a hex-encoded string slice which decodes hex and yields bytes.
+#[cfg(feature = "hex")]
 pub struct HexIterator<'a> {
     /// The `Bytes` iterator whose next two bytes will be decoded to yield
     /// the next byte.
     iter: str::Bytes<'a>,
+    /// The string being decoded, for reporting invalid characters
+    s: &'a str,
+    /// Number of bytes of `s` already consumed from the front
+    front: usize,
 }
 
+#[cfg(feature = "hex")]
 impl<'a> HexIterator<'a> {
     /// Constructs a new `HexIterator` from a string slice. If the string is of
-    /// odd length it returns an error.
+    /// odd length it returns an error; a non-hex character is reported by the
+    /// item it is decoded into.
     pub fn new(s: &'a str) -> Result<HexIterator<'a>, Error> {
-        if s.len() % 2 != 0 {
-            Err(Error::OddLengthString(s.len()))
-        } else {
-            Ok(HexIterator { iter: s.bytes() })
+        if s.len() % 2 == 1 {
+            return Err(Error::OddLengthString(s.len()));
         }
+        Ok(HexIterator { iter: s.bytes(), s, front: 0 })
     }
 }
 
-fn chars_to_hex(hi: u8, lo: u8) -> Result<u8, Error> {
-    let hih = (hi as char)
-        .to_digit(16)
-        .ok_or(Error::InvalidChar(hi))?;
-    let loh = (lo as char)
-        .to_digit(16)
-        .ok_or(Error::InvalidChar(lo))?;
+/// Decodes the pair of bytes `hi`, `lo` found at `pos` in `s`
+#[cfg(feature = "hex")]
+fn chars_to_hex(s: &str, pos: usize, hi: u8, lo: u8) -> Result<u8, Error> {
+    let (hi, lo) = (hex_value(hi), hex_value(lo));
+    if hi == INVALID {
+        return Err(invalid_char(s, pos));
+    }
+    if lo == INVALID {
+        return Err(invalid_char(s, pos + 1));
+    }
+    Ok((hi << 4) | lo)
+}
+
+/// Marks bytes which are not hex digits in `hex_value`
+#[cfg(feature = "hex")]
+const INVALID: u8 = 0xff;
 
-    let ret = (hih << 4) + loh;
-    Ok(ret as u8)
+/// Value of every byte as a hex digit, or `INVALID`
+#[cfg(all(feature = "hex", not(feature = "small-hash")))]
+static HEX_DECODE: [u8; 256] = {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < 10 {
+        table[b'0' as usize + i] = i as u8;
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 6 {
+        table[b'a' as usize + i] = 10 + i as u8;
+        table[b'A' as usize + i] = 10 + i as u8;
+        i += 1;
+    }
+    table
+};
+
+/// Value of `c` as a hex digit, or `INVALID`
+#[cfg(all(feature = "hex", not(feature = "small-hash")))]
+fn hex_value(c: u8) -> u8 {
+    HEX_DECODE[c as usize]
 }
 
+/// Value of `c` as a hex digit, or `INVALID`; computed rather than looked up
+/// for the `small-hash` feature
+#[cfg(all(feature = "hex", feature = "small-hash"))]
+fn hex_value(c: u8) -> u8 {
+    let (val, valid) = nibble_ct(c);
+    val | !valid
+}
+
+/// The `InvalidChar` error for the non-hex byte of `s` at `pos`, reported
+/// as the character it is part of
+#[cfg(feature = "hex")]
+fn invalid_char(s: &str, mut pos: usize) -> Error {
+    // Iterating from the back can stop inside a multi-byte character
+    while !s.is_char_boundary(pos) {
+        pos -= 1;
+    }
+    let c = s[pos..].chars().next().expect("pos < s.len()");
+    Error::InvalidChar { c, pos }
+}
+
+#[cfg(feature = "hex")]
 impl<'a> Iterator for HexIterator<'a> {
     type Item = Result<u8, Error>;
 
     fn next(&mut self) -> Option<Result<u8, Error>> {
         let hi = self.iter.next()?;
         let lo = self.iter.next().unwrap();
-        Some(chars_to_hex(hi, lo))
+        let pos = self.front;
+        self.front += 2;
+        Some(chars_to_hex(self.s, pos, hi, lo))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -130,63 +246,281 @@ impl<'a> Iterator for HexIterator<'a> {
     }
 }
 
+#[cfg(feature = "hex")]
 impl<'a> DoubleEndedIterator for HexIterator<'a> {
     fn next_back(&mut self) -> Option<Result<u8, Error>> {
         let lo = self.iter.next_back()?;
         let hi = self.iter.next_back().unwrap();
-        Some(chars_to_hex(hi, lo))
+        let pos = self.front + self.iter.len();
+        Some(chars_to_hex(self.s, pos, hi, lo))
     }
 }
 
+#[cfg(feature = "hex")]
 impl<'a> ExactSizeIterator for HexIterator<'a> {}
 
-/// Output hex into an object implementing `fmt::Write`, which is usually more
-/// efficient than going through a `String` using `ToHex`.
-pub fn format_hex(data: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
-    let prec = f.precision().unwrap_or(2 * data.len());
-    let width = f.width().unwrap_or(2 * data.len());
-    for _ in (2 * data.len())..width {
-        f.write_str("0")?;
+const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
+const HEX_CHARS_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Decodes the hex string `src` into `dst`, returning the number of bytes
+/// written, which is always `dst.len()`.
+///
+/// `src` must be exactly twice as long as `dst`; an odd-length `src` is
+/// reported as `OddLengthString` and any other mismatch as `InvalidLength`,
+/// in both cases before anything is written. Decoding then proceeds from the
+/// start of `src`, so on an `InvalidChar` error every byte of `dst` before the
+/// one containing the offending character has been written and the rest of
+/// `dst` is left untouched.
+#[cfg(feature = "hex")]
+pub fn decode_to_slice(src: &str, dst: &mut [u8]) -> Result<usize, Error> {
+    if src.len() % 2 == 1 {
+        return Err(Error::OddLengthString(src.len()));
     }
-    for ch in data.iter().take(prec / 2) {
-        write!(f, "{:02x}", *ch)?;
+    if src.len() != 2 * dst.len() {
+        return Err(Error::InvalidLength(2 * dst.len(), src.len()));
     }
-    if prec < 2 * data.len() && prec % 2 == 1 {
-        write!(f, "{:x}", data[prec / 2] / 16)?;
+    for (n, (pair, out)) in src.as_bytes().chunks(2).zip(dst.iter_mut()).enumerate() {
+        let hi = hex_value(pair[0]);
+        let lo = hex_value(pair[1]);
+        if hi == INVALID {
+            return Err(invalid_char(src, 2 * n));
+        }
+        if lo == INVALID {
+            return Err(invalid_char(src, 2 * n + 1));
+        }
+        *out = (hi << 4) | lo;
     }
-    Ok(())
+    Ok(dst.len())
+}
+
+/// Decodes one hex digit without branches or table lookups, returning its
+/// value and a mask which is `0xff` if `c` is a hex digit and `0` otherwise
+#[cfg(feature = "hex")]
+fn nibble_ct(c: u8) -> (u8, u8) {
+    let c = c as u32;
+    // `c ^ 48` is below 10 exactly for '0'..='9'; the subtraction then
+    // wraps and shifting leaves all ones in the low byte
+    let num = c ^ 48;
+    let num_mask = (num.wrapping_sub(10) >> 8) as u8;
+    // folding to uppercase, 'A'..='F' map to 10..=15
+    let alpha = (c & !32).wrapping_sub(55);
+    let alpha_mask = ((alpha.wrapping_sub(10) ^ alpha.wrapping_sub(16)) >> 8) as u8;
+    ((num as u8 & num_mask) | (alpha as u8 & alpha_mask), num_mask | alpha_mask)
+}
+
+/// Decodes the hex string `src` into `dst` in constant time, for secret
+/// material such as keys.
+///
+/// Lengths are checked, and reported, exactly as by `decode_to_slice`. The
+/// digits themselves are decoded without table lookups and without
+/// data-dependent branches or early exit, so the time taken depends only on
+/// the length. If any character is invalid, `dst` is zeroed and the first
+/// offending character is reported as `InvalidChar`.
+#[cfg(feature = "hex")]
+pub fn decode_to_slice_ct(src: &str, dst: &mut [u8]) -> Result<usize, Error> {
+    if src.len() % 2 == 1 {
+        return Err(Error::OddLengthString(src.len()));
+    }
+    if src.len() != 2 * dst.len() {
+        return Err(Error::InvalidLength(2 * dst.len(), src.len()));
+    }
+    let mut valid = 0xff;
+    for (pair, out) in src.as_bytes().chunks(2).zip(dst.iter_mut()) {
+        let (hi, hi_valid) = nibble_ct(pair[0]);
+        let (lo, lo_valid) = nibble_ct(pair[1]);
+        valid &= hi_valid & lo_valid;
+        *out = (hi << 4) | lo;
+    }
+    if valid == 0 {
+        for out in dst.iter_mut() {
+            *out = 0;
+        }
+        // The input is rejected anyway; only now is it scanned for the error
+        let pos = src.bytes().position(|c| nibble_ct(c).1 == 0).expect("an invalid digit");
+        return Err(invalid_char(src, pos));
+    }
+    Ok(dst.len())
+}
+
+/// Decodes a fixed-length hash from hex in constant time, using
+/// `decode_to_slice_ct`. Backward-displayed hashes are handled as by
+/// `from_hex`.
+#[cfg(feature = "hex")]
+pub fn from_hex_ct<T: Hash>(s: &str) -> Result<T, Error> where T::Inner: AsMut<[u8]> {
+    let mut buf = T::default().into_inner();
+    let bytes = buf.as_mut();
+    decode_to_slice_ct(s, bytes)?;
+    if T::DISPLAY_BACKWARD {
+        bytes.reverse();
+    }
+    let ret = T::from_slice(bytes).expect("correct length");
+    for byte in bytes.iter_mut() {
+        unsafe { ::core::ptr::write_volatile(byte, 0); }
+    }
+    Ok(ret)
+}
+
+/// Encodes `src` as lowercase hex into `dst`, which must be exactly twice as
+/// long as `src`, and returns the written part of `dst` as a string.
+#[cfg(feature = "hex")]
+pub fn encode_to_slice<'a>(src: &[u8], dst: &'a mut [u8]) -> Result<&'a str, Error> {
+    if dst.len() != 2 * src.len() {
+        return Err(Error::InvalidLength(2 * src.len(), dst.len()));
+    }
+    for (byte, out) in src.iter().zip(dst.chunks_mut(2)) {
+        out[0] = HEX_CHARS[(byte >> 4) as usize];
+        out[1] = HEX_CHARS[(byte & 0x0f) as usize];
+    }
+    Ok(str::from_utf8(dst).expect("hex digits are ASCII"))
+}
+
+/// Writes `src` as lowercase hex into an object implementing `fmt::Write`,
+/// without allocating.
+pub fn encode_to_fmt<W: fmt::Write + ?Sized>(src: &[u8], f: &mut W) -> fmt::Result {
+    encode_iter_to_fmt(src.iter(), HEX_CHARS, f)
+}
+
+/// Encodes bytes a stack buffer at a time, to avoid a `fmt::Write` call
+/// per character.
+fn encode_iter_to_fmt<'a, I, W>(iter: I, chars: &[u8; 16], f: &mut W) -> fmt::Result
+    where I: Iterator<Item = &'a u8>,
+          W: fmt::Write + ?Sized,
+{
+    let mut buf = [0; 64];
+    let mut len = 0;
+    for byte in iter {
+        buf[len] = chars[(byte >> 4) as usize];
+        buf[len + 1] = chars[(byte & 0x0f) as usize];
+        len += 2;
+        if len == buf.len() {
+            f.write_str(str::from_utf8(&buf).expect("hex digits are ASCII"))?;
+            len = 0;
+        }
+    }
+    f.write_str(str::from_utf8(&buf[..len]).expect("hex digits are ASCII"))
+}
+
+/// Writes `src` as lowercase hex into an object implementing `io::Write`,
+/// without allocating.
+#[cfg(feature = "std")]
+pub fn encode_to_io<W: ::std::io::Write + ?Sized>(src: &[u8], w: &mut W) -> ::std::io::Result<()> {
+    encode_iter_to_io(src.iter(), w)
+}
+
+/// Encodes bytes a stack buffer at a time, like `encode_iter_to_fmt` but for
+/// byte-oriented sinks.
+#[cfg(feature = "std")]
+pub(crate) fn encode_iter_to_io<'a, I, W>(iter: I, w: &mut W) -> ::std::io::Result<()>
+    where I: Iterator<Item = &'a u8>,
+          W: ::std::io::Write + ?Sized,
+{
+    let mut buf = [0; 64];
+    let mut len = 0;
+    for byte in iter {
+        buf[len] = HEX_CHARS[(byte >> 4) as usize];
+        buf[len + 1] = HEX_CHARS[(byte & 0x0f) as usize];
+        len += 2;
+        if len == buf.len() {
+            w.write_all(&buf)?;
+            len = 0;
+        }
+    }
+    w.write_all(&buf[..len])
+}
+
+/// Output hex into an object implementing `fmt::Write`, which is usually more
+/// efficient than going through a `String` using `ToHex`.
+pub fn format_hex(data: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
+    format_hex_chars(data, false, HEX_CHARS, f)
 }
 
 /// Output hex in reverse order; used for Sha256dHash whose standard hex encoding
 /// has the bytes reversed.
 pub fn format_hex_reverse(data: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
+    format_hex_chars(data, true, HEX_CHARS, f)
+}
+
+/// Output hex to a `defmt` formatter, one byte at a time, so that the target
+/// only sends the bytes and the host does the formatting.
+#[cfg(feature = "defmt")]
+pub fn format_defmt(data: &[u8], f: defmt::Formatter) {
+    for byte in data {
+        defmt::write!(f, "{=u8:02x}", *byte);
+    }
+}
+
+/// Output hex to a `defmt` formatter in reverse order, like `format_hex_reverse`.
+#[cfg(feature = "defmt")]
+pub fn format_defmt_reverse(data: &[u8], f: defmt::Formatter) {
+    for byte in data.iter().rev() {
+        defmt::write!(f, "{=u8:02x}", *byte);
+    }
+}
+
+/// Common implementation of the `format_hex` functions, honouring the width
+/// (padding with zeros) and precision (truncating) of `f`.
+fn format_hex_chars(data: &[u8], backward: bool, chars: &[u8; 16], f: &mut fmt::Formatter) -> fmt::Result {
     let prec = f.precision().unwrap_or(2 * data.len());
     let width = f.width().unwrap_or(2 * data.len());
     for _ in (2 * data.len())..width {
         f.write_str("0")?;
     }
-    for ch in data.iter().rev().take(prec / 2) {
-        write!(f, "{:02x}", *ch)?;
+    if backward {
+        encode_iter_to_fmt(data.iter().rev().take(prec / 2), chars, f)?;
+    } else {
+        encode_iter_to_fmt(data.iter().take(prec / 2), chars, f)?;
     }
     if prec < 2 * data.len() && prec % 2 == 1 {
-        write!(f, "{:x}", data[data.len() - 1 - prec / 2] / 16)?;
+        let byte = if backward { data[data.len() - 1 - prec / 2] } else { data[prec / 2] };
+        fmt::Write::write_char(f, chars[(byte >> 4) as usize] as char)?;
     }
     Ok(())
 }
 
-#[cfg(any(test, feature = "std"))]
+/// Hex `Display` of a hash in an explicit case, returned by `Hash::lower` and
+/// `Hash::upper`. Like the hash's own formatting traits, it honours
+/// `DISPLAY_BACKWARD` and the width and precision flags.
+#[derive(Copy, Clone, Debug)]
+pub struct DisplayHex<'a> {
+    data: &'a [u8],
+    backward: bool,
+    upper: bool,
+}
+
+impl<'a> DisplayHex<'a> {
+    /// Hex of `data`, uppercase if `upper` and reversed if `backward`
+    pub(crate) fn new(data: &'a [u8], backward: bool, upper: bool) -> DisplayHex<'a> {
+        DisplayHex { data, backward, upper }
+    }
+}
+
+impl<'a> fmt::Display for DisplayHex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chars = if self.upper { HEX_CHARS_UPPER } else { HEX_CHARS };
+        format_hex_chars(self.data, self.backward, chars, f)
+    }
+}
+
+/// Byte slice wrapper formatting through `format_hex`, so that slices and
+/// hash types share a single encoding routine
+#[cfg(all(feature = "hex", any(test, feature = "std")))]
+struct HexSlice<'a>(&'a [u8]);
+
+#[cfg(all(feature = "hex", any(test, feature = "std")))]
+impl<'a> fmt::LowerHex for HexSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_hex(self.0, f)
+    }
+}
+
+#[cfg(all(feature = "hex", any(test, feature = "std")))]
 impl ToHex for [u8] {
     fn to_hex(&self) -> String {
-        use core::fmt::Write;
-        let mut ret = String::with_capacity(2 * self.len());
-        for ch in self {
-            write!(ret, "{:02x}", ch).expect("writing to string");
-        }
-        ret
+        HexSlice(self).to_hex()
     }
 }
 
-#[cfg(any(test, feature = "std"))]
+#[cfg(all(feature = "hex", any(test, feature = "std")))]
 impl FromHex for Vec<u8> {
     fn from_byte_iter<I>(iter: I) -> Result<Self, Error>
         where I: Iterator<Item=Result<u8, Error>> +
@@ -197,47 +531,30 @@ impl FromHex for Vec<u8> {
     }
 }
 
-macro_rules! impl_fromhex_array {
-    ($len:expr) => {
-        impl FromHex for [u8; $len] {
-            fn from_byte_iter<I>(iter: I) -> Result<Self, Error>
-                where I: Iterator<Item=Result<u8, Error>> +
-                    ExactSizeIterator +
-                    DoubleEndedIterator,
-            {
-                if iter.len() == $len {
-                    let mut ret = [0; $len];
-                    for (n, byte) in iter.enumerate() {
-                        ret[n] = byte?;
-                    }
-                    Ok(ret)
-                } else {
-                    Err(Error::InvalidLength(2 * $len, 2 * iter.len()))
-                }
+#[cfg(feature = "hex")]
+impl<const N: usize> FromHex for [u8; N] {
+    fn from_byte_iter<I>(iter: I) -> Result<Self, Error>
+        where I: Iterator<Item=Result<u8, Error>> +
+            ExactSizeIterator +
+            DoubleEndedIterator,
+    {
+        if iter.len() == N {
+            let mut ret = [0; N];
+            for (n, byte) in iter.enumerate() {
+                ret[n] = byte?;
             }
+            Ok(ret)
+        } else {
+            Err(Error::InvalidLength(2 * N, 2 * iter.len()))
         }
     }
-}
 
-impl_fromhex_array!(2);
-impl_fromhex_array!(4);
-impl_fromhex_array!(6);
-impl_fromhex_array!(8);
-impl_fromhex_array!(10);
-impl_fromhex_array!(12);
-impl_fromhex_array!(14);
-impl_fromhex_array!(16);
-impl_fromhex_array!(20);
-impl_fromhex_array!(24);
-impl_fromhex_array!(28);
-impl_fromhex_array!(32);
-impl_fromhex_array!(33);
-impl_fromhex_array!(64);
-impl_fromhex_array!(65);
-impl_fromhex_array!(128);
-impl_fromhex_array!(256);
-impl_fromhex_array!(384);
-impl_fromhex_array!(512);
+    fn from_hex(s: &str) -> Result<Self, Error> {
+        let mut ret = [0; N];
+        decode_to_slice(s, &mut ret)?;
+        Ok(ret)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -245,6 +562,7 @@ mod tests {
 
     use core::fmt;
 
+    #[cfg(feature = "hex")]
     #[test]
     fn hex_roundtrip() {
         let expected = "0123456789abcdef";
@@ -266,6 +584,348 @@ mod tests {
         assert_eq!(ser, expected);
     }
 
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_roundtrip_vec() {
+        // xorshift, to get some arbitrary-looking bytes without a dependency
+        let mut state = 0x2545f491u32;
+        for len in 0..100 {
+            let bytes: Vec<u8> = (0..len).map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            }).collect();
+            let hex = bytes.to_hex();
+            assert_eq!(hex.len(), 2 * len);
+            assert_eq!(Vec::<u8>::from_hex(&hex), Ok(bytes));
+        }
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_iterator() {
+        let mut buf = [0u8; 8];
+        let mut n = 0;
+        for (slot, byte) in buf.iter_mut().zip(HexIterator::new("deadbeef").unwrap()) {
+            *slot = byte.unwrap();
+            n += 1;
+        }
+        assert_eq!(&buf[..n], &[0xde, 0xad, 0xbe, 0xef]);
+
+        let iter = HexIterator::new("00112233").unwrap();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.rev().collect::<Result<Vec<u8>, Error>>(), Ok(vec![0x33, 0x22, 0x11, 0x00]));
+
+        let bad = "0011z233q0";
+        assert_eq!(HexIterator::new(bad).unwrap().collect::<Result<Vec<u8>, Error>>(),
+                   Err(Error::InvalidChar { c: 'z', pos: 4 }));
+        assert_eq!(HexIterator::new(bad).unwrap().rev().collect::<Result<Vec<u8>, Error>>(),
+                   Err(Error::InvalidChar { c: 'q', pos: 8 }));
+        // the back pair ends inside the 'é', which is reported from its start
+        assert_eq!(HexIterator::new("0\u{e9}0").unwrap().next_back(),
+                   Some(Err(Error::InvalidChar { c: '\u{e9}', pos: 1 })));
+        assert_eq!(HexIterator::new("001").err(), Some(Error::OddLengthString(3)));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_iterator_fuzz() {
+        let mut state = 0x9e3779b9u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        let alphabet = b"0123456789abcdefABCDEFxyz";
+        for _ in 0..500 {
+            let len = next() as usize % 40;
+            let s: String = (0..len)
+                .map(|_| alphabet[next() as usize % alphabet.len()] as char)
+                .collect();
+
+            let via_iter = HexIterator::new(&s).and_then(|iter| iter.collect::<Result<Vec<u8>, Error>>());
+            assert_eq!(via_iter, Vec::<u8>::from_hex(&s), "input {:?}", s);
+
+            // independent reference decoder
+            let reference: Option<Vec<u8>> = if s.len() % 2 == 1 {
+                None
+            } else {
+                (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()
+            };
+            assert_eq!(via_iter.ok(), reference, "input {:?}", s);
+        }
+    }
+
+    /// The decoder as it was before `HEX_DECODE`, char by char
+    #[cfg(feature = "hex")]
+    fn reference_decode(s: &str) -> Result<Vec<u8>, Error> {
+        if s.len() % 2 == 1 {
+            return Err(Error::OddLengthString(s.len()));
+        }
+        if let Some((pos, c)) = s.char_indices().find(|&(_, c)| !c.is_ascii_hexdigit()) {
+            return Err(Error::InvalidChar { c, pos });
+        }
+        Ok(s.as_bytes().chunks(2).map(|pair| {
+            let hi = (pair[0] as char).to_digit(16).unwrap();
+            let lo = (pair[1] as char).to_digit(16).unwrap();
+            ((hi << 4) + lo) as u8
+        }).collect())
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_tables() {
+        for byte in 0..=255u8 {
+            let hex = [byte].to_hex();
+            assert_eq!(hex, format!("{:02x}", byte));
+            assert_eq!(Vec::<u8>::from_hex(&hex), Ok(vec![byte]));
+            assert_eq!(Vec::<u8>::from_hex(&hex.to_uppercase()), Ok(vec![byte]));
+
+            let expected = (byte as char).to_digit(16).map(|d| d as u8).unwrap_or(INVALID);
+            assert_eq!(hex_value(byte), expected, "byte {}", byte);
+        }
+
+        let mut state = 0x2545f491u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        let alphabet: Vec<char> = "0123456789abcdefABCDEF0123456789gG /\u{0}\u{ff}«€".chars().collect();
+        for _ in 0..2000 {
+            let len = next() as usize % 20;
+            let s: String = (0..len).map(|_| alphabet[next() as usize % alphabet.len()]).collect();
+
+            let expected = reference_decode(&s);
+            assert_eq!(Vec::<u8>::from_hex(&s), expected, "input {:?}", s);
+
+            let mut buf = vec![0; s.len() / 2];
+            let res = super::decode_to_slice(&s, &mut buf).map(|_| buf);
+            assert_eq!(res, expected, "input {:?}", s);
+        }
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn to_hex_slice() {
+        let bytes = [0xabu8, 0xCD, 0x00, 0xff].to_vec();
+        assert_eq!(bytes.to_hex(), "abcd00ff");
+        assert_eq!(bytes[..].to_hex(), "abcd00ff");
+        assert_eq!(bytes[1..3].to_hex(), "cd00");
+        assert_eq!(Vec::<u8>::new().to_hex(), "");
+        assert_eq!([0u8; 0][..].to_hex(), "");
+
+        // lowercase always, and the same as the hash types produce
+        let all: Vec<u8> = (0..=255).collect();
+        let hex = all.to_hex();
+        assert!(!hex.bytes().any(|c| c.is_ascii_uppercase()));
+        assert_eq!(Vec::<u8>::from_hex(&hex), Ok(all));
+
+        let hash = ::sha256::Hash::hash(&[]);
+        assert_eq!(hash[..].to_hex(), hash.to_hex());
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn encode_to_slice() {
+        let mut buf = [0u8; 8];
+        assert_eq!(super::encode_to_slice(&[0xde, 0xad, 0xbe, 0xef], &mut buf), Ok("deadbeef"));
+        assert_eq!(super::encode_to_slice(&[], &mut []), Ok(""));
+        assert_eq!(super::encode_to_slice(&[1, 2, 3, 4, 5], &mut buf), Err(Error::InvalidLength(10, 8)));
+        assert_eq!(super::encode_to_slice(&[1, 2, 3], &mut buf), Err(Error::InvalidLength(6, 8)));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn decode_to_slice() {
+        let mut buf = [0u8; 4];
+        assert_eq!(super::decode_to_slice("deadBEEF", &mut buf), Ok(4));
+        assert_eq!(buf, [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(super::decode_to_slice("", &mut []), Ok(0));
+
+        // length mismatches are caught before anything is written
+        let mut buf = [0u8; 4];
+        assert_eq!(super::decode_to_slice("0102030405", &mut buf), Err(Error::InvalidLength(8, 10)));
+        assert_eq!(super::decode_to_slice("010203", &mut buf), Err(Error::InvalidLength(8, 6)));
+        assert_eq!(super::decode_to_slice("0102030", &mut buf), Err(Error::OddLengthString(7)));
+        assert_eq!(buf, [0; 4]);
+
+        // an invalid character leaves the bytes before it decoded
+        let mut buf = [0xffu8; 4];
+        assert_eq!(
+            super::decode_to_slice("01020g04", &mut buf),
+            Err(Error::InvalidChar { c: 'g', pos: 5 })
+        );
+        assert_eq!(buf, [0x01, 0x02, 0xff, 0xff]);
+        assert_eq!(
+            super::decode_to_slice("01«0304", &mut buf),
+            Err(Error::InvalidChar { c: '«', pos: 2 })
+        );
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn decode_to_slice_ct() {
+        // every byte value agrees with the table-driven decoder
+        for c in 0..=255u8 {
+            let (val, valid) = super::nibble_ct(c);
+            if hex_value(c) == INVALID {
+                assert_eq!(valid, 0, "{}", c);
+            } else {
+                assert_eq!((val, valid), (hex_value(c), 0xff), "{}", c);
+            }
+        }
+
+        let mut buf = [0u8; 4];
+        assert_eq!(super::decode_to_slice_ct("deadBEEF", &mut buf), Ok(4));
+        assert_eq!(buf, [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(super::decode_to_slice_ct("", &mut []), Ok(0));
+        assert_eq!(super::decode_to_slice_ct("0102030", &mut buf), Err(Error::OddLengthString(7)));
+        assert_eq!(super::decode_to_slice_ct("010203", &mut buf), Err(Error::InvalidLength(8, 6)));
+
+        // invalid input is fully rejected
+        assert_eq!(
+            super::decode_to_slice_ct("01020g0h", &mut buf),
+            Err(Error::InvalidChar { c: 'g', pos: 5 })
+        );
+        assert_eq!(buf, [0; 4]);
+        assert_eq!(
+            super::decode_to_slice_ct("01«0304", &mut buf),
+            Err(Error::InvalidChar { c: '«', pos: 2 })
+        );
+        assert_eq!(
+            super::decode_to_slice_ct("0102030:", &mut buf),
+            Err(Error::InvalidChar { c: ':', pos: 7 })
+        );
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn from_hex_ct() {
+        use {hash160, sha256, sha256d, sha512, Hash};
+
+        let key = sha512::Hash::hash(b"key");
+        let hex = format!("{:x}", key);
+        assert_eq!(super::from_hex_ct::<sha512::Hash>(&hex), Ok(key));
+        assert_eq!(super::from_hex_ct::<sha512::Hash>(&hex.to_uppercase()), Ok(key));
+        for len in &[0, 20, 32] {
+            let s = &hex[..2 * len];
+            assert_eq!(super::from_hex_ct::<hash160::Hash>(s), hash160::Hash::from_hex(s));
+            assert_eq!(super::from_hex_ct::<sha256::Hash>(s), sha256::Hash::from_hex(s));
+            assert_eq!(super::from_hex_ct::<sha256d::Hash>(s), sha256d::Hash::from_hex(s));
+        }
+
+        let s = "56944c5d3f98413ef45cf54545538103cc9f298e0575820ad3591376e2e0f65d";
+        assert_eq!(super::from_hex_ct::<sha256d::Hash>(s), sha256d::Hash::from_hex(s));
+        let s = "56944c5d3f98413ef45cf54545538103cc9f298e0575820ad3591376e2e0f6x5";
+        assert_eq!(
+            super::from_hex_ct::<sha256d::Hash>(s),
+            Err(Error::InvalidChar { c: 'x', pos: 62 })
+        );
+    }
+
+    /// `fmt::Write` into a fixed buffer, recording how it was written to
+    struct FixedWriter {
+        buf: [u8; 256],
+        len: usize,
+        calls: usize,
+    }
+
+    impl fmt::Write for FixedWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.buf[self.len..self.len + s.len()].copy_from_slice(s.as_bytes());
+            self.len += s.len();
+            if !s.is_empty() {
+                self.calls += 1;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn encode_to_fmt() {
+        use core::fmt::Write;
+        use {sha256, sha256d, sha512, Hash};
+
+        let data: Vec<u8> = (0..100).collect();
+        let mut w = FixedWriter { buf: [0; 256], len: 0, calls: 0 };
+        super::encode_to_fmt(&data, &mut w).unwrap();
+        assert_eq!(w.len, 200);
+        assert!(w.buf[..200].chunks(2).zip(&data).all(|(hex, byte)| {
+            u8::from_str_radix(str::from_utf8(hex).unwrap(), 16) == Ok(*byte)
+        }));
+
+        // Display goes straight to the writer, in a few large pieces
+        let hash = sha512::Hash::hash(&data);
+        let mut w = FixedWriter { buf: [0; 256], len: 0, calls: 0 };
+        write!(w, "{}", hash).unwrap();
+        assert_eq!(w.len, 128);
+        assert!(w.calls <= 3, "{} writes", w.calls);
+        assert_eq!(&w.buf[..128], format!("{:x}", hash).as_bytes());
+
+        let hash = sha256d::Hash::from_inner(sha256::Hash::hash(&data).into_inner());
+        let mut w = FixedWriter { buf: [0; 256], len: 0, calls: 0 };
+        write!(w, "{:.5}", hash).unwrap();
+        assert_eq!(&w.buf[..w.len], &hash.to_string().as_bytes()[..5]);
+    }
+
+    #[test]
+    fn fmt_no_alloc() {
+        use core::fmt::Write;
+        use {hash160, sha256d, Hash};
+
+        // `FixedWriter` cannot grow, so everything below is formatted
+        // without any intermediate `String`
+        let hash = sha256d::Hash::hash(b"no alloc");
+        let expected = hash.to_string();
+        let mut w = FixedWriter { buf: [0; 256], len: 0, calls: 0 };
+        write!(w, "{} {:?} {:.10} {:>70}", hash, hash, hash, hash).unwrap();
+        let mut want = String::new();
+        write!(want, "{} {} {} {:0>70}", expected, expected, &expected[..10], expected).unwrap();
+        assert_eq!(&w.buf[..w.len], want.as_bytes());
+
+        let hash = hash160::Hash::hash(b"no alloc");
+        let expected = hash.to_string();
+        let mut w = FixedWriter { buf: [0; 256], len: 0, calls: 0 };
+        write!(w, "{}", hash).unwrap();
+        assert_eq!(&w.buf[..w.len], expected.as_bytes());
+        assert_eq!(w.calls, 1);
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_array() {
+        assert_eq!(<[u8; 3]>::from_hex("0a0b0c"), Ok([10, 11, 12]));
+        assert_eq!(<[u8; 3]>::from_hex("0a0b"), Err(Error::InvalidLength(6, 4)));
+        assert_eq!(<[u8; 3]>::from_hex("0a0b0c0d"), Err(Error::InvalidLength(6, 8)));
+        assert_eq!(<[u8; 0]>::from_hex(""), Ok([]));
+        assert_eq!([10u8, 11, 12].to_hex(), "0a0b0c");
+        let slice: &[u8] = &[10, 11, 12];
+        assert_eq!(slice.to_hex(), "0a0b0c");
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_matches_hash_types() {
+        use {sha256, sha256d, Hash};
+
+        let hex = "6cfb35868c4465b7c289d7d5641563aa973db6a929655282a7bf95c8257f53ef";
+        let array = <[u8; 32]>::from_hex(hex).unwrap();
+        let hash = sha256::Hash::from_hex(hex).unwrap();
+        assert_eq!(array, hash);
+        assert_eq!(hash.to_hex(), array.to_hex());
+
+        // SHA256d displays backwards, so its hex is that of the reversed bytes
+        let hash = sha256d::Hash::from_inner(array);
+        let mut reversed = array;
+        reversed.reverse();
+        assert_eq!(hash.to_hex(), reversed.to_hex());
+        assert_eq!(sha256d::Hash::from_hex(&reversed.to_hex()), Ok(hash));
+    }
+
     #[test]
     fn hex_truncate() {
         struct HexBytes(Vec<u8>);
@@ -332,6 +992,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "hex")]
     #[test]
     fn hex_error() {
         let oddlen = "0123456789abcdef0";
@@ -353,16 +1014,140 @@ mod tests {
         );
         assert_eq!(
             Vec::<u8>::from_hex(badchar1),
-            Err(Error::InvalidChar(b'Z'))
+            Err(Error::InvalidChar { c: 'Z', pos: 0 })
         );
         assert_eq!(
             Vec::<u8>::from_hex(badchar2),
-            Err(Error::InvalidChar(b'Y'))
+            Err(Error::InvalidChar { c: 'Y', pos: 3 })
         );
         assert_eq!(
             Vec::<u8>::from_hex(badchar3),
-            Err(Error::InvalidChar(194))
+            Err(Error::InvalidChar { c: '«', pos: 0 })
+        );
+
+        // The variants are distinguishable programmatically and in messages
+        match Vec::<u8>::from_hex("abc") {
+            Err(Error::OddLengthString(3)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match Vec::<u8>::from_hex("abcg") {
+            Err(Error::InvalidChar { c: 'g', pos: 3 }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(Error::OddLengthString(3).to_string(), "odd hex string length 3");
+        assert_eq!(Error::InvalidLength(64, 62).to_string(), "bad hex string length 62 (expected 64)");
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_error_position() {
+        use {sha256, sha256d};
+
+        let valid = "6cfb35868c4465b7c289d7d5641563aa973db6a929655282a7bf95c8257f53ef";
+        for &pos in &[0, 31, 63] {
+            let mut bad = valid.to_owned();
+            bad.replace_range(pos..pos + 1, "g");
+            let expected = Error::InvalidChar { c: 'g', pos };
+            assert_eq!(sha256::Hash::from_hex(&bad).unwrap_err(), expected);
+            // reversed display order must not change which error is reported
+            assert_eq!(sha256d::Hash::from_hex(&bad).unwrap_err(), expected);
+        }
+
+        // Length is checked before characters, whichever the display order
+        for bad in &["zz", "zzz", "", "0g"] {
+            assert_eq!(sha256d::Hash::from_hex(bad).unwrap_err(), sha256::Hash::from_hex(bad).unwrap_err());
+        }
+        assert_eq!(sha256d::Hash::from_hex("zz"), Err(Error::InvalidLength(64, 2)));
+        assert_eq!(sha256::Hash::from_hex("zz"), Err(Error::InvalidLength(64, 2)));
+        assert_eq!(sha256d::Hash::from_hex("zzz"), Err(Error::OddLengthString(3)));
+        assert_eq!(sha256::Hash::from_hex("zzz"), Err(Error::OddLengthString(3)));
+
+        let garbage = "x".repeat(64);
+        assert_eq!(sha256d::Hash::from_hex(&garbage), Err(Error::InvalidChar { c: 'x', pos: 0 }));
+        assert_eq!(sha256d::Hash::from_hex(&valid[1..]), Err(Error::OddLengthString(63)));
+
+        assert_eq!(
+            Error::InvalidChar { c: 'g', pos: 31 }.to_string(),
+            "invalid hex character 'g' at position 31"
+        );
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_prefixed() {
+        use {sha256, sha256d};
+
+        let hex = "6cfb35868c4465b7c289d7d5641563aa973db6a929655282a7bf95c8257f53ef";
+        let hash = sha256::Hash::from_hex(hex).unwrap();
+        let hashd = sha256d::Hash::from_hex(hex).unwrap();
+        for input in &[hex.to_owned(), format!("0x{}", hex), format!("0X{}", hex)] {
+            assert_eq!(sha256::Hash::from_hex_prefixed(input), Ok(hash));
+            assert_eq!(sha256d::Hash::from_hex_prefixed(input), Ok(hashd));
+        }
+
+        // Strict parsing is unchanged
+        assert_eq!(
+            sha256::Hash::from_hex(&format!("0x{}", &hex[2..])),
+            Err(Error::InvalidChar { c: 'x', pos: 1 })
+        );
+        assert!(format!("0x{}", hex).parse::<sha256::Hash>().is_err());
+
+        // Positions count the prefix
+        let mut bad = format!("0x{}", hex);
+        bad.replace_range(12..13, "g");
+        assert_eq!(sha256::Hash::from_hex_prefixed(&bad), Err(Error::InvalidChar { c: 'g', pos: 12 }));
+        assert_eq!(sha256d::Hash::from_hex_prefixed(&bad), Err(Error::InvalidChar { c: 'g', pos: 12 }));
+        assert_eq!(Vec::<u8>::from_hex_prefixed("0xabz0"), Err(Error::InvalidChar { c: 'z', pos: 4 }));
+
+        // Only one prefix is stripped, and a bare one is not a hash
+        assert_eq!(sha256::Hash::from_hex_prefixed("0x"), Err(Error::InvalidLength(64, 0)));
+        assert_eq!(sha256d::Hash::from_hex_prefixed("0X"), Err(Error::InvalidLength(64, 0)));
+        assert_eq!(
+            sha256::Hash::from_hex_prefixed(&format!("0x0x{}", &hex[2..])),
+            Err(Error::InvalidChar { c: 'x', pos: 3 })
         );
     }
 }
 
+#[cfg(all(test, feature = "unstable", feature = "hex"))]
+mod benches {
+    use test::{black_box, Bencher};
+
+    use super::{FromHex, ToHex};
+
+    #[bench]
+    fn hex_encode_32b(bh: &mut Bencher) {
+        let data = [0xa5u8; 32];
+        bh.iter(|| {
+            black_box(data.to_hex());
+        });
+        bh.bytes = data.len() as u64;
+    }
+
+    #[bench]
+    fn hex_encode_1k(bh: &mut Bencher) {
+        let data = [0xa5u8; 1024];
+        bh.iter(|| {
+            black_box(data.to_hex());
+        });
+        bh.bytes = data.len() as u64;
+    }
+
+    #[bench]
+    fn hex_decode_32b(bh: &mut Bencher) {
+        let hex = [0xa5u8; 32].to_hex();
+        bh.iter(|| {
+            black_box(<[u8; 32]>::from_hex(&hex).unwrap());
+        });
+        bh.bytes = 32;
+    }
+
+    #[bench]
+    fn hex_decode_1k(bh: &mut Bencher) {
+        let hex = [0xa5u8; 1024].to_hex();
+        bh.iter(|| {
+            black_box(Vec::<u8>::from_hex(&hex).unwrap());
+        });
+        bh.bytes = 1024;
+    }
+}
@@ -0,0 +1,157 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # HKDF (RFC 5869)
+//!
+//! HMAC-based key derivation, parameterized by the underlying hash function.
+//!
+
+use HashEngine as EngineTrait;
+use Hash as HashTrait;
+use {Hmac, HmacEngine};
+
+/// HKDF-Extract: condenses the input keying material `ikm` into a
+/// pseudorandom key, using `salt` as the HMAC key. An empty salt is
+/// equivalent to a salt of `T::LEN` zero bytes.
+pub fn extract<T: HashTrait>(salt: &[u8], ikm: &[u8]) -> Hmac<T> {
+    let mut engine = HmacEngine::<T>::new(salt);
+    engine.input(ikm);
+    Hmac::from_engine(engine)
+}
+
+/// HKDF-Expand: fills `out` with output keying material derived from the
+/// pseudorandom key `prk` and the context string `info`.
+///
+/// Panics if `out` is longer than `255 * T::LEN` bytes.
+pub fn expand<T: HashTrait>(prk: &[u8], info: &[u8], out: &mut [u8]) {
+    expand_parts::<T>(prk, &[info], out)
+}
+
+/// HKDF-Expand-Label, as used by TLS 1.3 (RFC 8446 section 7.1) and
+/// protocols which borrow its key schedule.
+///
+/// This is HKDF-Expand with the `info` field set to the serialization of
+///
+/// ```text
+/// struct {
+///     uint16 length;          // out.len(), big-endian
+///     opaque label<0..255>;   // one length byte, then the label
+///     opaque context<0..255>; // one length byte, then the context
+/// } HkdfLabel;
+/// ```
+///
+/// The label is used verbatim: TLS 1.3 callers must include the `"tls13 "`
+/// prefix themselves.
+///
+/// Panics if `label` or `context` are longer than 255 bytes, or if `out` is
+/// longer than `255 * T::LEN` bytes.
+pub fn expand_label<T: HashTrait>(prk: &[u8], label: &[u8], context: &[u8], out: &mut [u8]) {
+    assert!(label.len() <= 255, "HKDF label too long: {} bytes", label.len());
+    assert!(context.len() <= 255, "HKDF context too long: {} bytes", context.len());
+    assert!(out.len() <= 0xffff, "HKDF output too long: {} bytes", out.len());
+
+    let length = [(out.len() >> 8) as u8, out.len() as u8];
+    expand_parts::<T>(prk, &[
+        &length[..],
+        &[label.len() as u8][..],
+        label,
+        &[context.len() as u8][..],
+        context,
+    ], out)
+}
+
+/// HKDF-Expand where the `info` field is given as the concatenation of
+/// several slices, which spares callers from assembling it in a buffer.
+fn expand_parts<T: HashTrait>(prk: &[u8], info: &[&[u8]], out: &mut [u8]) {
+    assert!(
+        out.len() <= 255 * T::LEN,
+        "HKDF output too long: {} bytes (max {})", out.len(), 255 * T::LEN
+    );
+
+    let keyed = HmacEngine::<T>::new(prk);
+    let mut prev: Option<Hmac<T>> = None;
+    for (n, chunk) in out.chunks_mut(T::LEN).enumerate() {
+        let mut engine = keyed.clone();
+        if let Some(ref prev) = prev {
+            engine.input(&prev[..]);
+        }
+        for part in info {
+            engine.input(part);
+        }
+        engine.input(&[n as u8 + 1]);
+
+        let t = Hmac::<T>::from_engine(engine);
+        let len = chunk.len();
+        chunk.copy_from_slice(&t[..len]);
+        prev = Some(t);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "hex")]
+    use hex::{FromHex, ToHex};
+    use {hkdf, sha256};
+    #[cfg(feature = "hex")]
+    use Hash;
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn rfc5869() {
+        // RFC 5869 Appendix A.1
+        let ikm = [0x0b; 22];
+        let salt = Vec::<u8>::from_hex("000102030405060708090a0b0c").unwrap();
+        let info = Vec::<u8>::from_hex("f0f1f2f3f4f5f6f7f8f9").unwrap();
+
+        let prk = hkdf::extract::<sha256::Hash>(&salt, &ikm);
+        assert_eq!(
+            prk.to_hex(),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+        );
+
+        let mut okm = [0; 42];
+        hkdf::expand::<sha256::Hash>(&prk[..], &info, &mut okm);
+        assert_eq!(
+            okm[..].to_hex(),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn tls13_expand_label() {
+        // RFC 8448 section 3: the early secret (no PSK) and the "derived"
+        // secret computed from it.
+        let early = hkdf::extract::<sha256::Hash>(&[], &[0; 32]);
+        assert_eq!(
+            early.to_hex(),
+            "33ad0a1c607ec03b09e6cd9893680ce210adf300aa1f2660e1b22e10f170f92a"
+        );
+
+        let empty_hash = sha256::Hash::hash(&[]);
+        let mut derived = [0; 32];
+        hkdf::expand_label::<sha256::Hash>(&early[..], b"tls13 derived", &empty_hash[..], &mut derived);
+        assert_eq!(
+            derived[..].to_hex(),
+            "6f2615a108c702c5678f54fc9dbab69716c076189c48250cebeac3576c3611ba"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn expand_too_long() {
+        let mut okm = [0; 255 * 32 + 1];
+        hkdf::expand::<sha256::Hash>(&[0; 32], &[], &mut okm);
+    }
+}
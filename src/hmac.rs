@@ -19,7 +19,7 @@
 
 //! # HMAC support
 
-use core::{borrow, fmt, ops, str};
+use core::{borrow, convert, str};
 #[cfg(feature="serde")]
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
@@ -38,6 +38,29 @@ impl<T: HashTrait + str::FromStr> str::FromStr for Hmac<T> {
     }
 }
 
+impl<'a, T: HashTrait + convert::TryFrom<&'a str>> convert::TryFrom<&'a str> for Hmac<T> {
+    type Error = <T as convert::TryFrom<&'a str>>::Error;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        Ok(Hmac(convert::TryFrom::try_from(s)?))
+    }
+}
+
+impl<T: HashTrait> Hmac<T> {
+    /// Recomputes the HMAC of `msg` under `key` and compares it with `tag`
+    /// in constant time. Use this rather than `==` to check a received tag.
+    pub fn verify(key: &[u8], msg: &[u8], tag: &Hmac<T>) -> bool {
+        let mut engine = HmacEngine::new(key);
+        engine.input(msg);
+        Hmac::verify_engine(engine, tag)
+    }
+
+    /// Finalizes a keyed engine which the message has been streamed into and
+    /// compares the result with `tag` in constant time.
+    pub fn verify_engine(engine: HmacEngine<T>, tag: &Hmac<T>) -> bool {
+        ::cmp::fixed_time_eq(&Hmac::from_engine(engine)[..], &tag[..])
+    }
+}
+
 /// Pair of underlying hash midstates which represent the current state
 /// of an `HmacEngine`
 pub struct HmacMidState<T: HashTrait> {
@@ -74,26 +97,70 @@ impl<T: HashTrait> HmacEngine<T> {
         };
 
         if key.len() > T::Engine::BLOCK_SIZE {
-            let hash = <T as HashTrait>::hash(key);
+            let mut hash = <T as HashTrait>::hash(key);
             for (b_i, b_h) in ipad.iter_mut().zip(&hash[..]) {
                 *b_i ^= *b_h;
             }
             for (b_o, b_h) in opad.iter_mut().zip(&hash[..]) {
                 *b_o ^= *b_h;
             }
+            unsafe { ::core::ptr::write_volatile(&mut hash, T::default()); }
         } else {
-            for (b_i, b_h) in ipad.iter_mut().zip(&key[..]) {
+            for (b_i, b_h) in ipad.iter_mut().zip(key) {
                 *b_i ^= *b_h;
             }
-            for (b_o, b_h) in opad.iter_mut().zip(&key[..]) {
+            for (b_o, b_h) in opad.iter_mut().zip(key) {
                 *b_o ^= *b_h;
             }
         };
 
         EngineTrait::input(&mut ret.iengine, &ipad[..T::Engine::BLOCK_SIZE]);
         EngineTrait::input(&mut ret.oengine, &opad[..T::Engine::BLOCK_SIZE]);
+        // The key can be recovered from either padded block
+        for byte in ipad.iter_mut().chain(opad.iter_mut()) {
+            unsafe { ::core::ptr::write_volatile(byte, 0); }
+        }
+        ret
+    }
+
+    /// Construct a keyed HMAC whose key is the first `BLOCK_SIZE` bytes of
+    /// HKDF-Expand of `prk` with `info`, using the same hash function.
+    ///
+    /// The expanded key, and the padded key blocks `new` derives from it,
+    /// only exist in stack buffers, which are overwritten with zeros before
+    /// returning.
+    pub fn from_prk_expand(prk: &[u8], info: &[u8]) -> HmacEngine<T> {
+        let mut key = [0u8; 128];
+        let key = &mut key[..T::Engine::BLOCK_SIZE];
+        ::hkdf::expand::<T>(prk, info, key);
+        let ret = HmacEngine::new(key);
+        for byte in key.iter_mut() {
+            unsafe { ::core::ptr::write_volatile(byte, 0); }
+        }
         ret
     }
+
+    /// Constructs an HMAC engine from already-keyed inner and outer hash
+    /// engines, e.g. ones saved by `into_engines` after keying once.
+    ///
+    /// `inner` must have absorbed the key XOR ipad block and `outer` the key
+    /// XOR opad block; anything else computes some other function.
+    pub fn with_precomputed(inner: T::Engine, outer: T::Engine) -> HmacEngine<T> {
+        HmacEngine {
+            iengine: inner,
+            oengine: outer,
+        }
+    }
+
+    /// Splits the engine into its inner and outer hash engines, the
+    /// counterpart of `with_precomputed`.
+    ///
+    /// Since engines are `Clone`, a freshly keyed `HmacEngine` can itself be
+    /// kept as the snapshot and cloned for each message, skipping the two
+    /// key-block compressions `new` does every time.
+    pub fn into_engines(self) -> (T::Engine, T::Engine) {
+        (self.iengine, self.oengine)
+    }
 }
 
 impl<T: HashTrait> EngineTrait for HmacEngine<T> {
@@ -113,62 +180,40 @@ impl<T: HashTrait> EngineTrait for HmacEngine<T> {
     }
 }
 
-impl<T: HashTrait> fmt::Debug for Hmac<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&self.0, f)
-    }
-}
-
-impl<T: HashTrait> fmt::Display for Hmac<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(&self.0, f)
-    }
-}
+engine_extend_impl!(HmacEngine, T: HashTrait);
 
-impl<T: HashTrait> fmt::LowerHex for Hmac<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::LowerHex::fmt(&self.0, f)
-    }
-}
+hex_fmt_impl!(Debug, Hmac, T: HashTrait);
+hex_fmt_impl!(Display, Hmac, T: HashTrait);
+hex_fmt_impl!(LowerHex, Hmac, T: HashTrait);
+defmt_impl!(Hmac, T: HashTrait);
+index_impl!(Hmac, T: HashTrait);
 
-impl<T: HashTrait> ops::Index<usize> for Hmac<T> {
-    type Output = u8;
-    fn index(&self, index: usize) -> &u8 {
-        &self.0[index]
+impl<T: HashTrait> borrow::Borrow<[u8]> for Hmac<T> {
+    fn borrow(&self) -> &[u8] {
+        &self[..]
     }
 }
 
-impl<T: HashTrait> ops::Index<ops::Range<usize>> for Hmac<T> {
-    type Output = [u8];
-    fn index(&self, index: ops::Range<usize>) -> &[u8] {
-        &self.0[index]
+impl<T: HashTrait> convert::AsRef<[u8]> for Hmac<T> {
+    fn as_ref(&self) -> &[u8] {
+        &self[..]
     }
 }
 
-impl<T: HashTrait> ops::Index<ops::RangeFrom<usize>> for Hmac<T> {
-    type Output = [u8];
-    fn index(&self, index: ops::RangeFrom<usize>) -> &[u8] {
-        &self.0[index]
-    }
-}
+impl<'a, T: HashTrait> convert::TryFrom<&'a [u8]> for Hmac<T> {
+    type Error = Error;
 
-impl<T: HashTrait> ops::Index<ops::RangeTo<usize>> for Hmac<T> {
-    type Output = [u8];
-    fn index(&self, index: ops::RangeTo<usize>) -> &[u8] {
-        &self.0[index]
+    fn try_from(sl: &'a [u8]) -> Result<Self, Error> {
+        Hmac::from_slice(sl)
     }
 }
 
-impl<T: HashTrait> ops::Index<ops::RangeFull> for Hmac<T> {
-    type Output = [u8];
-    fn index(&self, index: ops::RangeFull) -> &[u8] {
-        &self.0[index]
-    }
-}
+#[cfg(any(test, feature = "std"))]
+impl<T: HashTrait> convert::TryFrom<Vec<u8>> for Hmac<T> {
+    type Error = Error;
 
-impl<T: HashTrait> borrow::Borrow<[u8]> for Hmac<T> {
-    fn borrow(&self) -> &[u8] {
-        &self[..]
+    fn try_from(vec: Vec<u8>) -> Result<Self, Error> {
+        Hmac::from_slice(&vec)
     }
 }
 
@@ -184,6 +229,7 @@ impl<T: HashTrait> HashTrait for Hmac<T> {
     }
 
     const LEN: usize = T::LEN;
+    const DISPLAY_BACKWARD: bool = T::DISPLAY_BACKWARD;
 
     fn from_slice(sl: &[u8]) -> Result<Hmac<T>, Error> {
         T::from_slice(sl).map(Hmac)
@@ -198,6 +244,64 @@ impl<T: HashTrait> HashTrait for Hmac<T> {
     }
 }
 
+#[cfg(feature="subtle")]
+impl<T: HashTrait + ::subtle::ConditionallySelectable> ::subtle::ConditionallySelectable for Hmac<T> {
+    fn conditional_select(a: &Self, b: &Self, choice: ::subtle::Choice) -> Self {
+        Hmac(T::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
+#[cfg(feature="arbitrary")]
+impl<'a, T: HashTrait + ::arbitrary::Arbitrary<'a>> ::arbitrary::Arbitrary<'a> for Hmac<T> {
+    fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+        Ok(Hmac(T::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        T::size_hint(depth)
+    }
+}
+
+#[cfg(feature="quickcheck")]
+impl<T: HashTrait + ::quickcheck::Arbitrary> ::quickcheck::Arbitrary for Hmac<T> {
+    fn arbitrary(g: &mut ::quickcheck::Gen) -> Self {
+        Hmac(T::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.0.shrink().map(Hmac))
+    }
+}
+
+#[cfg(feature="borsh")]
+impl<T: HashTrait + ::borsh::BorshSerialize> ::borsh::BorshSerialize for Hmac<T> {
+    fn serialize<W: ::borsh::io::Write>(&self, writer: &mut W) -> ::borsh::io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+#[cfg(feature="borsh")]
+impl<T: HashTrait + ::borsh::BorshDeserialize> ::borsh::BorshDeserialize for Hmac<T> {
+    fn deserialize_reader<R: ::borsh::io::Read>(reader: &mut R) -> ::borsh::io::Result<Self> {
+        T::deserialize_reader(reader).map(Hmac)
+    }
+}
+
+#[cfg(feature="schemars")]
+impl<T: HashTrait + ::schemars::JsonSchema> ::schemars::JsonSchema for Hmac<T> {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        format!("Hmac<{}>", T::schema_name())
+    }
+
+    fn json_schema(gen: &mut ::schemars::gen::SchemaGenerator) -> ::schemars::schema::Schema {
+        T::json_schema(gen)
+    }
+}
+
 #[cfg(feature="serde")]
 impl<T: HashTrait + Serialize> Serialize for Hmac<T> {
     fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
@@ -216,7 +320,7 @@ impl<'de, T: HashTrait + Deserialize<'de>> Deserialize<'de> for Hmac<T> {
 #[cfg(test)]
 mod tests {
     use sha256;
-    #[cfg(feature="serde")] use sha512;
+    use sha512;
     use {Hash, HashEngine, Hmac, HmacEngine};
 
     #[derive(Clone)]
@@ -341,6 +445,126 @@ mod tests {
         }
     }
 
+    #[test]
+    fn verify() {
+        let key = b"key";
+        let msg = b"The quick brown fox jumps over the lazy dog";
+        let mut engine = HmacEngine::<sha256::Hash>::new(key);
+        engine.input(msg);
+        let tag = Hmac::from_engine(engine);
+
+        assert!(Hmac::verify(key, msg, &tag));
+        assert!(!Hmac::verify(b"kez", msg, &tag));
+
+        let mut inner = tag.into_inner();
+        inner[31] ^= 1;
+        let bad_tag = Hmac::<sha256::Hash>::from_inner(inner);
+        assert!(!Hmac::verify(key, msg, &bad_tag));
+
+        let mut engine = HmacEngine::<sha256::Hash>::new(key);
+        engine.input(&msg[..10]);
+        engine.input(&msg[10..]);
+        assert!(Hmac::verify_engine(engine.clone(), &tag));
+        assert!(!Hmac::verify_engine(engine, &bad_tag));
+    }
+
+    #[cfg(feature="hex")]
+    #[test]
+    fn hex_roundtrip() {
+        use hex::FromHex;
+        use sha256d;
+
+        let mut engine = HmacEngine::<sha256::Hash>::new(b"key");
+        engine.input(b"The quick brown fox jumps over the lazy dog");
+        let tag = Hmac::<sha256::Hash>::from_engine(engine);
+
+        let hex = format!("{}", tag);
+        assert_eq!(hex, "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+        assert_eq!(format!("{:?}", tag), hex);
+        assert_eq!(format!("{:x}", tag), hex);
+        assert_eq!(Hmac::<sha256::Hash>::from_hex(&hex).unwrap(), tag);
+        assert_eq!(hex.parse::<Hmac<sha256::Hash>>().unwrap(), tag);
+        assert_eq!(Hmac::<sha256::Hash>::from_slice(&tag[..]).unwrap(), tag);
+        assert_eq!(tag[..4], [0xf7, 0xbc, 0x83, 0xf4]);
+        assert_eq!(tag[31], 0xd8);
+
+        // Backward-displayed inner hashes round-trip the same way
+        let mut engine = HmacEngine::<sha256d::Hash>::new(b"key");
+        engine.input(b"abc");
+        let tag = Hmac::<sha256d::Hash>::from_engine(engine);
+        let hex = tag.to_string();
+        assert_eq!(hex, tag.0.to_string());
+        assert_eq!(Hmac::<sha256d::Hash>::from_hex(&hex).unwrap(), tag);
+    }
+
+    #[test]
+    fn try_from() {
+        use std::convert::TryFrom;
+        use Error;
+
+        let mac = Hmac::<sha256::Hash>::hash(&[1, 2, 3]);
+        assert_eq!(Hmac::<sha256::Hash>::try_from(&mac[..]), Ok(mac));
+        assert_eq!(Hmac::<sha256::Hash>::try_from(mac[..].to_vec()), Ok(mac));
+        assert_eq!(Hmac::<sha256::Hash>::try_from(&mac[1..]), Err(Error::InvalidLength(32, 31)));
+
+        #[cfg(feature = "hex")]
+        {
+            let hex = mac.to_string();
+            assert_eq!(Hmac::<sha256::Hash>::try_from(&hex[..]), Ok(mac));
+            assert_eq!(
+                Hmac::<sha256::Hash>::try_from(&hex[2..]),
+                Err(Error::InvalidLength(32, 31))
+            );
+        }
+    }
+
+    #[test]
+    fn from_prk_expand() {
+        use hkdf;
+
+        let prk = hkdf::extract::<sha256::Hash>(b"salt", b"input key material");
+        let msg = b"first message";
+
+        let mut engine = HmacEngine::<sha256::Hash>::from_prk_expand(&prk[..], b"info");
+        engine.input(msg);
+        let mut key = [0; 64];
+        hkdf::expand::<sha256::Hash>(&prk[..], b"info", &mut key);
+        let mut manual = HmacEngine::<sha256::Hash>::new(&key);
+        manual.input(msg);
+        assert_eq!(Hmac::from_engine(engine), Hmac::from_engine(manual));
+
+        // SHA512 blocks, and so keys, are 128 bytes
+        let mut engine = HmacEngine::<sha512::Hash>::from_prk_expand(&prk[..], b"info");
+        engine.input(msg);
+        let mut key = [0; 128];
+        hkdf::expand::<sha512::Hash>(&prk[..], b"info", &mut key);
+        let mut manual = HmacEngine::<sha512::Hash>::new(&key);
+        manual.input(msg);
+        assert_eq!(Hmac::from_engine(engine), Hmac::from_engine(manual));
+    }
+
+    #[test]
+    fn precomputed() {
+        let keys: [&[u8]; 3] = [b"", b"noise session key", &[0xaa; 131]];
+        let msgs: [&[u8]; 3] = [b"", b"first message", &[0x11; 200]];
+
+        for key in keys.iter() {
+            let (inner, outer) = HmacEngine::<sha256::Hash>::new(key).into_engines();
+            let keyed = HmacEngine::<sha256::Hash>::with_precomputed(inner, outer);
+
+            for msg in msgs.iter() {
+                let mut engine = keyed.clone();
+                engine.input(msg);
+                let tag = Hmac::from_engine(engine);
+
+                let mut fresh = HmacEngine::<sha256::Hash>::new(key);
+                fresh.input(msg);
+                assert_eq!(tag, Hmac::from_engine(fresh));
+                assert!(Hmac::verify(key, msg, &tag));
+            }
+        }
+    }
+
     #[cfg(feature="serde")]
     #[test]
     fn hmac_sha512_serde() {
@@ -367,6 +591,38 @@ mod tests {
             )],
         );
     }
+
+    #[cfg(feature="serde")]
+    #[test]
+    fn hmac_serde_round_trip() {
+        use {bincode, serde_json};
+
+        fn round_trip<T>(mac: Hmac<T>)
+        where
+            T: Hash + ::serde::Serialize + for<'de> ::serde::Deserialize<'de>,
+        {
+            let json = serde_json::to_string(&mac).unwrap();
+            assert_eq!(json, format!("\"{}\"", mac));
+            assert_eq!(serde_json::from_str::<Hmac<T>>(&json).unwrap(), mac);
+
+            let bin = bincode::serialize(&mac).unwrap();
+            assert_eq!(&bin[..8], &(T::LEN as u64).to_le_bytes());
+            assert_eq!(&bin[8..], &mac[..]);
+            assert_eq!(bincode::deserialize::<Hmac<T>>(&bin).unwrap(), mac);
+        }
+
+        let mut engine = HmacEngine::<sha256::Hash>::new(b"key");
+        engine.input(b"The quick brown fox jumps over the lazy dog");
+        round_trip(Hmac::<sha256::Hash>::from_engine(engine));
+
+        let mut engine = HmacEngine::<sha512::Hash>::new(b"key");
+        engine.input(b"The quick brown fox jumps over the lazy dog");
+        round_trip(Hmac::<sha512::Hash>::from_engine(engine));
+
+        // Wrong length for the inner hash
+        let json = serde_json::to_string(&Hmac::<sha512::Hash>::hash(b"")).unwrap();
+        assert!(serde_json::from_str::<Hmac<sha256::Hash>>(&json).is_err());
+    }
 }
 
 #[cfg(all(test, feature="unstable"))]
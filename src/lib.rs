@@ -38,15 +38,32 @@
 #[cfg(all(test, feature = "unstable"))] extern crate test;
 
 #[cfg(any(test, feature="std"))] pub extern crate core;
-#[cfg(feature="serde")] pub extern crate serde;
+#[cfg(feature="serde")] pub extern crate actual_serde as serde;
+#[cfg(feature="schemars")] pub extern crate actual_schemars as schemars;
+#[cfg(feature="borsh")] pub extern crate borsh;
+#[cfg(feature="subtle")] pub extern crate subtle;
+#[cfg(feature="bytes")] extern crate bytes;
+#[cfg(feature="arbitrary")] pub extern crate arbitrary;
+#[cfg(feature="quickcheck")] pub extern crate actual_quickcheck as quickcheck;
+#[cfg(feature="rand")] pub extern crate rand;
+#[cfg(feature="rayon")] pub extern crate actual_rayon as rayon;
+#[cfg(feature="digest")] pub extern crate digest;
+#[cfg(feature="defmt")] pub extern crate defmt;
+
 #[cfg(all(test,feature="serde"))] extern crate serde_test;
+#[cfg(all(test,feature="serde"))] extern crate serde_json;
+#[cfg(all(test,feature="serde"))] extern crate bincode;
 
 #[macro_use] mod util;
 #[macro_use] mod serde_macros;
 #[cfg(any(test, feature = "std"))] mod std_impls;
+#[cfg(feature = "digest")] mod digest_impls;
 pub mod error;
 pub mod hex;
+#[cfg(feature = "base64")] pub mod base64;
 pub mod hash160;
+pub mod hash160_sha512;
+pub mod hkdf;
 pub mod hmac;
 pub mod ripemd160;
 pub mod sha1;
@@ -55,7 +72,12 @@ pub mod sha256d;
 pub mod sha256t;
 pub mod siphash24;
 pub mod sha512;
+pub mod sha512_256;
 pub mod cmp;
+pub mod checksum;
+#[cfg(feature = "lightning")] pub mod lightning;
+#[cfg(feature = "rayon")] pub mod parallel;
+#[cfg(all(feature = "arbitrary", any(test, feature = "std")))] pub mod hash_input;
 
 use core::{borrow, fmt, hash, ops};
 
@@ -65,6 +87,10 @@ pub use error::Error;
 /// A hashing engine which bytes can be serialized into. It is expected
 /// to implement the `io::Write` trait, but to never return errors under
 /// any conditions.
+///
+/// Engines are cheap to clone, which is how to get the digest of the data
+/// input so far without giving up the engine: finalize a clone with
+/// `Hash::from_engine` and keep feeding the original.
 pub trait HashEngine: Clone + Default {
     /// Byte array representing the internal state of the hash engine
     type MidState;
@@ -77,6 +103,9 @@ pub trait HashEngine: Clone + Default {
     const BLOCK_SIZE: usize;
 
     /// Add data to the hash engine
+    ///
+    /// This is always available, including without `std`; the `io::Write`
+    /// impls on the engines simply forward to it.
     fn input(&mut self, data: &[u8]);
 }
 
@@ -96,7 +125,7 @@ pub trait Hash: Copy + Clone + PartialEq + Eq + Default + PartialOrd + Ord +
     type Engine: HashEngine;
 
     /// The byte array that represents the hash internally
-    type Inner: hex::FromHex;
+    type Inner;
 
     /// Construct a new engine
     fn engine() -> Self::Engine {
@@ -106,19 +135,138 @@ pub trait Hash: Copy + Clone + PartialEq + Eq + Default + PartialOrd + Ord +
     /// Produce a hash from the current state of a given engine
     fn from_engine(e: Self::Engine) -> Self;
 
+    /// Produce a hash from the current state of a given engine, finalizing a
+    /// clone of it so that more data can still be input into the original
+    fn from_engine_ref(e: &Self::Engine) -> Self {
+        Self::from_engine(e.clone())
+    }
+
+    /// Finalizes the engine like `from_engine`, writing the digest into
+    /// `out` rather than returning a hash, e.g. a `[u8; 32]` for SHA256
+    fn finalize_into(e: Self::Engine, out: &mut Self::Inner) {
+        *out = Self::from_engine(e).into_inner();
+    }
+
     /// Length of the hash, in bytes
+    ///
+    /// This is usable in constant expressions for concrete hash types, e.g.
+    /// `[u8; sha256::Hash::LEN]`. Stable Rust does not yet allow array
+    /// lengths that depend on a generic parameter, so `[u8; T::LEN]` is not
+    /// possible inside generic functions.
     const LEN: usize;
 
     /// Copies a byte slice into a hash object
     fn from_slice(sl: &[u8]) -> Result<Self, Error>;
 
+    /// Constructs a hash from a byte array of exactly the right length
+    ///
+    /// Since array lengths cannot depend on `Self::LEN` in generic code, the
+    /// length is a parameter here, restricted to the one for which the hash
+    /// type implements `From<[u8; N]>`, i.e. `N == Self::LEN`. A wrong length
+    /// is therefore a compile error rather than a runtime one.
+    ///
+    /// The hash types of this crate also have an inherent `const fn` of the
+    /// same name, which is what calls on a concrete type resolve to.
+    fn from_byte_array<const N: usize>(arr: [u8; N]) -> Self where Self: From<[u8; N]> {
+        Self::from(arr)
+    }
+
+    /// Copies `Self::LEN` bytes starting at `offset` of a larger buffer into
+    /// a hash object. If fewer bytes are available the error reports how many
+    /// there were, as `Error::InvalidLength(Self::LEN, available)`.
+    fn from_slice_at(buf: &[u8], offset: usize) -> Result<Self, Error> {
+        let rest = buf.get(offset..).unwrap_or(&[]);
+        if rest.len() < Self::LEN {
+            return Err(Error::InvalidLength(Self::LEN, rest.len()));
+        }
+        Self::from_slice(&rest[..Self::LEN])
+    }
+
     /// Hashes some bytes
+    ///
+    /// Anything dereferencing to `[u8]`, such as `Vec<u8>` or `Cow<[u8]>`,
+    /// can be passed by reference without a conversion.
     fn hash(data: &[u8]) -> Self {
         let mut engine = Self::engine();
         engine.input(data);
         Self::from_engine(engine)
     }
 
+    /// Hashes the bytes yielded by `iter`, e.g. data generated on the fly,
+    /// without collecting them first.
+    fn hash_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let mut engine = Self::engine();
+        input_iter(&mut engine, iter);
+        Self::from_engine(engine)
+    }
+
+    /// Hashes `block` repeated `times` times, the same as hashing the
+    /// concatenation but without building it.
+    fn hash_repeated(block: &[u8], times: usize) -> Self {
+        let mut engine = Self::engine();
+        for _ in 0..times {
+            engine.input(block);
+        }
+        Self::from_engine(engine)
+    }
+
+    /// Hashes a slice of `u32`s, each serialized little-endian
+    fn hash_u32_le(words: &[u32]) -> Self {
+        let mut engine = Self::engine();
+        input_words(&mut engine, words, u32::to_le_bytes);
+        Self::from_engine(engine)
+    }
+
+    /// Hashes a slice of `u32`s, each serialized big-endian
+    fn hash_u32_be(words: &[u32]) -> Self {
+        let mut engine = Self::engine();
+        input_words(&mut engine, words, u32::to_be_bytes);
+        Self::from_engine(engine)
+    }
+
+    /// Hashes a slice of `u64`s, each serialized little-endian
+    fn hash_u64_le(words: &[u64]) -> Self {
+        let mut engine = Self::engine();
+        input_words(&mut engine, words, u64::to_le_bytes);
+        Self::from_engine(engine)
+    }
+
+    /// Hashes a slice of `u64`s, each serialized big-endian
+    fn hash_u64_be(words: &[u64]) -> Self {
+        let mut engine = Self::engine();
+        input_words(&mut engine, words, u64::to_be_bytes);
+        Self::from_engine(engine)
+    }
+
+    /// Hashes some bytes, feeding them to the engine `chunk` bytes at a time
+    /// as a streaming reader would. The result is the same as `hash(data)`
+    /// for any chunk size; this exists for benchmarking and tests.
+    ///
+    /// Panics if `chunk` is 0.
+    fn hash_chunked(data: &[u8], chunk: usize) -> Self {
+        let mut engine = Self::engine();
+        for piece in data.chunks(chunk) {
+            engine.input(piece);
+        }
+        Self::from_engine(engine)
+    }
+
+    /// Hashes the remaining contents of a `bytes::Buf`, advancing it to
+    /// the end. Non-contiguous buffers are fed to the engine chunk by chunk.
+    #[cfg(feature = "bytes")]
+    fn hash_buf<B: bytes::Buf>(buf: &mut B) -> Self {
+        let mut engine = Self::engine();
+        while buf.has_remaining() {
+            let len = {
+                let chunk = buf.chunk();
+                engine.input(chunk);
+                chunk.len()
+            };
+            buf.advance(len);
+        }
+        Self::from_engine(engine)
+    }
+
     /// Flag indicating whether user-visible serializations of this hash
     /// should be backward. For some reason Satoshi decided this should be
     /// true for `Sha256dHash`, so here we are.
@@ -129,6 +277,130 @@ pub trait Hash: Copy + Clone + PartialEq + Eq + Default + PartialOrd + Ord +
 
     /// Constructs a hash from the underlying byte array
     fn from_inner(inner: Self::Inner) -> Self;
+
+    /// Converts the hash into a byte array of length `N`, for generic code
+    /// where `N` is not statically known to equal `Self::LEN`.
+    ///
+    /// Fails with `Error::InvalidLength(N, Self::LEN)` unless the lengths match.
+    fn into_array<const N: usize>(self) -> Result<[u8; N], Error> {
+        if N != Self::LEN {
+            return Err(Error::InvalidLength(N, Self::LEN));
+        }
+        let mut ret = [0; N];
+        ret.copy_from_slice(&self[..]);
+        Ok(ret)
+    }
+
+    /// Copies the first `N` bytes of the hash into an array, e.g. the 4-byte
+    /// checksum of Base58Check.
+    ///
+    /// Panics if `N` is greater than `Self::LEN`.
+    fn prefix<const N: usize>(&self) -> [u8; N] {
+        let mut ret = [0; N];
+        ret.copy_from_slice(&self[..N]);
+        ret
+    }
+
+    /// Writes the hex encoding of the hash, the same as `to_hex` gives, into
+    /// an `io::Write` without allocating.
+    #[cfg(feature = "std")]
+    fn write_hex_io<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        if Self::DISPLAY_BACKWARD {
+            hex::encode_iter_to_io(self[..].iter().rev(), w)
+        } else {
+            hex::encode_iter_to_io(self[..].iter(), w)
+        }
+    }
+
+    /// Returns a `Display` wrapper writing the hash as lowercase hex, the
+    /// same as `{:x}`, for use where a format flag is awkward.
+    fn lower(&self) -> hex::DisplayHex<'_> {
+        hex::DisplayHex::new(&self[..], Self::DISPLAY_BACKWARD, false)
+    }
+
+    /// Returns a `Display` wrapper writing the hash as uppercase hex.
+    fn upper(&self) -> hex::DisplayHex<'_> {
+        hex::DisplayHex::new(&self[..], Self::DISPLAY_BACKWARD, true)
+    }
+
+    /// Whether `self` equals `other` with its bytes reversed, e.g. a hash in
+    /// internal byte order against one parsed from reversed display hex by
+    /// some other tool.
+    fn eq_reversed(&self, other: &Self) -> bool {
+        self[..].iter().eq(other[..].iter().rev())
+    }
+
+    /// Whether `hex` is the hex of `self` in either byte order, i.e. its
+    /// display hex or the hex of its bytes reversed. Invalid hex gives
+    /// `false`.
+    ///
+    /// This is meant for test assertions, to tell a byte order mix-up, such
+    /// as a txid string copied from an explorer, apart from a wrong hash:
+    /// `assert!(txid.eq_either_endian(s))` next to `assert_eq!(txid.to_string(), s)`.
+    #[cfg(feature = "hex")]
+    fn eq_either_endian(&self, hex: &str) -> bool
+        where Self::Inner: hex::FromHex
+    {
+        match <Self as hex::FromHex>::from_hex(hex) {
+            Ok(parsed) => *self == parsed || self.eq_reversed(&parsed),
+            Err(_) => false,
+        }
+    }
+}
+
+/// A hash whose output is exactly 32 bytes, e.g. `sha256::Hash`,
+/// `sha256d::Hash`, `sha256t::Hash` or a `hash_newtype!` around one of them.
+///
+/// Consumers needing a 32-byte message, such as a signature library, can
+/// accept `impl Hash32` and take the array infallibly, rather than going
+/// through a byte slice and checking its length at runtime.
+pub trait Hash32: Hash<Inner = [u8; 32]> {
+    /// Returns the 32 bytes of the hash, the same as `into_inner`
+    fn to_byte_array(self) -> [u8; 32] {
+        self.into_inner()
+    }
+}
+
+impl<T: Hash<Inner = [u8; 32]>> Hash32 for T {}
+
+/// Feeds `words` to `engine` serialized by `to_bytes`, a block's worth at a
+/// time rather than one call to `input` per word
+fn input_words<E: HashEngine, W: Copy, const N: usize>(engine: &mut E, words: &[W], to_bytes: fn(W) -> [u8; N]) {
+    let mut buf = [0; 64];
+    for chunk in words.chunks(64 / N) {
+        for (bytes, word) in buf.chunks_mut(N).zip(chunk) {
+            bytes.copy_from_slice(&to_bytes(*word));
+        }
+        engine.input(&buf[..N * chunk.len()]);
+    }
+}
+
+/// Feeds the bytes of `iter` to `engine`, gathered into blocks rather than
+/// calling `input` per byte
+fn input_iter<E: HashEngine, I: IntoIterator<Item = u8>>(engine: &mut E, iter: I) {
+    let mut buf = [0; 64];
+    let mut len = 0;
+    for byte in iter {
+        buf[len] = byte;
+        len += 1;
+        if len == buf.len() {
+            engine.input(&buf);
+            len = 0;
+        }
+    }
+    engine.input(&buf[..len]);
+}
+
+/// Adapter implementing `fmt::Write` on top of a hash engine, so that `write!`
+/// can feed the UTF-8 encoding of formatted data into the engine without an
+/// intermediate `String`.
+pub struct FmtWriter<'a, E: HashEngine + 'a>(pub &'a mut E);
+
+impl<'a, E: HashEngine> fmt::Write for FmtWriter<'a, E> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.input(s.as_bytes());
+        Ok(())
+    }
 }
 
 /// Create a new newtype around a [Hash] type.
@@ -144,7 +416,19 @@ macro_rules! hash_newtype {
         hex_fmt_impl!(LowerHex, $newtype);
         index_impl!($newtype);
         serde_impl!($newtype, $len);
+        schemars_impl!($newtype, $len);
         borrow_slice_impl!($newtype);
+        bytes_eq_impl!($newtype, $len);
+        from_array_impl!($newtype, $len);
+        arbitrary_impl!($newtype, $len);
+        quickcheck_impl!($newtype, $len);
+        borsh_impl!($newtype, $len);
+        defmt_impl!($newtype);
+        rand_impl!($newtype, $len);
+        bitxor_impl!($newtype, $len);
+        try_from_impl!($newtype);
+        hex_from_str_impl!($newtype);
+        subtle_impl!($newtype);
 
         impl $newtype {
             /// Create this type from the inner hash type.
@@ -159,6 +443,12 @@ macro_rules! hash_newtype {
             }
         }
 
+        impl $crate::core::convert::AsRef<[u8; $len]> for $newtype {
+            fn as_ref(&self) -> &[u8; $len] {
+                self.0.as_ref()
+            }
+        }
+
         impl ::std::convert::From<$hash> for $newtype {
             fn from(inner: $hash) -> $newtype {
                 // Due to rust 1.22 we have to use this instead of simple `Self(inner)`
@@ -198,22 +488,424 @@ macro_rules! hash_newtype {
                 self.0.into_inner()
             }
         }
-
-        impl ::std::str::FromStr for $newtype {
-            type Err = <$hash as ::std::str::FromStr>::Err;
-            fn from_str(s: &str) -> ::std::result::Result<$newtype, Self::Err> {
-                s.parse().map($newtype)
-            }
-        }
     };
 }
 
 #[cfg(test)]
-mod test {
-    use Hash;
+mod tests {
+    use {FmtWriter, Hash, HashEngine};
     hash_newtype!(TestNewtype, ::sha256d::Hash, 32, doc="A test newtype");
     hash_newtype!(TestNewtype2, ::sha256d::Hash, 32, doc="A test newtype");
 
+    /// Counts the allocations made by the current thread, so that tests
+    /// running in parallel do not interfere
+    ///
+    /// The `*_no_alloc` tests which use this run in every configuration; if
+    /// they ever get feature-gated, gate this and `allocations` to match.
+    struct CountingAlloc;
+
+    thread_local! {
+        // `const` initializers for thread locals need Rust 1.59
+        #[allow(clippy::missing_const_for_thread_local)]
+        static ALLOCATIONS: ::core::cell::Cell<usize> = ::core::cell::Cell::new(0);
+    }
+
+    unsafe impl ::std::alloc::GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: ::std::alloc::Layout) -> *mut u8 {
+            ALLOCATIONS.with(|n| n.set(n.get() + 1));
+            ::std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: ::std::alloc::Layout) {
+            ::std::alloc::System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOC: CountingAlloc = CountingAlloc;
+
+    /// Number of allocations made so far by the current thread
+    pub(crate) fn allocations() -> usize {
+        ALLOCATIONS.with(|n| n.get())
+    }
+
+    #[test]
+    fn prefix() {
+        // Base58Check checksum of the version byte 0x00 followed by the
+        // HASH160 of the generator point (address 1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH)
+        let payload = [
+            0x00, 0x75, 0x1e, 0x76, 0xe8, 0x19, 0x91, 0x96, 0xd4, 0x54, 0x94,
+            0x1c, 0x45, 0xd1, 0xb3, 0xa3, 0x23, 0xf1, 0x43, 0x3b, 0xd6,
+        ];
+        let checksum: [u8; 4] = ::sha256d::Hash::hash(&payload).prefix();
+        assert_eq!(checksum, [0x51, 0x0d, 0x16, 0x34]);
+
+        let hash = ::sha256::Hash::hash(&[]);
+        assert_eq!(hash.prefix::<32>(), hash.into_inner());
+        assert_eq!(hash.prefix::<0>(), [0u8; 0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn prefix_too_long() {
+        ::ripemd160::Hash::hash(&[]).prefix::<21>();
+    }
+
+    #[test]
+    fn from_byte_array() {
+        fn make<H, const N: usize>(arr: [u8; N]) -> H where H: Hash + From<[u8; N]> {
+            assert_eq!(N, H::LEN);
+            H::from_byte_array(arr)
+        }
+
+        let sha: ::sha256::Hash = make([0xab; 32]);
+        assert_eq!(sha.into_inner(), [0xab; 32]);
+        let h160: ::hash160::Hash = make([0xcd; 20]);
+        assert_eq!(h160.into_inner(), [0xcd; 20]);
+        let newtype: TestNewtype = make([0xef; 32]);
+        assert_eq!(newtype.into_inner(), [0xef; 32]);
+    }
+
+    #[test]
+    fn hash32() {
+        use {hmac, sha256, sha256d, sha256t, sha512_256, Hash32};
+
+        /// A signing library's message type, built without a length check
+        struct Message([u8; 32]);
+
+        impl Message {
+            fn from_hash<H: Hash32>(hash: H) -> Message {
+                Message(hash.to_byte_array())
+            }
+        }
+
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+        struct TestTag;
+
+        impl sha256t::Tag for TestTag {
+            fn engine() -> sha256::HashEngine {
+                sha256::HashEngine::default()
+            }
+        }
+
+        fn check<H: Hash32>(hash: H) {
+            assert_eq!(Message::from_hash(hash).0, hash.into_inner());
+            let arr: [u8; 32] = hash.into_inner();
+            assert_eq!(&arr[..], &hash[..]);
+        }
+
+        check(sha256::Hash::hash(b"message"));
+        check(sha256d::Hash::hash(b"message"));
+        check(sha256t::Hash::<TestTag>::hash(b"message"));
+        check(sha512_256::Hash::hash(b"message"));
+        check(hmac::Hmac::<sha256::Hash>::hash(b"message"));
+        check(TestNewtype::hash(b"message"));
+
+        let hash = sha256d::Hash::hash(b"message");
+        let arr: [u8; 32] = hash.into();
+        assert_eq!(Message::from_hash(hash).0, arr);
+    }
+
+    #[test]
+    fn const_byte_array() {
+        use sha256d;
+
+        const GENESIS: sha256d::Hash = sha256d::Hash::from_byte_array([
+            0x6f, 0xe2, 0x8c, 0x0a, 0xb6, 0xf1, 0xb3, 0x72, 0xc1, 0xa6, 0xa2, 0x46, 0xae, 0x63, 0xf7, 0x4f,
+            0x93, 0x1e, 0x83, 0x65, 0xe1, 0x5a, 0x08, 0x9c, 0x68, 0xd6, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        const GENESIS_BYTES: [u8; sha256d::Hash::LEN] = GENESIS.to_byte_array();
+        const LAST_BYTE: u8 = GENESIS.as_byte_array()[31];
+        const BLOCK_SIZE: usize = <::sha256::HashEngine as HashEngine>::BLOCK_SIZE;
+
+        // The genesis block header
+        let header = [
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x3b, 0xa3, 0xed, 0xfd, 0x7a, 0x7b, 0x12, 0xb2, 0x7a, 0xc7, 0x2c, 0x3e,
+            0x67, 0x76, 0x8f, 0x61, 0x7f, 0xc8, 0x1b, 0xc3, 0x88, 0x8a, 0x51, 0x32, 0x3a, 0x9f, 0xb8, 0xaa,
+            0x4b, 0x1e, 0x5e, 0x4a, 0x29, 0xab, 0x5f, 0x49, 0xff, 0xff, 0x00, 0x1d, 0x1d, 0xac, 0x2b, 0x7c,
+        ];
+        let hash = sha256d::Hash::hash(&header);
+        match hash {
+            GENESIS => {}
+            _ => panic!("not the genesis block"),
+        }
+        assert_eq!(hash.to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+        assert_eq!(GENESIS_BYTES, hash.into_inner());
+        assert_eq!(LAST_BYTE, 0);
+        assert_eq!(BLOCK_SIZE, 64);
+        assert_eq!(sha256d::Hash::from_byte_array(GENESIS_BYTES), GENESIS);
+    }
+
+    #[test]
+    fn into_array() {
+        let hash = ::hash160::Hash::hash(b"into_array");
+        assert_eq!(hash.into_array::<20>(), Ok(hash.into_inner()));
+        assert_eq!(hash.into_array::<32>(), Err(::Error::InvalidLength(32, 20)));
+        assert_eq!(hash.into_array::<0>(), Err(::Error::InvalidLength(0, 20)));
+
+        let newtype = TestNewtype::hash(b"into_array");
+        assert_eq!(newtype.into_array::<32>(), Ok(newtype.into_inner()));
+        assert_eq!(newtype.into_array::<20>(), Err(::Error::InvalidLength(20, 32)));
+    }
+
+    #[cfg(all(feature = "std", feature = "hex"))]
+    #[test]
+    fn write_hex_io() {
+        use hex::ToHex;
+
+        fn check<T: Hash>(hash: T) {
+            let mut out = Vec::new();
+            hash.write_hex_io(&mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), hash.to_hex());
+        }
+        check(::sha256::Hash::hash(b"write_hex_io"));
+        check(::sha256d::Hash::hash(b"write_hex_io"));
+        check(::sha512::Hash::hash(b"write_hex_io"));
+        check(::siphash24::Hash::hash(b"write_hex_io"));
+
+        let mut out = b"prefix ".to_vec();
+        ::hex::encode_to_io(&[0xde, 0xad, 0xbe, 0xef], &mut out).unwrap();
+        assert_eq!(out, b"prefix deadbeef");
+
+        // a sink which fails partway through reports the error
+        let mut buf = [0u8; 10];
+        let mut sink = &mut buf[..];
+        assert!(::sha256::Hash::hash(b"").write_hex_io(&mut sink).is_err());
+    }
+
+    #[test]
+    fn lower_upper() {
+        use {sha256, sha256d};
+
+        let hash = sha256::Hash::hash(b"abc");
+        let hex = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(format!("{}", hash.lower()), hex);
+        assert_eq!(format!("{}", hash.upper()), hex.to_uppercase());
+        assert_eq!(format!("{}", hash.lower()), format!("{:x}", hash));
+
+        // Byte order, width and precision as for the hash itself
+        let hash = sha256d::Hash::hash(b"abc");
+        assert_eq!(format!("{}", hash.lower()), format!("{}", hash));
+        assert_eq!(format!("{}", hash.upper()), format!("{}", hash).to_uppercase());
+        assert_eq!(format!("{:.5}", hash.upper()), format!("{:.5}", hash).to_uppercase());
+        assert_eq!(format!("{:70}", hash.upper()), format!("{:70}", hash).to_uppercase());
+    }
+
+    #[test]
+    fn hash_repeated() {
+        use {hash160, sha256, sha256d, sha512, Hmac};
+
+        let block = b"thirteen byte";
+        assert_eq!(block.len(), 13);
+        let concatenated = block.repeat(1000);
+        assert_eq!(sha256::Hash::hash_repeated(block, 1000), sha256::Hash::hash(&concatenated));
+        assert_eq!(sha256d::Hash::hash_repeated(block, 1000), sha256d::Hash::hash(&concatenated));
+        assert_eq!(hash160::Hash::hash_repeated(block, 1000), hash160::Hash::hash(&concatenated));
+        assert_eq!(sha512::Hash::hash_repeated(block, 1000), sha512::Hash::hash(&concatenated));
+        assert_eq!(Hmac::<sha256::Hash>::hash_repeated(block, 1000), Hmac::<sha256::Hash>::hash(&concatenated));
+
+        assert_eq!(sha256::Hash::hash_repeated(block, 0), sha256::Hash::hash(&[]));
+        assert_eq!(sha256::Hash::hash_repeated(&[], 1000), sha256::Hash::hash(&[]));
+        assert_eq!(sha256::Hash::hash_repeated(block, 1), sha256::Hash::hash(block));
+    }
+
+    #[test]
+    fn hash_iter() {
+        use {hash160, sha256, sha256d, sha512, siphash24, Hmac};
+
+        let mut all = [0u8; 256];
+        for (i, byte) in all.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        assert_eq!(sha256::Hash::hash_iter(0u8..=255), sha256::Hash::hash(&all));
+        assert_eq!(sha256d::Hash::hash_iter(0u8..=255), sha256d::Hash::hash(&all));
+        assert_eq!(hash160::Hash::hash_iter(0u8..=255), hash160::Hash::hash(&all));
+        assert_eq!(sha512::Hash::hash_iter(0u8..=255), sha512::Hash::hash(&all));
+        assert_eq!(siphash24::Hash::hash_iter(0u8..=255), siphash24::Hash::hash(&all));
+        assert_eq!(Hmac::<sha256::Hash>::hash_iter(0u8..=255), Hmac::<sha256::Hash>::hash(&all));
+
+        // Lengths around the buffer size, and lazily generated data
+        for len in [0, 1, 63, 64, 65, 128, 200].iter() {
+            assert_eq!(sha256::Hash::hash_iter(all[..*len].iter().cloned()), sha256::Hash::hash(&all[..*len]));
+        }
+        let squares = (0u32..1000).map(|i| (i * i) as u8);
+        let expected: Vec<u8> = (0u32..1000).map(|i| (i * i) as u8).collect();
+        assert_eq!(sha256::Hash::hash_iter(squares), sha256::Hash::hash(&expected));
+    }
+
+    #[test]
+    fn eq_reversed() {
+        let hash = ::sha256d::Hash::hash(b"eq_reversed");
+        let mut bytes = hash.into_inner();
+        bytes.reverse();
+        let reversed = ::sha256d::Hash::from_inner(bytes);
+
+        assert!(hash.eq_reversed(&reversed));
+        assert!(reversed.eq_reversed(&hash));
+        assert!(!hash.eq_reversed(&hash));
+        assert!(hash != reversed);
+
+        let palindrome = ::hash160::Hash::from_inner([7; 20]);
+        assert!(palindrome.eq_reversed(&palindrome));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn eq_either_endian() {
+        use hex::ToHex;
+
+        // The txid of the Bitcoin genesis block's coinbase, and its bytes as
+        // they appear in the serialized transaction
+        let txid_hex = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let internal_hex = "3ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a";
+        let txid: ::sha256d::Hash = txid_hex.parse().unwrap();
+        assert_eq!(txid[..].to_hex(), internal_hex);
+
+        // The mix-up: the reversed string does not parse to the same hash
+        assert!(txid != internal_hex.parse::<::sha256d::Hash>().unwrap());
+        assert!(txid.eq_either_endian(txid_hex));
+        assert!(txid.eq_either_endian(internal_hex));
+        assert!(txid.eq_either_endian(&txid_hex.to_uppercase()));
+
+        // Forward-displayed types work the same way
+        let hash = ::sha256::Hash::hash(b"eq_either_endian");
+        let mut reversed = hash.into_inner();
+        reversed.reverse();
+        assert!(hash.eq_either_endian(&hash.to_hex()));
+        assert!(hash.eq_either_endian(&reversed.to_hex()));
+
+        assert!(!txid.eq_either_endian(&hash.to_hex()));
+        assert!(!txid.eq_either_endian(&txid_hex[2..]));
+        assert!(!txid.eq_either_endian(""));
+        assert!(!txid.eq_either_endian(&txid_hex.replace('a', "x")));
+    }
+
+    #[test]
+    fn from_slice_at() {
+        let hash = ::sha256::Hash::hash(b"from_slice_at");
+        let mut buf = [0xffu8; 40];
+        buf[5..37].copy_from_slice(&hash[..]);
+
+        assert_eq!(::sha256::Hash::from_slice_at(&buf, 5), Ok(hash));
+        assert_eq!(::sha256::Hash::from_slice_at(&buf[..37], 5), Ok(hash));
+        assert_eq!(::sha256::Hash::from_slice_at(&buf, 9), Err(::Error::InvalidLength(32, 31)));
+        assert_eq!(::sha256::Hash::from_slice_at(&buf, 40), Err(::Error::InvalidLength(32, 0)));
+        assert_eq!(::sha256::Hash::from_slice_at(&buf, usize::MAX), Err(::Error::InvalidLength(32, 0)));
+    }
+
+    /// Feeds `data` to an engine in chunks of pseudo-random size, from single
+    /// bytes up to 200 KiB and both block-aligned and not, and checks the
+    /// result against hashing it in one go.
+    fn random_chunks<T: Hash>(data: &[u8], mut state: u32) {
+        let block = T::Engine::BLOCK_SIZE;
+        let mut engine = T::engine();
+        let mut rest = data;
+        while !rest.is_empty() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let len = match state % 6 {
+                0 => 1 + (state >> 8) as usize % 3,
+                1 => 1 + (state >> 8) as usize % (2 * block),
+                2 => block * (1 + (state >> 8) as usize % 4),
+                3 => block * (1 + (state >> 8) as usize % 64) - 1,
+                4 => 1 + (state >> 8) as usize % (200 * 1024),
+                _ => block * (1 + (state >> 8) as usize % (200 * 1024 / block)),
+            };
+            let len = ::core::cmp::min(len, rest.len());
+            engine.input(&rest[..len]);
+            rest = &rest[len..];
+        }
+        assert_eq!(T::from_engine(engine), <T as Hash>::hash(data));
+    }
+
+    #[test]
+    fn engine_input_chunks() {
+        let data: Vec<u8> = (0..1_000_003u32).map(|i| (i.wrapping_mul(0x9e3779b9) >> 24) as u8).collect();
+        for seed in 1..5 {
+            random_chunks::<::sha1::Hash>(&data, seed);
+            random_chunks::<::sha256::Hash>(&data, seed);
+            random_chunks::<::sha512::Hash>(&data, seed);
+            random_chunks::<::ripemd160::Hash>(&data, seed);
+            // start from an unaligned offset too
+            random_chunks::<::sha256::Hash>(&data[7..], seed);
+            random_chunks::<::ripemd160::Hash>(&data[13..], seed);
+        }
+    }
+
+    #[test]
+    fn hash_words() {
+        assert_eq!(::sha256::Hash::hash_u32_le(&[1, 2]), ::sha256::Hash::hash(&[1, 0, 0, 0, 2, 0, 0, 0]));
+        assert_eq!(::sha256::Hash::hash_u32_be(&[1, 2]), ::sha256::Hash::hash(&[0, 0, 0, 1, 0, 0, 0, 2]));
+        assert_eq!(::sha256::Hash::hash_u64_le(&[]), ::sha256::Hash::hash(&[]));
+
+        // more words than fit in one buffer
+        let words: Vec<u64> = (0..100).map(|i| 0x0102030405060708 * i).collect();
+        let le: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes().to_vec()).collect();
+        let be: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes().to_vec()).collect();
+        assert_eq!(::sha512::Hash::hash_u64_le(&words), ::sha512::Hash::hash(&le));
+        assert_eq!(::sha512::Hash::hash_u64_be(&words), ::sha512::Hash::hash(&be));
+        let words: Vec<u32> = (0..100).map(|i| 0x01020304 * i).collect();
+        let le: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes().to_vec()).collect();
+        assert_eq!(::ripemd160::Hash::hash_u32_le(&words), ::ripemd160::Hash::hash(&le));
+    }
+
+    #[test]
+    fn cow() {
+        use std::borrow::Cow;
+
+        // `&Cow<[u8]>` deref-coerces to `&[u8]`, whether borrowed or owned
+        let data = b"hello world";
+        let borrowed: Cow<[u8]> = Cow::Borrowed(&data[..]);
+        let owned: Cow<[u8]> = Cow::Owned(data.to_vec());
+        let expected = ::sha256::Hash::hash(data);
+        assert_eq!(::sha256::Hash::hash(&borrowed), expected);
+        assert_eq!(::sha256::Hash::hash(&owned), expected);
+        assert!(matches!(borrowed, Cow::Borrowed(_)));
+
+        let borrowed: Cow<[u8]> = Cow::Borrowed(&expected[..]);
+        let owned: Cow<[u8]> = Cow::Owned(expected[..].to_vec());
+        assert_eq!(::sha256::Hash::from_slice(&borrowed), Ok(expected));
+        assert_eq!(::sha256::Hash::from_slice(&owned), Ok(expected));
+    }
+
+    #[test]
+    fn from_engine_ref() {
+        let mut engine = ::sha256d::Hash::engine();
+        engine.input(b"first");
+        let first = ::sha256d::Hash::from_engine_ref(&engine);
+        engine.input(b" second");
+        let second = ::sha256d::Hash::from_engine_ref(&engine);
+
+        assert_eq!(first, ::sha256d::Hash::hash(b"first"));
+        assert_eq!(second, ::sha256d::Hash::hash(b"first second"));
+        assert_eq!(::sha256d::Hash::from_engine(engine), second);
+    }
+
+    #[test]
+    fn hash_chunked() {
+        let data: Vec<u8> = (0..200u8).collect();
+        let expected = ::sha256::Hash::hash(&data);
+        for &chunk in &[1, 7, 64, 200, 1000] {
+            assert_eq!(::sha256::Hash::hash_chunked(&data, chunk), expected);
+            assert_eq!(::ripemd160::Hash::hash_chunked(&data, chunk), ::ripemd160::Hash::hash(&data));
+        }
+    }
+
+    #[test]
+    fn len_const() {
+        const SHA256_LEN: usize = ::sha256::Hash::LEN;
+        static BUF: [u8; ::ripemd160::Hash::LEN + ::sha256::Hash::LEN] = [0; 52];
+
+        let mut buf = [0u8; SHA256_LEN];
+        buf.copy_from_slice(&::sha256::Hash::hash(&BUF)[..]);
+        assert_eq!(::sha256::Hash::from_inner(buf), ::sha256::Hash::hash(&[0; 52]));
+        assert_eq!(<TestNewtype as Hash>::LEN, 32);
+    }
+
+    #[cfg(feature = "hex")]
     #[test]
     fn convert_newtypes() {
         let h1 = TestNewtype::hash(&[]);
@@ -224,5 +916,31 @@ mod test {
         let h2: TestNewtype = h.to_string().parse().unwrap();
         assert_eq!(h2.as_hash(), h);
     }
-}
 
+    #[test]
+    fn fmt_writer() {
+        use core::fmt::Write;
+
+        let mut engine = ::sha256::Hash::engine();
+        let two = "two";
+        write!(FmtWriter(&mut engine), "{} + {} = {:x}", 1, two, 0xabcu32).unwrap();
+        engine.input(b"!");
+        assert_eq!(
+            ::sha256::Hash::from_engine(engine),
+            ::sha256::Hash::hash(b"1 + two = abc!"),
+        );
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn hash_buf() {
+        use bytes::{Buf, Bytes};
+
+        let mut buf = Bytes::from_static(b"The quick brown fox ")
+            .chain(Bytes::from_static(b""))
+            .chain(Bytes::from_static(b"jumps over the lazy dog"));
+        let hash = ::sha256::Hash::hash_buf(&mut buf);
+        assert!(!buf.has_remaining());
+        assert_eq!(hash, ::sha256::Hash::hash(b"The quick brown fox jumps over the lazy dog"));
+    }
+}
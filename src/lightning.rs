@@ -0,0 +1,130 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # Lightning key derivation hashes
+//!
+//! The hashing steps of the BOLT-3 key derivation and per-commitment secret
+//! generation. Points are 33-byte compressed public keys; the elliptic curve
+//! arithmetic that the results feed into is left to the caller.
+
+use sha256;
+use Hash;
+use HashEngine;
+
+/// Per-commitment indices are 48 bits
+const INDEX_BITS: usize = 48;
+
+/// SHA256 of the concatenation of two points
+fn hash_points(first: &[u8; 33], second: &[u8; 33]) -> sha256::Hash {
+    let mut engine = sha256::Hash::engine();
+    engine.input(first);
+    engine.input(second);
+    sha256::Hash::from_engine(engine)
+}
+
+/// The tweak `SHA256(per_commitment_point || basepoint)` of BOLT-3 key
+/// derivation. The derived private key is `basepoint_secret + tweak` and the
+/// derived public key `basepoint + tweak * G`.
+pub fn derive_privkey_tweak(per_commitment_point: &[u8; 33], basepoint: &[u8; 33]) -> sha256::Hash {
+    hash_points(per_commitment_point, basepoint)
+}
+
+/// The multiplier `SHA256(revocation_basepoint || per_commitment_point)` of
+/// the revocation basepoint when deriving a BOLT-3 revocation key
+pub fn derive_revocation_basepoint_hash(revocation_basepoint: &[u8; 33], per_commitment_point: &[u8; 33]) -> sha256::Hash {
+    hash_points(revocation_basepoint, per_commitment_point)
+}
+
+/// The multiplier `SHA256(per_commitment_point || revocation_basepoint)` of
+/// the per-commitment point when deriving a BOLT-3 revocation key
+pub fn derive_revocation_per_commitment_hash(revocation_basepoint: &[u8; 33], per_commitment_point: &[u8; 33]) -> sha256::Hash {
+    hash_points(per_commitment_point, revocation_basepoint)
+}
+
+/// The per-commitment secret with index `index`, derived from `seed` as in
+/// BOLT-3's `generate_from_seed`. The first commitment uses index
+/// 2^48 - 1, counting down.
+///
+/// Panics if `index` does not fit in 48 bits.
+pub fn per_commitment_secret(seed: &[u8; 32], index: u64) -> [u8; 32] {
+    assert!(index < 1 << INDEX_BITS, "per-commitment index {} does not fit in 48 bits", index);
+
+    let mut secret = *seed;
+    for bit in (0..INDEX_BITS).rev() {
+        if index & (1 << bit) != 0 {
+            secret[bit / 8] ^= 1 << (bit % 8);
+            secret = sha256::Hash::hash(&secret).into_inner();
+        }
+    }
+    secret
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "hex")]
+    use hex::{FromHex, ToHex};
+    use super::*;
+
+    #[cfg(feature = "hex")]
+    fn point(s: &str) -> [u8; 33] {
+        let mut ret = [0; 33];
+        ret.copy_from_slice(&Vec::<u8>::from_hex(s).unwrap());
+        ret
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn key_derivation() {
+        // BOLT-3 appendix E
+        let base_point = point("036d6caac248af96f6afa7f904f550253a0f3ef3f5aa2fe6838a95b216691468e2");
+        let per_commitment_point = point("025f7117a78150fe2ef97db7cfc83bd57b2e2c0d0dd25eaf467a4a1c2a45ce1486");
+
+        assert_eq!(
+            derive_privkey_tweak(&per_commitment_point, &base_point).to_hex(),
+            "cbcdd70fcfad15ea8e9e5c5a12365cf00912504f08ce01593689dd426bca9ff0",
+        );
+        // The appendix uses the same point as revocation basepoint
+        assert_eq!(
+            derive_revocation_basepoint_hash(&base_point, &per_commitment_point).to_hex(),
+            "efbf7ba5a074276701798376950a64a90f698997cce0dff4d24a6d2785d20963",
+        );
+        assert_eq!(
+            derive_revocation_per_commitment_hash(&base_point, &per_commitment_point).to_hex(),
+            "cbcdd70fcfad15ea8e9e5c5a12365cf00912504f08ce01593689dd426bca9ff0",
+        );
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn generate_from_seed() {
+        // BOLT-3 appendix D
+        let tests: [([u8; 32], u64, &str); 5] = [
+            ([0x00; 32], 281474976710655, "02a40c85b6f28da08dfdbe0926c53fab2de6d28c10301f8f7c4073d5e42e3148"),
+            ([0xff; 32], 281474976710655, "7cc854b54e3e0dcdb010d7a3fee464a9687be6e8db3be6854c475621e007a5dc"),
+            ([0xff; 32], 0xaaaaaaaaaaa, "56f4008fb007ca9acf0e15b054d5c9fd12ee06cea347914ddbaed70d1c13a528"),
+            ([0xff; 32], 0x555555555555, "9015daaeb06dba4ccc05b91b2f73bd54405f2be9f217fbacd3c5ac2e62327d31"),
+            ([0x01; 32], 1, "915c75942a26bb3a433a8ce2cb0427c29ec6c1775cfc78328b57f6ba7bfeaa9c"),
+        ];
+        for &(seed, index, expected) in &tests {
+            assert_eq!(per_commitment_secret(&seed, index)[..].to_hex(), expected);
+        }
+        assert_eq!(per_commitment_secret(&[0x42; 32], 0), [0x42; 32]);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in 48 bits")]
+    fn index_too_large() {
+        per_commitment_secret(&[0; 32], 1 << 48);
+    }
+}
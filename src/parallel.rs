@@ -0,0 +1,107 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # Parallel hashing
+//!
+//! Helpers for hashing many independent items across the global `rayon`
+//! thread pool. Output order always matches input order.
+
+use rayon::prelude::*;
+
+use Hash;
+
+/// Below this many items `hash_many` hashes on the calling thread, since
+/// handing the work to the pool costs more than it saves.
+const SEQUENTIAL_THRESHOLD: usize = 256;
+
+/// Smallest number of items given to a single pool task.
+const MIN_TASK_LEN: usize = 64;
+
+/// Hashes every item of `items`, returning the hashes in the same order.
+///
+/// Large inputs are split across the global `rayon` pool; small ones are
+/// hashed sequentially.
+pub fn hash_many<H, T>(items: &[T]) -> Vec<H>
+where
+    H: Hash + Send,
+    T: AsRef<[u8]> + Sync,
+{
+    if items.len() < SEQUENTIAL_THRESHOLD {
+        items.iter().map(|item| <H as Hash>::hash(item.as_ref())).collect()
+    } else {
+        items.par_iter()
+            .with_min_len(MIN_TASK_LEN)
+            .map(|item| <H as Hash>::hash(item.as_ref()))
+            .collect()
+    }
+}
+
+/// Hashes every item yielded by a parallel iterator, returning the hashes in
+/// the iterator's order.
+///
+/// Unlike `hash_many` this never falls back to sequential hashing; the
+/// granularity is whatever the caller's iterator provides.
+pub fn hash_many_par<H, I>(items: I) -> Vec<H>
+where
+    H: Hash + Send,
+    I: IntoParallelIterator,
+    I::Item: AsRef<[u8]>,
+{
+    items.into_par_iter().map(|item| <H as Hash>::hash(item.as_ref())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+
+    use {sha256, sha256d, siphash24, Hash};
+    use super::{hash_many, hash_many_par};
+
+    /// A few thousand items of pseudo-random content and length (0 to 255 bytes)
+    fn items() -> Vec<Vec<u8>> {
+        (0..3000u32).map(|i| {
+            let seed = sha256::Hash::hash(&i.to_le_bytes());
+            let len = seed[0] as usize;
+            seed[..].iter().cycle().take(len).cloned().collect()
+        }).collect()
+    }
+
+    #[test]
+    fn matches_sequential() {
+        let items = items();
+
+        let seq: Vec<sha256d::Hash> = items.iter().map(|i| sha256d::Hash::hash(i)).collect();
+        assert_eq!(hash_many::<sha256d::Hash, _>(&items), seq);
+        assert_eq!(hash_many_par::<sha256d::Hash, _>(&items), seq);
+        assert_eq!(hash_many_par::<sha256d::Hash, _>(items.par_iter().cloned()), seq);
+
+        let seq: Vec<siphash24::Hash> = items.iter().map(|i| siphash24::Hash::hash(i)).collect();
+        assert_eq!(hash_many::<siphash24::Hash, _>(&items), seq);
+
+        // Below the sequential threshold, and empty
+        let seq: Vec<sha256::Hash> = items[..10].iter().map(|i| sha256::Hash::hash(i)).collect();
+        assert_eq!(hash_many::<sha256::Hash, _>(&items[..10]), seq);
+        assert!(hash_many::<sha256::Hash, Vec<u8>>(&[]).is_empty());
+    }
+
+    #[test]
+    fn preserves_order() {
+        let items: Vec<[u8; 4]> = (0..5000u32).map(|i| i.to_be_bytes()).collect();
+        let hashes = hash_many::<sha256::Hash, _>(&items);
+        assert_eq!(hashes.len(), items.len());
+        for (item, hash) in items.iter().zip(&hashes) {
+            assert_eq!(*hash, sha256::Hash::hash(item));
+        }
+    }
+}
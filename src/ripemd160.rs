@@ -19,7 +19,7 @@
 
 //! # RIPEMD160
 
-use core::{cmp, str};
+use core::cmp;
 
 use HashEngine as EngineTrait;
 use Hash as HashTrait;
@@ -33,7 +33,7 @@ const BLOCK_SIZE: usize = 64;
 pub struct HashEngine {
     buffer: [u8; BLOCK_SIZE],
     h: [u32; 5],
-    length: usize,
+    length: u64, // bytes processed so far
 }
 
 impl Default for HashEngine {
@@ -70,6 +70,8 @@ impl EngineTrait for HashEngine {
     engine_input_impl!();
 }
 
+engine_extend_impl!(HashEngine);
+
 /// Output of the RIPEMD160 hash function
 #[derive(Copy, Clone, PartialEq, Eq, Default, PartialOrd, Ord, Hash)]
 pub struct Hash([u8; 20]);
@@ -79,14 +81,22 @@ hex_fmt_impl!(Display, Hash);
 hex_fmt_impl!(LowerHex, Hash);
 index_impl!(Hash);
 serde_impl!(Hash, 20);
+schemars_impl!(Hash, 20);
 borrow_slice_impl!(Hash);
-
-impl str::FromStr for Hash {
-    type Err = ::hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        ::hex::FromHex::from_hex(s)
-    }
-}
+as_ref_array_impl!(Hash, 20);
+bytes_eq_impl!(Hash, 20);
+from_array_impl!(Hash, 20);
+arbitrary_impl!(Hash, 20);
+quickcheck_impl!(Hash, 20);
+borsh_impl!(Hash, 20);
+defmt_impl!(Hash);
+rand_impl!(Hash, 20);
+bitxor_impl!(Hash, 20);
+try_from_impl!(Hash);
+hex_from_str_impl!(Hash);
+subtle_impl!(Hash);
+all_bytes_impl!(Hash, 20);
+const_array_impl!(Hash, 20);
 
 impl HashTrait for Hash {
     type Engine = HashEngine;
@@ -95,19 +105,19 @@ impl HashTrait for Hash {
     #[cfg(not(feature = "fuzztarget"))]
     fn from_engine(mut e: HashEngine) -> Hash {
         // pad buffer with a single 1-bit then all 0s, until there are exactly 8 bytes remaining
-        let data_len = e.length as u64;
+        let data_len = e.length;
 
         let zeroes = [0; BLOCK_SIZE - 8];
         e.input(&[0x80]);
-        if e.length % BLOCK_SIZE > zeroes.len() {
+        if e.length % BLOCK_SIZE as u64 > zeroes.len() as u64 {
             e.input(&zeroes);
         }
-        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE);
+        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE as u64) as usize;
         e.input(&zeroes[..pad_length]);
-        debug_assert_eq!(e.length % BLOCK_SIZE, zeroes.len());
+        debug_assert_eq!(e.length % BLOCK_SIZE as u64, zeroes.len() as u64);
 
-        e.input(&util::u64_to_array_le(8 * data_len));
-        debug_assert_eq!(e.length % BLOCK_SIZE, 0);
+        e.input(&util::u64_to_array_le(util::bit_length(data_len)));
+        debug_assert_eq!(e.length % BLOCK_SIZE as u64, 0);
 
         Hash(e.midstate())
     }
@@ -140,306 +150,340 @@ impl HashTrait for Hash {
     }
 }
 
+macro_rules! f1( ($x:expr, $y:expr, $z:expr) => ($x ^ $y ^ $z) );
+macro_rules! f2( ($x:expr, $y:expr, $z:expr) => (($x & $y) | (!$x & $z)) );
+macro_rules! f3( ($x:expr, $y:expr, $z:expr) => (($x | !$y) ^ $z) );
+macro_rules! f4( ($x:expr, $y:expr, $z:expr) => (($x & $z) | ($y & !$z)) );
+macro_rules! f5( ($x:expr, $y:expr, $z:expr) => ($x ^ ($y | !$z)) );
+
+#[cfg(not(feature = "small-hash"))]
 macro_rules! round(
-    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr,
-     $x:expr, $bits:expr, $add:expr, $round:expr) => ({
-        $a = $a.wrapping_add($round).wrapping_add($x).wrapping_add($add);
-        $a = circular_lshift32!($bits, $a).wrapping_add($e);
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr, $k:expr, $x:expr, $bits:expr) => (
+        $a = circular_lshift32!($bits, $a.wrapping_add($f).wrapping_add($x).wrapping_add($k)).wrapping_add($e);
         $c = circular_lshift32!(10, $c);
-    });
-);
-
-macro_rules! process_block(
-    ($h:expr, $data:expr,
-     $( round1: h_ordering $f0:expr, $f1:expr, $f2:expr, $f3:expr, $f4:expr;
-                data_index $data_index1:expr; roll_shift $bits1:expr; )*
-     $( round2: h_ordering $g0:expr, $g1:expr, $g2:expr, $g3:expr, $g4:expr;
-                data_index $data_index2:expr; roll_shift $bits2:expr; )*
-     $( round3: h_ordering $h0:expr, $h1:expr, $h2:expr, $h3:expr, $h4:expr;
-                data_index $data_index3:expr; roll_shift $bits3:expr; )*
-     $( round4: h_ordering $i0:expr, $i1:expr, $i2:expr, $i3:expr, $i4:expr;
-                data_index $data_index4:expr; roll_shift $bits4:expr; )*
-     $( round5: h_ordering $j0:expr, $j1:expr, $j2:expr, $j3:expr, $j4:expr;
-                data_index $data_index5:expr; roll_shift $bits5:expr; )*
-     $( par_round1: h_ordering $pj0:expr, $pj1:expr, $pj2:expr, $pj3:expr, $pj4:expr;
-                    data_index $pdata_index1:expr; roll_shift $pbits1:expr; )*
-     $( par_round2: h_ordering $pi0:expr, $pi1:expr, $pi2:expr, $pi3:expr, $pi4:expr;
-                    data_index $pdata_index2:expr; roll_shift $pbits2:expr; )*
-     $( par_round3: h_ordering $ph0:expr, $ph1:expr, $ph2:expr, $ph3:expr, $ph4:expr;
-                    data_index $pdata_index3:expr; roll_shift $pbits3:expr; )*
-     $( par_round4: h_ordering $pg0:expr, $pg1:expr, $pg2:expr, $pg3:expr, $pg4:expr;
-                    data_index $pdata_index4:expr; roll_shift $pbits4:expr; )*
-     $( par_round5: h_ordering $pf0:expr, $pf1:expr, $pf2:expr, $pf3:expr, $pf4:expr;
-                    data_index $pdata_index5:expr; roll_shift $pbits5:expr; )*
-    ) => ({
-        let mut bb = $h;
-        let mut bbb = $h;
-
-        // Round 1
-        $( round!(bb[$f0], bb[$f1], bb[$f2], bb[$f3], bb[$f4],
-                  $data[$data_index1], $bits1, 0x00000000,
-                  bb[$f1] ^ bb[$f2] ^ bb[$f3]); )*
-
-        // Round 2
-        $( round!(bb[$g0], bb[$g1], bb[$g2], bb[$g3], bb[$g4],
-                  $data[$data_index2], $bits2, 0x5a827999,
-                  (bb[$g1] & bb[$g2]) | (!bb[$g1] & bb[$g3])); )*
-
-        // Round 3
-        $( round!(bb[$h0], bb[$h1], bb[$h2], bb[$h3], bb[$h4],
-                  $data[$data_index3], $bits3, 0x6ed9eba1,
-                  (bb[$h1] | !bb[$h2]) ^ bb[$h3]); )*
-
-        // Round 4
-        $( round!(bb[$i0], bb[$i1], bb[$i2], bb[$i3], bb[$i4],
-                  $data[$data_index4], $bits4, 0x8f1bbcdc,
-                  (bb[$i1] & bb[$i3]) | (bb[$i2] & !bb[$i3])); )*
-
-        // Round 5
-        $( round!(bb[$j0], bb[$j1], bb[$j2], bb[$j3], bb[$j4],
-                  $data[$data_index5], $bits5, 0xa953fd4e,
-                  bb[$j1] ^ (bb[$j2] | !bb[$j3])); )*
-
-        // Parallel rounds: these are the same as the previous five
-        // rounds except that the constants have changed, we work
-        // with the other buffer, and they are applied in reverse
-        // order.
-
-        // Parallel Round 1
-        $( round!(bbb[$pj0], bbb[$pj1], bbb[$pj2], bbb[$pj3], bbb[$pj4],
-                  $data[$pdata_index1], $pbits1, 0x50a28be6,
-                  bbb[$pj1] ^ (bbb[$pj2] | !bbb[$pj3])); )*
-
-        // Porallel Round 2
-        $( round!(bbb[$pi0], bbb[$pi1], bbb[$pi2], bbb[$pi3], bbb[$pi4],
-                  $data[$pdata_index2], $pbits2, 0x5c4dd124,
-                  (bbb[$pi1] & bbb[$pi3]) | (bbb[$pi2] & !bbb[$pi3])); )*
-
-        // Parallel Round 3
-        $( round!(bbb[$ph0], bbb[$ph1], bbb[$ph2], bbb[$ph3], bbb[$ph4],
-                  $data[$pdata_index3], $pbits3, 0x6d703ef3,
-                  (bbb[$ph1] | !bbb[$ph2]) ^ bbb[$ph3]); )*
-
-        // Parallel Round 4
-        $( round!(bbb[$pg0], bbb[$pg1], bbb[$pg2], bbb[$pg3], bbb[$pg4],
-                  $data[$pdata_index4], $pbits4, 0x7a6d76e9,
-                  (bbb[$pg1] & bbb[$pg2]) | (!bbb[$pg1] & bbb[$pg3])); )*
-
-        // Parallel Round 5
-        $( round!(bbb[$pf0], bbb[$pf1], bbb[$pf2], bbb[$pf3], bbb[$pf4],
-                  $data[$pdata_index5], $pbits5, 0x00000000,
-                  bbb[$pf1] ^ bbb[$pf2] ^ bbb[$pf3]); )*
-
-        // Combine results
-        bbb[3] = bbb[3].wrapping_add($h[1]).wrapping_add(bb[2]);
-        $h[1]  =  $h[2].wrapping_add(bb[3]).wrapping_add(bbb[4]);
-        $h[2]  =  $h[3].wrapping_add(bb[4]).wrapping_add(bbb[0]);
-        $h[3]  =  $h[4].wrapping_add(bb[0]).wrapping_add(bbb[1]);
-        $h[4]  =  $h[0].wrapping_add(bb[1]).wrapping_add(bbb[2]);
-        $h[0]  =                                         bbb[3];
-    });
+    );
 );
 
 impl HashEngine {
-    fn process_block(&mut self) {
-        debug_assert_eq!(self.buffer.len(), BLOCK_SIZE);
+    /// Returns the number of bytes which have been fed into the engine so far.
+    pub fn n_bytes_hashed(&self) -> u64 {
+        self.length
+    }
 
-        let mut w = [0u32; 16];
-        for (w_val, buff_bytes) in w.iter_mut().zip(self.buffer.chunks(4)) {
-            *w_val = util::slice_to_u32_le(buff_bytes);
-        }
+    fn process_block(state: &mut [u32; 5], block: &[u8; BLOCK_SIZE]) {
+        compress(state, block);
+    }
+}
 
-        process_block!(self.h, w,
-            // Round 1
-            round1: h_ordering 0, 1, 2, 3, 4; data_index  0; roll_shift 11;
-            round1: h_ordering 4, 0, 1, 2, 3; data_index  1; roll_shift 14;
-            round1: h_ordering 3, 4, 0, 1, 2; data_index  2; roll_shift 15;
-            round1: h_ordering 2, 3, 4, 0, 1; data_index  3; roll_shift 12;
-            round1: h_ordering 1, 2, 3, 4, 0; data_index  4; roll_shift  5;
-            round1: h_ordering 0, 1, 2, 3, 4; data_index  5; roll_shift  8;
-            round1: h_ordering 4, 0, 1, 2, 3; data_index  6; roll_shift  7;
-            round1: h_ordering 3, 4, 0, 1, 2; data_index  7; roll_shift  9;
-            round1: h_ordering 2, 3, 4, 0, 1; data_index  8; roll_shift 11;
-            round1: h_ordering 1, 2, 3, 4, 0; data_index  9; roll_shift 13;
-            round1: h_ordering 0, 1, 2, 3, 4; data_index 10; roll_shift 14;
-            round1: h_ordering 4, 0, 1, 2, 3; data_index 11; roll_shift 15;
-            round1: h_ordering 3, 4, 0, 1, 2; data_index 12; roll_shift  6;
-            round1: h_ordering 2, 3, 4, 0, 1; data_index 13; roll_shift  7;
-            round1: h_ordering 1, 2, 3, 4, 0; data_index 14; roll_shift  9;
-            round1: h_ordering 0, 1, 2, 3, 4; data_index 15; roll_shift  8;
-
-            // Round 2
-            round2: h_ordering 4, 0, 1, 2, 3; data_index  7; roll_shift  7;
-            round2: h_ordering 3, 4, 0, 1, 2; data_index  4; roll_shift  6;
-            round2: h_ordering 2, 3, 4, 0, 1; data_index 13; roll_shift  8;
-            round2: h_ordering 1, 2, 3, 4, 0; data_index  1; roll_shift 13;
-            round2: h_ordering 0, 1, 2, 3, 4; data_index 10; roll_shift 11;
-            round2: h_ordering 4, 0, 1, 2, 3; data_index  6; roll_shift  9;
-            round2: h_ordering 3, 4, 0, 1, 2; data_index 15; roll_shift  7;
-            round2: h_ordering 2, 3, 4, 0, 1; data_index  3; roll_shift 15;
-            round2: h_ordering 1, 2, 3, 4, 0; data_index 12; roll_shift  7;
-            round2: h_ordering 0, 1, 2, 3, 4; data_index  0; roll_shift 12;
-            round2: h_ordering 4, 0, 1, 2, 3; data_index  9; roll_shift 15;
-            round2: h_ordering 3, 4, 0, 1, 2; data_index  5; roll_shift  9;
-            round2: h_ordering 2, 3, 4, 0, 1; data_index  2; roll_shift 11;
-            round2: h_ordering 1, 2, 3, 4, 0; data_index 14; roll_shift  7;
-            round2: h_ordering 0, 1, 2, 3, 4; data_index 11; roll_shift 13;
-            round2: h_ordering 4, 0, 1, 2, 3; data_index  8; roll_shift 12;
-
-            // Round 3
-            round3: h_ordering 3, 4, 0, 1, 2; data_index  3; roll_shift 11;
-            round3: h_ordering 2, 3, 4, 0, 1; data_index 10; roll_shift 13;
-            round3: h_ordering 1, 2, 3, 4, 0; data_index 14; roll_shift  6;
-            round3: h_ordering 0, 1, 2, 3, 4; data_index  4; roll_shift  7;
-            round3: h_ordering 4, 0, 1, 2, 3; data_index  9; roll_shift 14;
-            round3: h_ordering 3, 4, 0, 1, 2; data_index 15; roll_shift  9;
-            round3: h_ordering 2, 3, 4, 0, 1; data_index  8; roll_shift 13;
-            round3: h_ordering 1, 2, 3, 4, 0; data_index  1; roll_shift 15;
-            round3: h_ordering 0, 1, 2, 3, 4; data_index  2; roll_shift 14;
-            round3: h_ordering 4, 0, 1, 2, 3; data_index  7; roll_shift  8;
-            round3: h_ordering 3, 4, 0, 1, 2; data_index  0; roll_shift 13;
-            round3: h_ordering 2, 3, 4, 0, 1; data_index  6; roll_shift  6;
-            round3: h_ordering 1, 2, 3, 4, 0; data_index 13; roll_shift  5;
-            round3: h_ordering 0, 1, 2, 3, 4; data_index 11; roll_shift 12;
-            round3: h_ordering 4, 0, 1, 2, 3; data_index  5; roll_shift  7;
-            round3: h_ordering 3, 4, 0, 1, 2; data_index 12; roll_shift  5;
-
-            // Round 4
-            round4: h_ordering 2, 3, 4, 0, 1; data_index  1; roll_shift 11;
-            round4: h_ordering 1, 2, 3, 4, 0; data_index  9; roll_shift 12;
-            round4: h_ordering 0, 1, 2, 3, 4; data_index 11; roll_shift 14;
-            round4: h_ordering 4, 0, 1, 2, 3; data_index 10; roll_shift 15;
-            round4: h_ordering 3, 4, 0, 1, 2; data_index  0; roll_shift 14;
-            round4: h_ordering 2, 3, 4, 0, 1; data_index  8; roll_shift 15;
-            round4: h_ordering 1, 2, 3, 4, 0; data_index 12; roll_shift  9;
-            round4: h_ordering 0, 1, 2, 3, 4; data_index  4; roll_shift  8;
-            round4: h_ordering 4, 0, 1, 2, 3; data_index 13; roll_shift  9;
-            round4: h_ordering 3, 4, 0, 1, 2; data_index  3; roll_shift 14;
-            round4: h_ordering 2, 3, 4, 0, 1; data_index  7; roll_shift  5;
-            round4: h_ordering 1, 2, 3, 4, 0; data_index 15; roll_shift  6;
-            round4: h_ordering 0, 1, 2, 3, 4; data_index 14; roll_shift  8;
-            round4: h_ordering 4, 0, 1, 2, 3; data_index  5; roll_shift  6;
-            round4: h_ordering 3, 4, 0, 1, 2; data_index  6; roll_shift  5;
-            round4: h_ordering 2, 3, 4, 0, 1; data_index  2; roll_shift 12;
-
-            // Round 5
-            round5: h_ordering 1, 2, 3, 4, 0; data_index  4; roll_shift  9;
-            round5: h_ordering 0, 1, 2, 3, 4; data_index  0; roll_shift 15;
-            round5: h_ordering 4, 0, 1, 2, 3; data_index  5; roll_shift  5;
-            round5: h_ordering 3, 4, 0, 1, 2; data_index  9; roll_shift 11;
-            round5: h_ordering 2, 3, 4, 0, 1; data_index  7; roll_shift  6;
-            round5: h_ordering 1, 2, 3, 4, 0; data_index 12; roll_shift  8;
-            round5: h_ordering 0, 1, 2, 3, 4; data_index  2; roll_shift 13;
-            round5: h_ordering 4, 0, 1, 2, 3; data_index 10; roll_shift 12;
-            round5: h_ordering 3, 4, 0, 1, 2; data_index 14; roll_shift  5;
-            round5: h_ordering 2, 3, 4, 0, 1; data_index  1; roll_shift 12;
-            round5: h_ordering 1, 2, 3, 4, 0; data_index  3; roll_shift 13;
-            round5: h_ordering 0, 1, 2, 3, 4; data_index  8; roll_shift 14;
-            round5: h_ordering 4, 0, 1, 2, 3; data_index 11; roll_shift 11;
-            round5: h_ordering 3, 4, 0, 1, 2; data_index  6; roll_shift  8;
-            round5: h_ordering 2, 3, 4, 0, 1; data_index 15; roll_shift  5;
-            round5: h_ordering 1, 2, 3, 4, 0; data_index 13; roll_shift  6;
-
-            // Porallel Round 1;
-            par_round1: h_ordering 0, 1, 2, 3, 4; data_index  5; roll_shift  8;
-            par_round1: h_ordering 4, 0, 1, 2, 3; data_index 14; roll_shift  9;
-            par_round1: h_ordering 3, 4, 0, 1, 2; data_index  7; roll_shift  9;
-            par_round1: h_ordering 2, 3, 4, 0, 1; data_index  0; roll_shift 11;
-            par_round1: h_ordering 1, 2, 3, 4, 0; data_index  9; roll_shift 13;
-            par_round1: h_ordering 0, 1, 2, 3, 4; data_index  2; roll_shift 15;
-            par_round1: h_ordering 4, 0, 1, 2, 3; data_index 11; roll_shift 15;
-            par_round1: h_ordering 3, 4, 0, 1, 2; data_index  4; roll_shift  5;
-            par_round1: h_ordering 2, 3, 4, 0, 1; data_index 13; roll_shift  7;
-            par_round1: h_ordering 1, 2, 3, 4, 0; data_index  6; roll_shift  7;
-            par_round1: h_ordering 0, 1, 2, 3, 4; data_index 15; roll_shift  8;
-            par_round1: h_ordering 4, 0, 1, 2, 3; data_index  8; roll_shift 11;
-            par_round1: h_ordering 3, 4, 0, 1, 2; data_index  1; roll_shift 14;
-            par_round1: h_ordering 2, 3, 4, 0, 1; data_index 10; roll_shift 14;
-            par_round1: h_ordering 1, 2, 3, 4, 0; data_index  3; roll_shift 12;
-            par_round1: h_ordering 0, 1, 2, 3, 4; data_index 12; roll_shift  6;
-
-            // Parallel Round 2
-            par_round2: h_ordering 4, 0, 1, 2, 3; data_index  6; roll_shift  9;
-            par_round2: h_ordering 3, 4, 0, 1, 2; data_index 11; roll_shift 13;
-            par_round2: h_ordering 2, 3, 4, 0, 1; data_index  3; roll_shift 15;
-            par_round2: h_ordering 1, 2, 3, 4, 0; data_index  7; roll_shift  7;
-            par_round2: h_ordering 0, 1, 2, 3, 4; data_index  0; roll_shift 12;
-            par_round2: h_ordering 4, 0, 1, 2, 3; data_index 13; roll_shift  8;
-            par_round2: h_ordering 3, 4, 0, 1, 2; data_index  5; roll_shift  9;
-            par_round2: h_ordering 2, 3, 4, 0, 1; data_index 10; roll_shift 11;
-            par_round2: h_ordering 1, 2, 3, 4, 0; data_index 14; roll_shift  7;
-            par_round2: h_ordering 0, 1, 2, 3, 4; data_index 15; roll_shift  7;
-            par_round2: h_ordering 4, 0, 1, 2, 3; data_index  8; roll_shift 12;
-            par_round2: h_ordering 3, 4, 0, 1, 2; data_index 12; roll_shift  7;
-            par_round2: h_ordering 2, 3, 4, 0, 1; data_index  4; roll_shift  6;
-            par_round2: h_ordering 1, 2, 3, 4, 0; data_index  9; roll_shift 15;
-            par_round2: h_ordering 0, 1, 2, 3, 4; data_index  1; roll_shift 13;
-            par_round2: h_ordering 4, 0, 1, 2, 3; data_index  2; roll_shift 11;
-
-            // Parallel Round 3
-            par_round3: h_ordering 3, 4, 0, 1, 2; data_index 15; roll_shift  9;
-            par_round3: h_ordering 2, 3, 4, 0, 1; data_index  5; roll_shift  7;
-            par_round3: h_ordering 1, 2, 3, 4, 0; data_index  1; roll_shift 15;
-            par_round3: h_ordering 0, 1, 2, 3, 4; data_index  3; roll_shift 11;
-            par_round3: h_ordering 4, 0, 1, 2, 3; data_index  7; roll_shift  8;
-            par_round3: h_ordering 3, 4, 0, 1, 2; data_index 14; roll_shift  6;
-            par_round3: h_ordering 2, 3, 4, 0, 1; data_index  6; roll_shift  6;
-            par_round3: h_ordering 1, 2, 3, 4, 0; data_index  9; roll_shift 14;
-            par_round3: h_ordering 0, 1, 2, 3, 4; data_index 11; roll_shift 12;
-            par_round3: h_ordering 4, 0, 1, 2, 3; data_index  8; roll_shift 13;
-            par_round3: h_ordering 3, 4, 0, 1, 2; data_index 12; roll_shift  5;
-            par_round3: h_ordering 2, 3, 4, 0, 1; data_index  2; roll_shift 14;
-            par_round3: h_ordering 1, 2, 3, 4, 0; data_index 10; roll_shift 13;
-            par_round3: h_ordering 0, 1, 2, 3, 4; data_index  0; roll_shift 13;
-            par_round3: h_ordering 4, 0, 1, 2, 3; data_index  4; roll_shift  7;
-            par_round3: h_ordering 3, 4, 0, 1, 2; data_index 13; roll_shift  5;
-
-            // Parallel Round 4
-            par_round4: h_ordering 2, 3, 4, 0, 1; data_index  8; roll_shift 15;
-            par_round4: h_ordering 1, 2, 3, 4, 0; data_index  6; roll_shift  5;
-            par_round4: h_ordering 0, 1, 2, 3, 4; data_index  4; roll_shift  8;
-            par_round4: h_ordering 4, 0, 1, 2, 3; data_index  1; roll_shift 11;
-            par_round4: h_ordering 3, 4, 0, 1, 2; data_index  3; roll_shift 14;
-            par_round4: h_ordering 2, 3, 4, 0, 1; data_index 11; roll_shift 14;
-            par_round4: h_ordering 1, 2, 3, 4, 0; data_index 15; roll_shift  6;
-            par_round4: h_ordering 0, 1, 2, 3, 4; data_index  0; roll_shift 14;
-            par_round4: h_ordering 4, 0, 1, 2, 3; data_index  5; roll_shift  6;
-            par_round4: h_ordering 3, 4, 0, 1, 2; data_index 12; roll_shift  9;
-            par_round4: h_ordering 2, 3, 4, 0, 1; data_index  2; roll_shift 12;
-            par_round4: h_ordering 1, 2, 3, 4, 0; data_index 13; roll_shift  9;
-            par_round4: h_ordering 0, 1, 2, 3, 4; data_index  9; roll_shift 12;
-            par_round4: h_ordering 4, 0, 1, 2, 3; data_index  7; roll_shift  5;
-            par_round4: h_ordering 3, 4, 0, 1, 2; data_index 10; roll_shift 15;
-            par_round4: h_ordering 2, 3, 4, 0, 1; data_index 14; roll_shift  8;
-
-            // Parallel Round 5
-            par_round5: h_ordering 1, 2, 3, 4, 0; data_index 12; roll_shift  8;
-            par_round5: h_ordering 0, 1, 2, 3, 4; data_index 15; roll_shift  5;
-            par_round5: h_ordering 4, 0, 1, 2, 3; data_index 10; roll_shift 12;
-            par_round5: h_ordering 3, 4, 0, 1, 2; data_index  4; roll_shift  9;
-            par_round5: h_ordering 2, 3, 4, 0, 1; data_index  1; roll_shift 12;
-            par_round5: h_ordering 1, 2, 3, 4, 0; data_index  5; roll_shift  5;
-            par_round5: h_ordering 0, 1, 2, 3, 4; data_index  8; roll_shift 14;
-            par_round5: h_ordering 4, 0, 1, 2, 3; data_index  7; roll_shift  6;
-            par_round5: h_ordering 3, 4, 0, 1, 2; data_index  6; roll_shift  8;
-            par_round5: h_ordering 2, 3, 4, 0, 1; data_index  2; roll_shift 13;
-            par_round5: h_ordering 1, 2, 3, 4, 0; data_index 13; roll_shift  6;
-            par_round5: h_ordering 0, 1, 2, 3, 4; data_index 14; roll_shift  5;
-            par_round5: h_ordering 4, 0, 1, 2, 3; data_index  0; roll_shift 15;
-            par_round5: h_ordering 3, 4, 0, 1, 2; data_index  3; roll_shift 13;
-            par_round5: h_ordering 2, 3, 4, 0, 1; data_index  9; roll_shift 11;
-            par_round5: h_ordering 1, 2, 3, 4, 0; data_index 11; roll_shift 11;
-        );
+/// Runs the RIPEMD160 compression function, updating `state` with one
+/// 64-byte message block. This is what the engine applies to each full block
+/// of input; no padding or length encoding is done here.
+#[cfg(not(feature = "small-hash"))]
+pub fn compress(state: &mut [u32; 5], block: &[u8; BLOCK_SIZE]) {
+    let mut w = [0u32; 16];
+    for (w_val, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *w_val = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+
+    // The two lines are independent until they are combined at the end;
+    // interleaving their steps lets the CPU work on both at once
+    let (mut la, mut lb, mut lc, mut ld, mut le) = (state[0], state[1], state[2], state[3], state[4]);
+    let (mut ra, mut rb, mut rc, mut rd, mut re) = (la, lb, lc, ld, le);
+
+    // Left line round 1, right line round 5
+    round!(la, lb, lc, ld, le, f1!(lb, lc, ld), 0x00000000, w[ 0], 11);
+    round!(ra, rb, rc, rd, re, f5!(rb, rc, rd), 0x50a28be6, w[ 5],  8);
+    round!(le, la, lb, lc, ld, f1!(la, lb, lc), 0x00000000, w[ 1], 14);
+    round!(re, ra, rb, rc, rd, f5!(ra, rb, rc), 0x50a28be6, w[14],  9);
+    round!(ld, le, la, lb, lc, f1!(le, la, lb), 0x00000000, w[ 2], 15);
+    round!(rd, re, ra, rb, rc, f5!(re, ra, rb), 0x50a28be6, w[ 7],  9);
+    round!(lc, ld, le, la, lb, f1!(ld, le, la), 0x00000000, w[ 3], 12);
+    round!(rc, rd, re, ra, rb, f5!(rd, re, ra), 0x50a28be6, w[ 0], 11);
+    round!(lb, lc, ld, le, la, f1!(lc, ld, le), 0x00000000, w[ 4],  5);
+    round!(rb, rc, rd, re, ra, f5!(rc, rd, re), 0x50a28be6, w[ 9], 13);
+    round!(la, lb, lc, ld, le, f1!(lb, lc, ld), 0x00000000, w[ 5],  8);
+    round!(ra, rb, rc, rd, re, f5!(rb, rc, rd), 0x50a28be6, w[ 2], 15);
+    round!(le, la, lb, lc, ld, f1!(la, lb, lc), 0x00000000, w[ 6],  7);
+    round!(re, ra, rb, rc, rd, f5!(ra, rb, rc), 0x50a28be6, w[11], 15);
+    round!(ld, le, la, lb, lc, f1!(le, la, lb), 0x00000000, w[ 7],  9);
+    round!(rd, re, ra, rb, rc, f5!(re, ra, rb), 0x50a28be6, w[ 4],  5);
+    round!(lc, ld, le, la, lb, f1!(ld, le, la), 0x00000000, w[ 8], 11);
+    round!(rc, rd, re, ra, rb, f5!(rd, re, ra), 0x50a28be6, w[13],  7);
+    round!(lb, lc, ld, le, la, f1!(lc, ld, le), 0x00000000, w[ 9], 13);
+    round!(rb, rc, rd, re, ra, f5!(rc, rd, re), 0x50a28be6, w[ 6],  7);
+    round!(la, lb, lc, ld, le, f1!(lb, lc, ld), 0x00000000, w[10], 14);
+    round!(ra, rb, rc, rd, re, f5!(rb, rc, rd), 0x50a28be6, w[15],  8);
+    round!(le, la, lb, lc, ld, f1!(la, lb, lc), 0x00000000, w[11], 15);
+    round!(re, ra, rb, rc, rd, f5!(ra, rb, rc), 0x50a28be6, w[ 8], 11);
+    round!(ld, le, la, lb, lc, f1!(le, la, lb), 0x00000000, w[12],  6);
+    round!(rd, re, ra, rb, rc, f5!(re, ra, rb), 0x50a28be6, w[ 1], 14);
+    round!(lc, ld, le, la, lb, f1!(ld, le, la), 0x00000000, w[13],  7);
+    round!(rc, rd, re, ra, rb, f5!(rd, re, ra), 0x50a28be6, w[10], 14);
+    round!(lb, lc, ld, le, la, f1!(lc, ld, le), 0x00000000, w[14],  9);
+    round!(rb, rc, rd, re, ra, f5!(rc, rd, re), 0x50a28be6, w[ 3], 12);
+    round!(la, lb, lc, ld, le, f1!(lb, lc, ld), 0x00000000, w[15],  8);
+    round!(ra, rb, rc, rd, re, f5!(rb, rc, rd), 0x50a28be6, w[12],  6);
+
+    // Left line round 2, right line round 4
+    round!(le, la, lb, lc, ld, f2!(la, lb, lc), 0x5a827999, w[ 7],  7);
+    round!(re, ra, rb, rc, rd, f4!(ra, rb, rc), 0x5c4dd124, w[ 6],  9);
+    round!(ld, le, la, lb, lc, f2!(le, la, lb), 0x5a827999, w[ 4],  6);
+    round!(rd, re, ra, rb, rc, f4!(re, ra, rb), 0x5c4dd124, w[11], 13);
+    round!(lc, ld, le, la, lb, f2!(ld, le, la), 0x5a827999, w[13],  8);
+    round!(rc, rd, re, ra, rb, f4!(rd, re, ra), 0x5c4dd124, w[ 3], 15);
+    round!(lb, lc, ld, le, la, f2!(lc, ld, le), 0x5a827999, w[ 1], 13);
+    round!(rb, rc, rd, re, ra, f4!(rc, rd, re), 0x5c4dd124, w[ 7],  7);
+    round!(la, lb, lc, ld, le, f2!(lb, lc, ld), 0x5a827999, w[10], 11);
+    round!(ra, rb, rc, rd, re, f4!(rb, rc, rd), 0x5c4dd124, w[ 0], 12);
+    round!(le, la, lb, lc, ld, f2!(la, lb, lc), 0x5a827999, w[ 6],  9);
+    round!(re, ra, rb, rc, rd, f4!(ra, rb, rc), 0x5c4dd124, w[13],  8);
+    round!(ld, le, la, lb, lc, f2!(le, la, lb), 0x5a827999, w[15],  7);
+    round!(rd, re, ra, rb, rc, f4!(re, ra, rb), 0x5c4dd124, w[ 5],  9);
+    round!(lc, ld, le, la, lb, f2!(ld, le, la), 0x5a827999, w[ 3], 15);
+    round!(rc, rd, re, ra, rb, f4!(rd, re, ra), 0x5c4dd124, w[10], 11);
+    round!(lb, lc, ld, le, la, f2!(lc, ld, le), 0x5a827999, w[12],  7);
+    round!(rb, rc, rd, re, ra, f4!(rc, rd, re), 0x5c4dd124, w[14],  7);
+    round!(la, lb, lc, ld, le, f2!(lb, lc, ld), 0x5a827999, w[ 0], 12);
+    round!(ra, rb, rc, rd, re, f4!(rb, rc, rd), 0x5c4dd124, w[15],  7);
+    round!(le, la, lb, lc, ld, f2!(la, lb, lc), 0x5a827999, w[ 9], 15);
+    round!(re, ra, rb, rc, rd, f4!(ra, rb, rc), 0x5c4dd124, w[ 8], 12);
+    round!(ld, le, la, lb, lc, f2!(le, la, lb), 0x5a827999, w[ 5],  9);
+    round!(rd, re, ra, rb, rc, f4!(re, ra, rb), 0x5c4dd124, w[12],  7);
+    round!(lc, ld, le, la, lb, f2!(ld, le, la), 0x5a827999, w[ 2], 11);
+    round!(rc, rd, re, ra, rb, f4!(rd, re, ra), 0x5c4dd124, w[ 4],  6);
+    round!(lb, lc, ld, le, la, f2!(lc, ld, le), 0x5a827999, w[14],  7);
+    round!(rb, rc, rd, re, ra, f4!(rc, rd, re), 0x5c4dd124, w[ 9], 15);
+    round!(la, lb, lc, ld, le, f2!(lb, lc, ld), 0x5a827999, w[11], 13);
+    round!(ra, rb, rc, rd, re, f4!(rb, rc, rd), 0x5c4dd124, w[ 1], 13);
+    round!(le, la, lb, lc, ld, f2!(la, lb, lc), 0x5a827999, w[ 8], 12);
+    round!(re, ra, rb, rc, rd, f4!(ra, rb, rc), 0x5c4dd124, w[ 2], 11);
+
+    // Round 3 on both lines
+    round!(ld, le, la, lb, lc, f3!(le, la, lb), 0x6ed9eba1, w[ 3], 11);
+    round!(rd, re, ra, rb, rc, f3!(re, ra, rb), 0x6d703ef3, w[15],  9);
+    round!(lc, ld, le, la, lb, f3!(ld, le, la), 0x6ed9eba1, w[10], 13);
+    round!(rc, rd, re, ra, rb, f3!(rd, re, ra), 0x6d703ef3, w[ 5],  7);
+    round!(lb, lc, ld, le, la, f3!(lc, ld, le), 0x6ed9eba1, w[14],  6);
+    round!(rb, rc, rd, re, ra, f3!(rc, rd, re), 0x6d703ef3, w[ 1], 15);
+    round!(la, lb, lc, ld, le, f3!(lb, lc, ld), 0x6ed9eba1, w[ 4],  7);
+    round!(ra, rb, rc, rd, re, f3!(rb, rc, rd), 0x6d703ef3, w[ 3], 11);
+    round!(le, la, lb, lc, ld, f3!(la, lb, lc), 0x6ed9eba1, w[ 9], 14);
+    round!(re, ra, rb, rc, rd, f3!(ra, rb, rc), 0x6d703ef3, w[ 7],  8);
+    round!(ld, le, la, lb, lc, f3!(le, la, lb), 0x6ed9eba1, w[15],  9);
+    round!(rd, re, ra, rb, rc, f3!(re, ra, rb), 0x6d703ef3, w[14],  6);
+    round!(lc, ld, le, la, lb, f3!(ld, le, la), 0x6ed9eba1, w[ 8], 13);
+    round!(rc, rd, re, ra, rb, f3!(rd, re, ra), 0x6d703ef3, w[ 6],  6);
+    round!(lb, lc, ld, le, la, f3!(lc, ld, le), 0x6ed9eba1, w[ 1], 15);
+    round!(rb, rc, rd, re, ra, f3!(rc, rd, re), 0x6d703ef3, w[ 9], 14);
+    round!(la, lb, lc, ld, le, f3!(lb, lc, ld), 0x6ed9eba1, w[ 2], 14);
+    round!(ra, rb, rc, rd, re, f3!(rb, rc, rd), 0x6d703ef3, w[11], 12);
+    round!(le, la, lb, lc, ld, f3!(la, lb, lc), 0x6ed9eba1, w[ 7],  8);
+    round!(re, ra, rb, rc, rd, f3!(ra, rb, rc), 0x6d703ef3, w[ 8], 13);
+    round!(ld, le, la, lb, lc, f3!(le, la, lb), 0x6ed9eba1, w[ 0], 13);
+    round!(rd, re, ra, rb, rc, f3!(re, ra, rb), 0x6d703ef3, w[12],  5);
+    round!(lc, ld, le, la, lb, f3!(ld, le, la), 0x6ed9eba1, w[ 6],  6);
+    round!(rc, rd, re, ra, rb, f3!(rd, re, ra), 0x6d703ef3, w[ 2], 14);
+    round!(lb, lc, ld, le, la, f3!(lc, ld, le), 0x6ed9eba1, w[13],  5);
+    round!(rb, rc, rd, re, ra, f3!(rc, rd, re), 0x6d703ef3, w[10], 13);
+    round!(la, lb, lc, ld, le, f3!(lb, lc, ld), 0x6ed9eba1, w[11], 12);
+    round!(ra, rb, rc, rd, re, f3!(rb, rc, rd), 0x6d703ef3, w[ 0], 13);
+    round!(le, la, lb, lc, ld, f3!(la, lb, lc), 0x6ed9eba1, w[ 5],  7);
+    round!(re, ra, rb, rc, rd, f3!(ra, rb, rc), 0x6d703ef3, w[ 4],  7);
+    round!(ld, le, la, lb, lc, f3!(le, la, lb), 0x6ed9eba1, w[12],  5);
+    round!(rd, re, ra, rb, rc, f3!(re, ra, rb), 0x6d703ef3, w[13],  5);
+
+    // Left line round 4, right line round 2
+    round!(lc, ld, le, la, lb, f4!(ld, le, la), 0x8f1bbcdc, w[ 1], 11);
+    round!(rc, rd, re, ra, rb, f2!(rd, re, ra), 0x7a6d76e9, w[ 8], 15);
+    round!(lb, lc, ld, le, la, f4!(lc, ld, le), 0x8f1bbcdc, w[ 9], 12);
+    round!(rb, rc, rd, re, ra, f2!(rc, rd, re), 0x7a6d76e9, w[ 6],  5);
+    round!(la, lb, lc, ld, le, f4!(lb, lc, ld), 0x8f1bbcdc, w[11], 14);
+    round!(ra, rb, rc, rd, re, f2!(rb, rc, rd), 0x7a6d76e9, w[ 4],  8);
+    round!(le, la, lb, lc, ld, f4!(la, lb, lc), 0x8f1bbcdc, w[10], 15);
+    round!(re, ra, rb, rc, rd, f2!(ra, rb, rc), 0x7a6d76e9, w[ 1], 11);
+    round!(ld, le, la, lb, lc, f4!(le, la, lb), 0x8f1bbcdc, w[ 0], 14);
+    round!(rd, re, ra, rb, rc, f2!(re, ra, rb), 0x7a6d76e9, w[ 3], 14);
+    round!(lc, ld, le, la, lb, f4!(ld, le, la), 0x8f1bbcdc, w[ 8], 15);
+    round!(rc, rd, re, ra, rb, f2!(rd, re, ra), 0x7a6d76e9, w[11], 14);
+    round!(lb, lc, ld, le, la, f4!(lc, ld, le), 0x8f1bbcdc, w[12],  9);
+    round!(rb, rc, rd, re, ra, f2!(rc, rd, re), 0x7a6d76e9, w[15],  6);
+    round!(la, lb, lc, ld, le, f4!(lb, lc, ld), 0x8f1bbcdc, w[ 4],  8);
+    round!(ra, rb, rc, rd, re, f2!(rb, rc, rd), 0x7a6d76e9, w[ 0], 14);
+    round!(le, la, lb, lc, ld, f4!(la, lb, lc), 0x8f1bbcdc, w[13],  9);
+    round!(re, ra, rb, rc, rd, f2!(ra, rb, rc), 0x7a6d76e9, w[ 5],  6);
+    round!(ld, le, la, lb, lc, f4!(le, la, lb), 0x8f1bbcdc, w[ 3], 14);
+    round!(rd, re, ra, rb, rc, f2!(re, ra, rb), 0x7a6d76e9, w[12],  9);
+    round!(lc, ld, le, la, lb, f4!(ld, le, la), 0x8f1bbcdc, w[ 7],  5);
+    round!(rc, rd, re, ra, rb, f2!(rd, re, ra), 0x7a6d76e9, w[ 2], 12);
+    round!(lb, lc, ld, le, la, f4!(lc, ld, le), 0x8f1bbcdc, w[15],  6);
+    round!(rb, rc, rd, re, ra, f2!(rc, rd, re), 0x7a6d76e9, w[13],  9);
+    round!(la, lb, lc, ld, le, f4!(lb, lc, ld), 0x8f1bbcdc, w[14],  8);
+    round!(ra, rb, rc, rd, re, f2!(rb, rc, rd), 0x7a6d76e9, w[ 9], 12);
+    round!(le, la, lb, lc, ld, f4!(la, lb, lc), 0x8f1bbcdc, w[ 5],  6);
+    round!(re, ra, rb, rc, rd, f2!(ra, rb, rc), 0x7a6d76e9, w[ 7],  5);
+    round!(ld, le, la, lb, lc, f4!(le, la, lb), 0x8f1bbcdc, w[ 6],  5);
+    round!(rd, re, ra, rb, rc, f2!(re, ra, rb), 0x7a6d76e9, w[10], 15);
+    round!(lc, ld, le, la, lb, f4!(ld, le, la), 0x8f1bbcdc, w[ 2], 12);
+    round!(rc, rd, re, ra, rb, f2!(rd, re, ra), 0x7a6d76e9, w[14],  8);
+
+    // Left line round 5, right line round 1
+    round!(lb, lc, ld, le, la, f5!(lc, ld, le), 0xa953fd4e, w[ 4],  9);
+    round!(rb, rc, rd, re, ra, f1!(rc, rd, re), 0x00000000, w[12],  8);
+    round!(la, lb, lc, ld, le, f5!(lb, lc, ld), 0xa953fd4e, w[ 0], 15);
+    round!(ra, rb, rc, rd, re, f1!(rb, rc, rd), 0x00000000, w[15],  5);
+    round!(le, la, lb, lc, ld, f5!(la, lb, lc), 0xa953fd4e, w[ 5],  5);
+    round!(re, ra, rb, rc, rd, f1!(ra, rb, rc), 0x00000000, w[10], 12);
+    round!(ld, le, la, lb, lc, f5!(le, la, lb), 0xa953fd4e, w[ 9], 11);
+    round!(rd, re, ra, rb, rc, f1!(re, ra, rb), 0x00000000, w[ 4],  9);
+    round!(lc, ld, le, la, lb, f5!(ld, le, la), 0xa953fd4e, w[ 7],  6);
+    round!(rc, rd, re, ra, rb, f1!(rd, re, ra), 0x00000000, w[ 1], 12);
+    round!(lb, lc, ld, le, la, f5!(lc, ld, le), 0xa953fd4e, w[12],  8);
+    round!(rb, rc, rd, re, ra, f1!(rc, rd, re), 0x00000000, w[ 5],  5);
+    round!(la, lb, lc, ld, le, f5!(lb, lc, ld), 0xa953fd4e, w[ 2], 13);
+    round!(ra, rb, rc, rd, re, f1!(rb, rc, rd), 0x00000000, w[ 8], 14);
+    round!(le, la, lb, lc, ld, f5!(la, lb, lc), 0xa953fd4e, w[10], 12);
+    round!(re, ra, rb, rc, rd, f1!(ra, rb, rc), 0x00000000, w[ 7],  6);
+    round!(ld, le, la, lb, lc, f5!(le, la, lb), 0xa953fd4e, w[14],  5);
+    round!(rd, re, ra, rb, rc, f1!(re, ra, rb), 0x00000000, w[ 6],  8);
+    round!(lc, ld, le, la, lb, f5!(ld, le, la), 0xa953fd4e, w[ 1], 12);
+    round!(rc, rd, re, ra, rb, f1!(rd, re, ra), 0x00000000, w[ 2], 13);
+    round!(lb, lc, ld, le, la, f5!(lc, ld, le), 0xa953fd4e, w[ 3], 13);
+    round!(rb, rc, rd, re, ra, f1!(rc, rd, re), 0x00000000, w[13],  6);
+    round!(la, lb, lc, ld, le, f5!(lb, lc, ld), 0xa953fd4e, w[ 8], 14);
+    round!(ra, rb, rc, rd, re, f1!(rb, rc, rd), 0x00000000, w[14],  5);
+    round!(le, la, lb, lc, ld, f5!(la, lb, lc), 0xa953fd4e, w[11], 11);
+    round!(re, ra, rb, rc, rd, f1!(ra, rb, rc), 0x00000000, w[ 0], 15);
+    round!(ld, le, la, lb, lc, f5!(le, la, lb), 0xa953fd4e, w[ 6],  8);
+    round!(rd, re, ra, rb, rc, f1!(re, ra, rb), 0x00000000, w[ 3], 13);
+    round!(lc, ld, le, la, lb, f5!(ld, le, la), 0xa953fd4e, w[15],  5);
+    round!(rc, rd, re, ra, rb, f1!(rd, re, ra), 0x00000000, w[ 9], 11);
+    round!(lb, lc, ld, le, la, f5!(lc, ld, le), 0xa953fd4e, w[13],  6);
+    round!(rb, rc, rd, re, ra, f1!(rc, rd, re), 0x00000000, w[11], 11);
+
+    let t = state[1].wrapping_add(lc).wrapping_add(rd);
+    state[1] = state[2].wrapping_add(ld).wrapping_add(re);
+    state[2] = state[3].wrapping_add(le).wrapping_add(ra);
+    state[3] = state[4].wrapping_add(la).wrapping_add(rb);
+    state[4] = state[0].wrapping_add(lb).wrapping_add(rc);
+    state[0] = t;
+}
+
+/// Message word selected at each step of the left line
+#[cfg(feature = "small-hash")]
+const WORD_LEFT: [u8; 80] = [
+     0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15,
+     7,  4, 13,  1, 10,  6, 15,  3, 12,  0,  9,  5,  2, 14, 11,  8,
+     3, 10, 14,  4,  9, 15,  8,  1,  2,  7,  0,  6, 13, 11,  5, 12,
+     1,  9, 11, 10,  0,  8, 12,  4, 13,  3,  7, 15, 14,  5,  6,  2,
+     4,  0,  5,  9,  7, 12,  2, 10, 14,  1,  3,  8, 11,  6, 15, 13,
+];
+
+/// Message word selected at each step of the right line
+#[cfg(feature = "small-hash")]
+const WORD_RIGHT: [u8; 80] = [
+     5, 14,  7,  0,  9,  2, 11,  4, 13,  6, 15,  8,  1, 10,  3, 12,
+     6, 11,  3,  7,  0, 13,  5, 10, 14, 15,  8, 12,  4,  9,  1,  2,
+    15,  5,  1,  3,  7, 14,  6,  9, 11,  8, 12,  2, 10,  0,  4, 13,
+     8,  6,  4,  1,  3, 11, 15,  0,  5, 12,  2, 13,  9,  7, 10, 14,
+    12, 15, 10,  4,  1,  5,  8,  7,  6,  2, 13, 14,  0,  3,  9, 11,
+];
+
+/// Rotation applied at each step of the left line
+#[cfg(feature = "small-hash")]
+const SHIFT_LEFT: [u8; 80] = [
+    11, 14, 15, 12,  5,  8,  7,  9, 11, 13, 14, 15,  6,  7,  9,  8,
+     7,  6,  8, 13, 11,  9,  7, 15,  7, 12, 15,  9, 11,  7, 13, 12,
+    11, 13,  6,  7, 14,  9, 13, 15, 14,  8, 13,  6,  5, 12,  7,  5,
+    11, 12, 14, 15, 14, 15,  9,  8,  9, 14,  5,  6,  8,  6,  5, 12,
+     9, 15,  5, 11,  6,  8, 13, 12,  5, 12, 13, 14, 11,  8,  5,  6,
+];
+
+/// Rotation applied at each step of the right line
+#[cfg(feature = "small-hash")]
+const SHIFT_RIGHT: [u8; 80] = [
+     8,  9,  9, 11, 13, 15, 15,  5,  7,  7,  8, 11, 14, 14, 12,  6,
+     9, 13, 15,  7, 12,  8,  9, 11,  7,  7, 12,  7,  6, 15, 13, 11,
+     9,  7, 15, 11,  8,  6,  6, 14, 12, 13,  5, 14, 13, 13,  7,  5,
+    15,  5,  8, 11, 14, 14,  6, 14,  6,  9, 12,  9, 12,  5, 15,  8,
+     8,  5, 12,  9, 12,  5, 14,  6,  8, 13,  6,  5, 15, 13, 11, 11,
+];
+
+/// Round constants of the left and right lines
+#[cfg(feature = "small-hash")]
+const K_LEFT: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+#[cfg(feature = "small-hash")]
+const K_RIGHT: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+
+/// Boolean function of round `round`; the right line uses them in reverse
+#[cfg(feature = "small-hash")]
+fn boolean(round: usize, x: u32, y: u32, z: u32) -> u32 {
+    match round {
+        0 => f1!(x, y, z),
+        1 => f2!(x, y, z),
+        2 => f3!(x, y, z),
+        3 => f4!(x, y, z),
+        _ => f5!(x, y, z),
+    }
+}
+
+/// Runs the RIPEMD160 compression function, updating `state` with one
+/// 64-byte message block. This is what the engine applies to each full block
+/// of input; no padding or length encoding is done here.
+#[cfg(feature = "small-hash")]
+pub fn compress(state: &mut [u32; 5], block: &[u8; BLOCK_SIZE]) {
+    let mut w = [0u32; 16];
+    for (w_val, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *w_val = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
     }
+
+    let (mut la, mut lb, mut lc, mut ld, mut le) = (state[0], state[1], state[2], state[3], state[4]);
+    let (mut ra, mut rb, mut rc, mut rd, mut re) = (la, lb, lc, ld, le);
+
+    for i in 0..80 {
+        let round = i / 16;
+
+        let t = circular_lshift32!(
+            SHIFT_LEFT[i] as u32,
+            la.wrapping_add(boolean(round, lb, lc, ld))
+                .wrapping_add(w[WORD_LEFT[i] as usize])
+                .wrapping_add(K_LEFT[round])
+        ).wrapping_add(le);
+        la = le;
+        le = ld;
+        ld = circular_lshift32!(10, lc);
+        lc = lb;
+        lb = t;
+
+        let t = circular_lshift32!(
+            SHIFT_RIGHT[i] as u32,
+            ra.wrapping_add(boolean(4 - round, rb, rc, rd))
+                .wrapping_add(w[WORD_RIGHT[i] as usize])
+                .wrapping_add(K_RIGHT[round])
+        ).wrapping_add(re);
+        ra = re;
+        re = rd;
+        rd = circular_lshift32!(10, rc);
+        rc = rb;
+        rb = t;
+    }
+
+    let t = state[1].wrapping_add(lc).wrapping_add(rd);
+    state[1] = state[2].wrapping_add(ld).wrapping_add(re);
+    state[2] = state[3].wrapping_add(le).wrapping_add(ra);
+    state[3] = state[4].wrapping_add(la).wrapping_add(rb);
+    state[4] = state[0].wrapping_add(lb).wrapping_add(rc);
+    state[0] = t;
 }
 
 #[cfg(test)]
 mod tests {
     use ripemd160;
+    #[cfg(feature = "hex")]
     use hex::{FromHex, ToHex};
     use Hash;
     use HashEngine;
 
+    #[cfg(feature = "hex")]
     #[derive(Clone)]
     struct Test {
         input: &'static str,
@@ -447,6 +491,7 @@ mod tests {
         output_str: &'static str,
     }
 
+    #[cfg(feature = "hex")]
     #[test]
     fn test() {
         let tests = vec![
@@ -501,7 +546,7 @@ mod tests {
 
         for test in tests {
             // Hash through high-level API, check hex encoding/decoding
-            let hash = ripemd160::Hash::hash(&test.input.as_bytes());
+            let hash = ripemd160::Hash::hash(test.input.as_bytes());
             assert_eq!(hash, ripemd160::Hash::from_hex(test.output_str).expect("parse hex"));
             assert_eq!(&hash[..], &test.output[..]);
             assert_eq!(&hash.to_hex(), &test.output_str);
@@ -517,6 +562,104 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "hex")]
+    #[test]
+    fn million_a() {
+        // The official "one million times 'a'" vector
+        let mut engine = ripemd160::Hash::engine();
+        let chunk = [b'a'; 1000];
+        for _ in 0..1000 {
+            engine.input(&chunk);
+        }
+        assert_eq!(
+            ripemd160::Hash::from_engine(engine).to_hex(),
+            "52783243c1697bdbe16d37f97f68f08325dc1528",
+        );
+    }
+
+    #[test]
+    fn compress() {
+        let msg = [0x61u8; 100];
+
+        // pad to two blocks: 0x80, zeroes, then the bit length little-endian
+        let mut padded = [0u8; 128];
+        padded[..100].copy_from_slice(&msg);
+        padded[100] = 0x80;
+        padded[120..].copy_from_slice(&::util::u64_to_array_le(8 * 100));
+
+        let mut state = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+        for block in padded.chunks(64) {
+            let mut buf = [0; 64];
+            buf.copy_from_slice(block);
+            ripemd160::compress(&mut state, &buf);
+        }
+
+        let mut out = [0; 20];
+        for (word, bytes) in state.iter().zip(out.chunks_mut(4)) {
+            bytes.copy_from_slice(&::util::u32_to_array_le(*word));
+        }
+        assert_eq!(out, ripemd160::Hash::hash(&msg));
+    }
+
+    #[test]
+    fn length_past_4gib() {
+        let iv = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+        let msg = [0x61u8; 100];
+
+        // Resume just below 4 GiB and cross it
+        let mut engine = ripemd160::HashEngine {
+            buffer: [0; 64],
+            h: iv,
+            length: (1 << 32) - 64,
+        };
+        engine.input(&msg);
+        assert_eq!(engine.n_bytes_hashed(), (1 << 32) + 36);
+
+        // The padding must carry the full 64-bit bit length
+        let mut padded = [0u8; 128];
+        padded[..100].copy_from_slice(&msg);
+        padded[100] = 0x80;
+        padded[120..].copy_from_slice(&::util::u64_to_array_le(8 * ((1 << 32) + 36)));
+
+        let mut state = iv;
+        for block in padded.chunks(64) {
+            let mut buf = [0; 64];
+            buf.copy_from_slice(block);
+            ripemd160::compress(&mut state, &buf);
+        }
+
+        let mut out = [0; 20];
+        for (word, bytes) in state.iter().zip(out.chunks_mut(4)) {
+            bytes.copy_from_slice(&::util::u32_to_array_le(*word));
+        }
+        assert_eq!(out, ripemd160::Hash::from_engine(engine));
+    }
+
+    #[test]
+    fn split_input() {
+        let mut msg = [0u8; 128];
+        for (i, b) in msg.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let expected = ripemd160::Hash::hash(&msg);
+
+        for split in 0..msg.len() + 1 {
+            let mut engine = ripemd160::Hash::engine();
+            engine.input(&msg[..split]);
+            assert_eq!(engine.n_bytes_hashed(), split as u64);
+            engine.input(&msg[split..]);
+            assert_eq!(engine.n_bytes_hashed(), msg.len() as u64);
+            assert_eq!(ripemd160::Hash::from_engine(engine), expected, "split at {}", split);
+        }
+
+        // 63 bytes then 2 crosses the first block boundary by one byte
+        let mut engine = ripemd160::Hash::engine();
+        engine.input(&msg[..63]);
+        engine.input(&msg[63..65]);
+        assert_eq!(engine.n_bytes_hashed(), 65);
+        assert_eq!(ripemd160::Hash::from_engine(engine), ripemd160::Hash::hash(&msg[..65]));
+    }
+
     #[cfg(feature="serde")]
     #[test]
     fn ripemd_serde() {
@@ -574,4 +717,24 @@ mod benches {
         bh.bytes = bytes.len() as u64;
     }
 
+    #[bench]
+    pub fn ripemd160_32b(bh: & mut Bencher) {
+        // The RIPEMD160 half of HASH160
+        let bytes = [1u8; 32];
+        bh.iter( || {
+            ripemd160::Hash::hash(&bytes)
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn ripemd160_compress(bh: & mut Bencher) {
+        let mut state = [0u32; 5];
+        let block = [1u8; 64];
+        bh.iter( || {
+            ripemd160::compress(&mut state, &block);
+        });
+        bh.bytes = 64;
+    }
+
 }
@@ -37,7 +37,7 @@ macro_rules! serde_impl(
                             if let Ok(hex) = ::std::str::from_utf8(v) {
                                 $t::from_hex(hex).map_err(E::custom)
                             } else {
-                                return Err(E::invalid_value($crate::serde::de::Unexpected::Bytes(v), &self));
+                                Err(E::invalid_value($crate::serde::de::Unexpected::Bytes(v), &self))
                             }
                         }
 
@@ -84,3 +84,57 @@ macro_rules! serde_impl(
 macro_rules! serde_impl(
     ($t:ident, $len:expr) => ()
 );
+
+/// Implements `schemars::JsonSchema` for a hash type, describing the hex
+/// string of `2 * $len` lowercase digits that `serde_impl!` serializes to in
+/// human-readable formats. The schema is inlined rather than referenced,
+/// since every hash type is named `Hash`.
+#[macro_export]
+#[cfg(feature="schemars")]
+macro_rules! schemars_impl(
+    ($t:ident, $len:expr) => (
+        schemars_impl!($t, $len, );
+    );
+    ($t:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::schemars::JsonSchema for $t<$($gen),*> {
+            fn is_referenceable() -> bool {
+                false
+            }
+
+            fn schema_name() -> String {
+                concat!(module_path!(), "::", stringify!($t)).to_owned()
+            }
+
+            fn json_schema(_: &mut $crate::schemars::gen::SchemaGenerator) -> $crate::schemars::schema::Schema {
+                use $crate::schemars::schema::{InstanceType, Metadata, SchemaObject, StringValidation};
+
+                let description = if $t::<$($gen),*>::DISPLAY_BACKWARD {
+                    format!("{} bytes as lowercase hex, in reverse byte order", $len)
+                } else {
+                    format!("{} bytes as lowercase hex", $len)
+                };
+                SchemaObject {
+                    instance_type: Some(InstanceType::String.into()),
+                    metadata: Some(Box::new(Metadata {
+                        description: Some(description),
+                        ..Default::default()
+                    })),
+                    string: Some(Box::new(StringValidation {
+                        max_length: Some(2 * $len),
+                        min_length: Some(2 * $len),
+                        pattern: Some(format!("^[0-9a-f]{{{}}}$", 2 * $len)),
+                    })),
+                    ..Default::default()
+                }.into()
+            }
+        }
+    )
+);
+
+/// Does an "empty" schemars implementation for the configuration without schemars feature
+#[macro_export]
+#[cfg(not(feature="schemars"))]
+macro_rules! schemars_impl(
+    ($t:ident, $len:expr) => ();
+    ($t:ident, $len:expr, $($gen:ident: $gent:ident),*) => ();
+);
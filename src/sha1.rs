@@ -14,7 +14,7 @@
 
 //! # SHA1
 
-use core::{cmp, str};
+use core::cmp;
 
 use HashEngine as EngineTrait;
 use Hash as HashTrait;
@@ -28,7 +28,7 @@ const BLOCK_SIZE: usize = 64;
 pub struct HashEngine {
     buffer: [u8; BLOCK_SIZE],
     h: [u32; 5],
-    length: usize,
+    length: u64, // bytes processed so far
 }
 
 impl Default for HashEngine {
@@ -65,6 +65,8 @@ impl EngineTrait for HashEngine {
     engine_input_impl!();
 }
 
+engine_extend_impl!(HashEngine);
+
 /// Output of the SHA1 hash function
 #[derive(Copy, Clone, PartialEq, Eq, Default, PartialOrd, Ord, Hash)]
 pub struct Hash([u8; 20]);
@@ -74,14 +76,22 @@ hex_fmt_impl!(Display, Hash);
 hex_fmt_impl!(LowerHex, Hash);
 index_impl!(Hash);
 serde_impl!(Hash, 20);
+schemars_impl!(Hash, 20);
 borrow_slice_impl!(Hash);
-
-impl str::FromStr for Hash {
-    type Err = ::hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        ::hex::FromHex::from_hex(s)
-    }
-}
+as_ref_array_impl!(Hash, 20);
+bytes_eq_impl!(Hash, 20);
+from_array_impl!(Hash, 20);
+arbitrary_impl!(Hash, 20);
+quickcheck_impl!(Hash, 20);
+borsh_impl!(Hash, 20);
+defmt_impl!(Hash);
+rand_impl!(Hash, 20);
+bitxor_impl!(Hash, 20);
+try_from_impl!(Hash);
+hex_from_str_impl!(Hash);
+subtle_impl!(Hash);
+all_bytes_impl!(Hash, 20);
+const_array_impl!(Hash, 20);
 
 impl HashTrait for Hash {
     type Engine = HashEngine;
@@ -89,19 +99,19 @@ impl HashTrait for Hash {
 
     fn from_engine(mut e: HashEngine) -> Hash {
         // pad buffer with a single 1-bit then all 0s, until there are exactly 8 bytes remaining
-        let data_len = e.length as u64;
+        let data_len = e.length;
 
         let zeroes = [0; BLOCK_SIZE - 8];
         e.input(&[0x80]);
-        if e.length % BLOCK_SIZE > zeroes.len() {
+        if e.length % BLOCK_SIZE as u64 > zeroes.len() as u64 {
             e.input(&zeroes);
         }
-        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE);
+        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE as u64) as usize;
         e.input(&zeroes[..pad_length]);
-        debug_assert_eq!(e.length % BLOCK_SIZE, zeroes.len());
+        debug_assert_eq!(e.length % BLOCK_SIZE as u64, zeroes.len() as u64);
         
-        e.input(&util::u64_to_array_be(8 * data_len));
-        debug_assert_eq!(e.length % BLOCK_SIZE, 0);
+        e.input(&util::u64_to_array_be(util::bit_length(data_len)));
+        debug_assert_eq!(e.length % BLOCK_SIZE as u64, 0);
 
         Hash(e.midstate())
     }
@@ -127,51 +137,179 @@ impl HashTrait for Hash {
     }
 }
 
-impl HashEngine {
-    // Basic unoptimized algorithm from Wikipedia
-    fn process_block(&mut self) {
-        debug_assert_eq!(self.buffer.len(), BLOCK_SIZE);
+macro_rules! Ch( ($x:expr, $y:expr, $z:expr) => ($z ^ ($x & ($y ^ $z))) );
+macro_rules! Parity( ($x:expr, $y:expr, $z:expr) => ($x ^ $y ^ $z) );
+macro_rules! Maj( ($x:expr, $y:expr, $z:expr) => (($x & $y) | ($z & ($x | $y))) );
+
+// Message schedule over a 16-word window: replaces `w[i]` (which held the
+// word from 16 rounds earlier) with the word for the current round
+#[cfg(not(feature = "small-hash"))]
+macro_rules! schedule(
+    ($w:expr, $i:expr, $i3:expr, $i8:expr, $i14:expr) => ({
+        $w[$i] = circular_lshift32!(1, $w[$i3] ^ $w[$i8] ^ $w[$i14] ^ $w[$i]);
+        $w[$i]
+    })
+);
+
+#[cfg(not(feature = "small-hash"))]
+macro_rules! round(
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr, $k:expr, $w:expr) => (
+        $e = $e.wrapping_add(circular_lshift32!(5, $a)).wrapping_add($f).wrapping_add($k).wrapping_add($w);
+        $b = circular_lshift32!(30, $b);
+    );
+);
 
-        let mut w = [0u32; 80];
-        for (w_val, buff_bytes) in w.iter_mut().zip(self.buffer.chunks(4)) {
-            *w_val = util::slice_to_u32_be(buff_bytes);
+impl HashEngine {
+    // Unrolled, with the variables renamed each round instead of shifted
+    #[cfg(not(feature = "small-hash"))]
+    fn process_block(state: &mut [u32; 5], block: &[u8; BLOCK_SIZE]) {
+        let mut w = [0u32; 16];
+        for (w_val, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *w_val = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
         }
-        for i in 16..80 {
-            w[i] = circular_lshift32!(1, w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]);
+
+        let mut a = state[0];
+        let mut b = state[1];
+        let mut c = state[2];
+        let mut d = state[3];
+        let mut e = state[4];
+
+        // Rounds 0 to 19
+        round!(a, b, c, d, e, Ch!(b, c, d), 0x5a827999, w[0]);
+        round!(e, a, b, c, d, Ch!(a, b, c), 0x5a827999, w[1]);
+        round!(d, e, a, b, c, Ch!(e, a, b), 0x5a827999, w[2]);
+        round!(c, d, e, a, b, Ch!(d, e, a), 0x5a827999, w[3]);
+        round!(b, c, d, e, a, Ch!(c, d, e), 0x5a827999, w[4]);
+        round!(a, b, c, d, e, Ch!(b, c, d), 0x5a827999, w[5]);
+        round!(e, a, b, c, d, Ch!(a, b, c), 0x5a827999, w[6]);
+        round!(d, e, a, b, c, Ch!(e, a, b), 0x5a827999, w[7]);
+        round!(c, d, e, a, b, Ch!(d, e, a), 0x5a827999, w[8]);
+        round!(b, c, d, e, a, Ch!(c, d, e), 0x5a827999, w[9]);
+        round!(a, b, c, d, e, Ch!(b, c, d), 0x5a827999, w[10]);
+        round!(e, a, b, c, d, Ch!(a, b, c), 0x5a827999, w[11]);
+        round!(d, e, a, b, c, Ch!(e, a, b), 0x5a827999, w[12]);
+        round!(c, d, e, a, b, Ch!(d, e, a), 0x5a827999, w[13]);
+        round!(b, c, d, e, a, Ch!(c, d, e), 0x5a827999, w[14]);
+        round!(a, b, c, d, e, Ch!(b, c, d), 0x5a827999, w[15]);
+        round!(e, a, b, c, d, Ch!(a, b, c), 0x5a827999, schedule!(w, 0, 13, 8, 2));
+        round!(d, e, a, b, c, Ch!(e, a, b), 0x5a827999, schedule!(w, 1, 14, 9, 3));
+        round!(c, d, e, a, b, Ch!(d, e, a), 0x5a827999, schedule!(w, 2, 15, 10, 4));
+        round!(b, c, d, e, a, Ch!(c, d, e), 0x5a827999, schedule!(w, 3, 0, 11, 5));
+
+        // Rounds 20 to 39
+        round!(a, b, c, d, e, Parity!(b, c, d), 0x6ed9eba1, schedule!(w, 4, 1, 12, 6));
+        round!(e, a, b, c, d, Parity!(a, b, c), 0x6ed9eba1, schedule!(w, 5, 2, 13, 7));
+        round!(d, e, a, b, c, Parity!(e, a, b), 0x6ed9eba1, schedule!(w, 6, 3, 14, 8));
+        round!(c, d, e, a, b, Parity!(d, e, a), 0x6ed9eba1, schedule!(w, 7, 4, 15, 9));
+        round!(b, c, d, e, a, Parity!(c, d, e), 0x6ed9eba1, schedule!(w, 8, 5, 0, 10));
+        round!(a, b, c, d, e, Parity!(b, c, d), 0x6ed9eba1, schedule!(w, 9, 6, 1, 11));
+        round!(e, a, b, c, d, Parity!(a, b, c), 0x6ed9eba1, schedule!(w, 10, 7, 2, 12));
+        round!(d, e, a, b, c, Parity!(e, a, b), 0x6ed9eba1, schedule!(w, 11, 8, 3, 13));
+        round!(c, d, e, a, b, Parity!(d, e, a), 0x6ed9eba1, schedule!(w, 12, 9, 4, 14));
+        round!(b, c, d, e, a, Parity!(c, d, e), 0x6ed9eba1, schedule!(w, 13, 10, 5, 15));
+        round!(a, b, c, d, e, Parity!(b, c, d), 0x6ed9eba1, schedule!(w, 14, 11, 6, 0));
+        round!(e, a, b, c, d, Parity!(a, b, c), 0x6ed9eba1, schedule!(w, 15, 12, 7, 1));
+        round!(d, e, a, b, c, Parity!(e, a, b), 0x6ed9eba1, schedule!(w, 0, 13, 8, 2));
+        round!(c, d, e, a, b, Parity!(d, e, a), 0x6ed9eba1, schedule!(w, 1, 14, 9, 3));
+        round!(b, c, d, e, a, Parity!(c, d, e), 0x6ed9eba1, schedule!(w, 2, 15, 10, 4));
+        round!(a, b, c, d, e, Parity!(b, c, d), 0x6ed9eba1, schedule!(w, 3, 0, 11, 5));
+        round!(e, a, b, c, d, Parity!(a, b, c), 0x6ed9eba1, schedule!(w, 4, 1, 12, 6));
+        round!(d, e, a, b, c, Parity!(e, a, b), 0x6ed9eba1, schedule!(w, 5, 2, 13, 7));
+        round!(c, d, e, a, b, Parity!(d, e, a), 0x6ed9eba1, schedule!(w, 6, 3, 14, 8));
+        round!(b, c, d, e, a, Parity!(c, d, e), 0x6ed9eba1, schedule!(w, 7, 4, 15, 9));
+
+        // Rounds 40 to 59
+        round!(a, b, c, d, e, Maj!(b, c, d), 0x8f1bbcdc, schedule!(w, 8, 5, 0, 10));
+        round!(e, a, b, c, d, Maj!(a, b, c), 0x8f1bbcdc, schedule!(w, 9, 6, 1, 11));
+        round!(d, e, a, b, c, Maj!(e, a, b), 0x8f1bbcdc, schedule!(w, 10, 7, 2, 12));
+        round!(c, d, e, a, b, Maj!(d, e, a), 0x8f1bbcdc, schedule!(w, 11, 8, 3, 13));
+        round!(b, c, d, e, a, Maj!(c, d, e), 0x8f1bbcdc, schedule!(w, 12, 9, 4, 14));
+        round!(a, b, c, d, e, Maj!(b, c, d), 0x8f1bbcdc, schedule!(w, 13, 10, 5, 15));
+        round!(e, a, b, c, d, Maj!(a, b, c), 0x8f1bbcdc, schedule!(w, 14, 11, 6, 0));
+        round!(d, e, a, b, c, Maj!(e, a, b), 0x8f1bbcdc, schedule!(w, 15, 12, 7, 1));
+        round!(c, d, e, a, b, Maj!(d, e, a), 0x8f1bbcdc, schedule!(w, 0, 13, 8, 2));
+        round!(b, c, d, e, a, Maj!(c, d, e), 0x8f1bbcdc, schedule!(w, 1, 14, 9, 3));
+        round!(a, b, c, d, e, Maj!(b, c, d), 0x8f1bbcdc, schedule!(w, 2, 15, 10, 4));
+        round!(e, a, b, c, d, Maj!(a, b, c), 0x8f1bbcdc, schedule!(w, 3, 0, 11, 5));
+        round!(d, e, a, b, c, Maj!(e, a, b), 0x8f1bbcdc, schedule!(w, 4, 1, 12, 6));
+        round!(c, d, e, a, b, Maj!(d, e, a), 0x8f1bbcdc, schedule!(w, 5, 2, 13, 7));
+        round!(b, c, d, e, a, Maj!(c, d, e), 0x8f1bbcdc, schedule!(w, 6, 3, 14, 8));
+        round!(a, b, c, d, e, Maj!(b, c, d), 0x8f1bbcdc, schedule!(w, 7, 4, 15, 9));
+        round!(e, a, b, c, d, Maj!(a, b, c), 0x8f1bbcdc, schedule!(w, 8, 5, 0, 10));
+        round!(d, e, a, b, c, Maj!(e, a, b), 0x8f1bbcdc, schedule!(w, 9, 6, 1, 11));
+        round!(c, d, e, a, b, Maj!(d, e, a), 0x8f1bbcdc, schedule!(w, 10, 7, 2, 12));
+        round!(b, c, d, e, a, Maj!(c, d, e), 0x8f1bbcdc, schedule!(w, 11, 8, 3, 13));
+
+        // Rounds 60 to 79
+        round!(a, b, c, d, e, Parity!(b, c, d), 0xca62c1d6, schedule!(w, 12, 9, 4, 14));
+        round!(e, a, b, c, d, Parity!(a, b, c), 0xca62c1d6, schedule!(w, 13, 10, 5, 15));
+        round!(d, e, a, b, c, Parity!(e, a, b), 0xca62c1d6, schedule!(w, 14, 11, 6, 0));
+        round!(c, d, e, a, b, Parity!(d, e, a), 0xca62c1d6, schedule!(w, 15, 12, 7, 1));
+        round!(b, c, d, e, a, Parity!(c, d, e), 0xca62c1d6, schedule!(w, 0, 13, 8, 2));
+        round!(a, b, c, d, e, Parity!(b, c, d), 0xca62c1d6, schedule!(w, 1, 14, 9, 3));
+        round!(e, a, b, c, d, Parity!(a, b, c), 0xca62c1d6, schedule!(w, 2, 15, 10, 4));
+        round!(d, e, a, b, c, Parity!(e, a, b), 0xca62c1d6, schedule!(w, 3, 0, 11, 5));
+        round!(c, d, e, a, b, Parity!(d, e, a), 0xca62c1d6, schedule!(w, 4, 1, 12, 6));
+        round!(b, c, d, e, a, Parity!(c, d, e), 0xca62c1d6, schedule!(w, 5, 2, 13, 7));
+        round!(a, b, c, d, e, Parity!(b, c, d), 0xca62c1d6, schedule!(w, 6, 3, 14, 8));
+        round!(e, a, b, c, d, Parity!(a, b, c), 0xca62c1d6, schedule!(w, 7, 4, 15, 9));
+        round!(d, e, a, b, c, Parity!(e, a, b), 0xca62c1d6, schedule!(w, 8, 5, 0, 10));
+        round!(c, d, e, a, b, Parity!(d, e, a), 0xca62c1d6, schedule!(w, 9, 6, 1, 11));
+        round!(b, c, d, e, a, Parity!(c, d, e), 0xca62c1d6, schedule!(w, 10, 7, 2, 12));
+        round!(a, b, c, d, e, Parity!(b, c, d), 0xca62c1d6, schedule!(w, 11, 8, 3, 13));
+        round!(e, a, b, c, d, Parity!(a, b, c), 0xca62c1d6, schedule!(w, 12, 9, 4, 14));
+        round!(d, e, a, b, c, Parity!(e, a, b), 0xca62c1d6, schedule!(w, 13, 10, 5, 15));
+        round!(c, d, e, a, b, Parity!(d, e, a), 0xca62c1d6, schedule!(w, 14, 11, 6, 0));
+        round!(b, c, d, e, a, Parity!(c, d, e), 0xca62c1d6, schedule!(w, 15, 12, 7, 1));
+
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+        state[4] = state[4].wrapping_add(e);
+    }
+
+    // One loop iteration per round, for the `small-hash` feature
+    #[cfg(feature = "small-hash")]
+    fn process_block(state: &mut [u32; 5], block: &[u8; BLOCK_SIZE]) {
+        let mut w = [0u32; 16];
+        for (w_val, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *w_val = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
         }
 
-        let mut a = self.h[0];
-        let mut b = self.h[1];
-        let mut c = self.h[2];
-        let mut d = self.h[3];
-        let mut e = self.h[4];
-
-        for (i, &wi) in w.iter().enumerate() {
-            let (f, k) = match i {
-                 0...19 => ((b & c) | (!b & d), 0x5a827999),
-                20...39 => (b ^ c ^ d, 0x6ed9eba1),
-                40...59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
-                60...79 => (b ^ c ^ d, 0xca62c1d6),
-                _ => unreachable!()
-            };
+        let mut a = state[0];
+        let mut b = state[1];
+        let mut c = state[2];
+        let mut d = state[3];
+        let mut e = state[4];
 
-            let new_a = circular_lshift32!(5, a).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(wi);
+        for i in 0..80 {
+            if i >= 16 {
+                w[i % 16] = circular_lshift32!(1, w[(i + 13) % 16] ^ w[(i + 8) % 16] ^ w[(i + 2) % 16] ^ w[i % 16]);
+            }
+            let (f, k) = match i / 20 {
+                0 => (Ch!(b, c, d), 0x5a827999),
+                1 => (Parity!(b, c, d), 0x6ed9eba1),
+                2 => (Maj!(b, c, d), 0x8f1bbcdc),
+                _ => (Parity!(b, c, d), 0xca62c1d6),
+            };
+            let t = circular_lshift32!(5, a).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(w[i % 16]);
             e = d;
             d = c;
             c = circular_lshift32!(30, b);
             b = a;
-            a = new_a;
+            a = t;
         }
 
-        self.h[0] = self.h[0].wrapping_add(a);
-        self.h[1] = self.h[1].wrapping_add(b);
-        self.h[2] = self.h[2].wrapping_add(c);
-        self.h[3] = self.h[3].wrapping_add(d);
-        self.h[4] = self.h[4].wrapping_add(e);
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+        state[4] = state[4].wrapping_add(e);
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "hex"))]
 mod tests {
     use sha1;
     use hex::{FromHex, ToHex};
@@ -222,11 +360,34 @@ mod tests {
                 ],
                 output_str: "de9f2c7fd25e1b3afad3e85a0bd17d9b100db4b3",
             },
+            // FIPS 180-2 examples, one and two blocks
+            Test {
+                input: "abc",
+                output: vec![
+                    0xa9, 0x99, 0x3e, 0x36,
+                    0x47, 0x06, 0x81, 0x6a,
+                    0xba, 0x3e, 0x25, 0x71,
+                    0x78, 0x50, 0xc2, 0x6c,
+                    0x9c, 0xd0, 0xd8, 0x9d,
+                ],
+                output_str: "a9993e364706816aba3e25717850c26c9cd0d89d",
+            },
+            Test {
+                input: "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                output: vec![
+                    0x84, 0x98, 0x3e, 0x44,
+                    0x1c, 0x3b, 0xd2, 0x6e,
+                    0xba, 0xae, 0x4a, 0xa1,
+                    0xf9, 0x51, 0x29, 0xe5,
+                    0xe5, 0x46, 0x70, 0xf1,
+                ],
+                output_str: "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            },
         ];
 
         for test in tests {
             // Hash through high-level API, check hex encoding/decoding
-            let hash = sha1::Hash::hash(&test.input.as_bytes());
+            let hash = sha1::Hash::hash(test.input.as_bytes());
             assert_eq!(hash, sha1::Hash::from_hex(test.output_str).expect("parse hex"));
             assert_eq!(&hash[..], &test.output[..]);
             assert_eq!(&hash.to_hex(), &test.output_str);
@@ -242,6 +403,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn million_a() {
+        let mut engine = sha1::Hash::engine();
+        let chunk = [b'a'; 1000];
+        for _ in 0..1000 {
+            engine.input(&chunk);
+        }
+        assert_eq!(
+            sha1::Hash::from_engine(engine).to_hex(),
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f",
+        );
+    }
+
     #[cfg(feature="serde")]
     #[test]
     fn sha1_serde() {
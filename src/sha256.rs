@@ -14,28 +14,41 @@
 
 //! # SHA256
 
-use core::{cmp, str};
+use core::{fmt, hash};
 
-use hex;
+#[cfg(not(feature = "fuzztarget"))] use core::cmp;
+#[cfg(feature = "hex")] use hex;
 use HashEngine as EngineTrait;
 use Hash as HashTrait;
 use Error;
+use sha256d;
 use util;
 
 const BLOCK_SIZE: usize = 64;
 
 /// Engine to compute SHA256 hash function
+///
+/// The engine is just a fixed 64-byte block buffer, the eight-word state and
+/// a `u64` byte count, so it never allocates and has the same size on every
+/// target.
 #[derive(Clone)]
 pub struct HashEngine {
     buffer: [u8; BLOCK_SIZE],
     h: [u32; 8],
-    length: usize,
+    length: u64, // bytes processed so far
 }
 
+/// Length of a serialized engine without its buffered input: the state
+/// words and the byte count
+const ENGINE_STATE_LEN: usize = 32 + 8;
+
+/// SHA256 initial state
+const IV: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+
 impl Default for HashEngine {
     fn default() -> Self {
         HashEngine {
-            h: [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19],
+            h: IV,
             length: 0,
             buffer: [0; BLOCK_SIZE],
         }
@@ -66,23 +79,33 @@ impl EngineTrait for HashEngine {
     engine_input_impl!();
 }
 
+engine_extend_impl!(HashEngine);
+
 /// Output of the SHA256 hash function
 #[derive(Copy, Clone, PartialEq, Eq, Default, PartialOrd, Ord, Hash)]
 pub struct Hash([u8; 32]);
 
-impl str::FromStr for Hash {
-    type Err = ::hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        ::hex::FromHex::from_hex(s)
-    }
-}
-
 hex_fmt_impl!(Debug, Hash);
 hex_fmt_impl!(Display, Hash);
 hex_fmt_impl!(LowerHex, Hash);
 index_impl!(Hash);
 serde_impl!(Hash, 32);
+schemars_impl!(Hash, 32);
 borrow_slice_impl!(Hash);
+as_ref_array_impl!(Hash, 32);
+bytes_eq_impl!(Hash, 32);
+from_array_impl!(Hash, 32);
+arbitrary_impl!(Hash, 32);
+quickcheck_impl!(Hash, 32);
+borsh_impl!(Hash, 32);
+defmt_impl!(Hash);
+rand_impl!(Hash, 32);
+bitxor_impl!(Hash, 32);
+try_from_impl!(Hash);
+hex_from_str_impl!(Hash);
+subtle_impl!(Hash);
+all_bytes_impl!(Hash, 32);
+const_array_impl!(Hash, 32);
 
 impl HashTrait for Hash {
     type Engine = HashEngine;
@@ -91,19 +114,19 @@ impl HashTrait for Hash {
     #[cfg(not(feature = "fuzztarget"))]
     fn from_engine(mut e: HashEngine) -> Hash {
         // pad buffer with a single 1-bit then all 0s, until there are exactly 8 bytes remaining
-        let data_len = e.length as u64;
+        let data_len = e.length;
 
         let zeroes = [0; BLOCK_SIZE - 8];
         e.input(&[0x80]);
-        if e.length % BLOCK_SIZE > zeroes.len() {
+        if e.length % BLOCK_SIZE as u64 > zeroes.len() as u64 {
             e.input(&zeroes);
         }
-        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE);
+        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE as u64) as usize;
         e.input(&zeroes[..pad_length]);
-        debug_assert_eq!(e.length % BLOCK_SIZE, zeroes.len());
+        debug_assert_eq!(e.length % BLOCK_SIZE as u64, zeroes.len() as u64);
 
-        e.input(&util::u64_to_array_be(8 * data_len));
-        debug_assert_eq!(e.length % BLOCK_SIZE, 0);
+        e.input(&util::u64_to_array_be(util::bit_length(data_len)));
+        debug_assert_eq!(e.length % BLOCK_SIZE as u64, 0);
 
         Hash(e.midstate().into_inner())
     }
@@ -134,6 +157,133 @@ impl HashTrait for Hash {
     }
 }
 
+impl Hash {
+    /// Finalizes `engine` and compares the result with `expected` in
+    /// constant time.
+    pub fn verify_stream(engine: HashEngine, expected: &Hash) -> bool {
+        ::cmp::fixed_time_eq(&Hash::from_engine(engine)[..], &expected[..])
+    }
+
+    /// Computes the BIP340 tagged hash `SHA256(SHA256(tag) || SHA256(tag) || msg)`.
+    ///
+    /// With the `std` feature, the midstate after the two tag hashes is
+    /// cached per thread and tag, keyed on the exact tag bytes, so that
+    /// repeating a tag only costs hashing `msg`. At most `TAG_CACHE_SIZE` tags
+    /// are cached on each thread; further ones are recomputed on every call.
+    pub fn hash_tagged(tag: &[u8], msg: &[u8]) -> Hash {
+        Hash::hash_after_tag(tag_midstate(tag), msg)
+    }
+
+    /// Computes the SHA256 of `data` in a `const fn`, so that hashes of fixed
+    /// data can be constants. This always uses the portable compression
+    /// function and is slower than `Hash::hash` at runtime.
+    pub const fn const_hash(data: &[u8]) -> Hash {
+        Hash(state_to_bytes(hash_const(data)))
+    }
+
+    /// Computes both `SHA256(data)` and `SHA256(SHA256(data))`, hashing
+    /// `data` only once.
+    pub fn hash_and_double(data: &[u8]) -> (Hash, sha256d::Hash) {
+        let single = Hash::hash(data);
+        let double = sha256d::Hash::from_inner(Hash::hash(&single[..]).0);
+        (single, double)
+    }
+
+    /// The BIP341 tagged hash with tag "TapLeaf", using a precomputed midstate
+    pub fn tap_leaf(msg: &[u8]) -> Hash {
+        Hash::hash_after_tag(TAP_LEAF_MIDSTATE, msg)
+    }
+
+    /// The BIP341 tagged hash with tag "TapBranch", using a precomputed midstate
+    pub fn tap_branch(msg: &[u8]) -> Hash {
+        Hash::hash_after_tag(TAP_BRANCH_MIDSTATE, msg)
+    }
+
+    /// The BIP341 tagged hash with tag "TapTweak", using a precomputed midstate
+    pub fn tap_tweak(msg: &[u8]) -> Hash {
+        Hash::hash_after_tag(TAP_TWEAK_MIDSTATE, msg)
+    }
+
+    /// The BIP341 tagged hash with tag "TapSighash", using a precomputed midstate
+    pub fn tap_sighash(msg: &[u8]) -> Hash {
+        Hash::hash_after_tag(TAP_SIGHASH_MIDSTATE, msg)
+    }
+
+    /// Hashes `msg` starting from the midstate after the two tag hashes
+    fn hash_after_tag(midstate: Midstate, msg: &[u8]) -> Hash {
+        let mut engine = HashEngine::from_midstate(midstate, 64);
+        engine.input(msg);
+        Hash::from_engine(engine)
+    }
+}
+
+const TAP_LEAF_MIDSTATE: Midstate = Midstate::hash_tag(b"TapLeaf");
+const TAP_BRANCH_MIDSTATE: Midstate = Midstate::hash_tag(b"TapBranch");
+const TAP_TWEAK_MIDSTATE: Midstate = Midstate::hash_tag(b"TapTweak");
+const TAP_SIGHASH_MIDSTATE: Midstate = Midstate::hash_tag(b"TapSighash");
+
+/// Maximum number of distinct tags whose midstates `Hash::hash_tagged` caches
+/// on each thread
+#[cfg(any(test, feature = "std"))]
+pub const TAG_CACHE_SIZE: usize = 256;
+
+/// The midstate after inputting `SHA256(tag)` twice
+fn compute_tag_midstate(tag: &[u8]) -> Midstate {
+    let tag_hash = Hash::hash(tag);
+    let mut engine = Hash::engine();
+    engine.input(&tag_hash[..]);
+    engine.input(&tag_hash[..]);
+    engine.midstate()
+}
+
+#[cfg(not(any(test, feature = "std")))]
+fn tag_midstate(tag: &[u8]) -> Midstate {
+    compute_tag_midstate(tag)
+}
+
+#[cfg(any(test, feature = "std"))]
+fn tag_midstate(tag: &[u8]) -> Midstate {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    thread_local! {
+        static CACHE: RefCell<HashMap<Vec<u8>, Midstate>> = RefCell::new(HashMap::new());
+    }
+
+    CACHE.with(|cache| {
+        if let Some(midstate) = cache.borrow().get(tag) {
+            return *midstate;
+        }
+        let midstate = compute_tag_midstate(tag);
+        let mut map = cache.borrow_mut();
+        if map.len() < TAG_CACHE_SIZE {
+            map.insert(tag.to_vec(), midstate);
+        }
+        midstate
+    })
+}
+
+/// Adapter exposing a SHA256 engine as a `core::hash::Hasher`, so that it
+/// can back a `BuildHasher` (e.g. `BuildHasherDefault<sha256::Hasher>`).
+///
+/// `finish` returns the first 8 bytes of the digest, read as a little-endian
+/// `u64`. Each call finalizes a copy of the engine, making this far slower
+/// than general-purpose hashers; only use it where a cryptographic hash is
+/// actually needed.
+#[derive(Clone, Default)]
+pub struct Hasher(HashEngine);
+
+impl hash::Hasher for Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.input(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let hash = Hash::from_engine(self.0.clone());
+        util::slice_to_u64_le(&hash[..8])
+    }
+}
+
 /// Output of the SHA256 hash function
 #[derive(Copy, Clone, PartialEq, Eq, Default, PartialOrd, Ord, Hash)]
 pub struct Midstate(pub [u8; 32]);
@@ -143,14 +293,12 @@ hex_fmt_impl!(Display, Midstate);
 hex_fmt_impl!(LowerHex, Midstate);
 index_impl!(Midstate);
 serde_impl!(Midstate, 32);
+schemars_impl!(Midstate, 32);
 borrow_slice_impl!(Midstate);
-
-impl str::FromStr for Midstate {
-    type Err = ::hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        ::hex::FromHex::from_hex(s)
-    }
-}
+as_ref_array_impl!(Midstate, 32);
+bytes_eq_impl!(Midstate, 32);
+defmt_impl!(Midstate);
+hex_from_str_impl!(Midstate);
 
 impl Midstate {
     /// Length of the midstate, in bytes.
@@ -162,10 +310,28 @@ impl Midstate {
     const DISPLAY_BACKWARD: bool = true;
 
     /// Construct a new midstate from the inner value.
-    pub fn from_inner(inner: [u8; 32]) -> Self {
+    pub const fn from_inner(inner: [u8; 32]) -> Self {
         Midstate(inner)
     }
 
+    /// Computes the midstate of a BIP340 tagged hash, i.e. the state after
+    /// compressing `SHA256(tag) || SHA256(tag)`, for use with
+    /// `HashEngine::from_midstate(midstate, 64)`.
+    ///
+    /// This is a `const fn`, so a `sha256t::Tag` can hold its midstate as
+    /// a compile-time constant.
+    pub const fn hash_tag(tag: &[u8]) -> Self {
+        let hash = Hash::const_hash(tag).0;
+        let mut block = [0; BLOCK_SIZE];
+        let mut i = 0;
+        while i < 32 {
+            block[i] = hash[i];
+            block[32 + i] = hash[i];
+            i += 1;
+        }
+        Midstate(state_to_bytes(HashEngine::compress_portable(IV, &block)))
+    }
+
     /// Copies a byte slice into the [Midstate] object.
     pub fn from_slice(sl: &[u8]) -> Result<Midstate, Error> {
         if sl.len() != Self::LEN {
@@ -183,6 +349,7 @@ impl Midstate {
     }
 }
 
+#[cfg(feature = "hex")]
 impl hex::FromHex for Midstate {
     fn from_byte_iter<I>(iter: I) -> Result<Self, hex::Error>
         where I: Iterator<Item=Result<u8, hex::Error>> +
@@ -200,6 +367,7 @@ macro_rules! Sigma0( ($x:expr) => (circular_lshift32!(30, $x) ^ circular_lshift3
 macro_rules! sigma0( ($x:expr) => (circular_lshift32!(25, $x) ^ circular_lshift32!(14, $x) ^ ($x >> 3)) );
 macro_rules! sigma1( ($x:expr) => (circular_lshift32!(15, $x) ^ circular_lshift32!(13, $x) ^ ($x >> 10)) );
 
+#[cfg(not(feature = "small-hash"))]
 macro_rules! round(
     // first round
     ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr, $g:expr, $h:expr, $k:expr, $w:expr) => (
@@ -218,10 +386,12 @@ macro_rules! round(
 impl HashEngine {
     /// Create a new [HashEngine] from a midstate.
     ///
+    /// `length` is the number of bytes already hashed into `midstate`.
+    ///
     /// Be aware that this method panics when [length] is
     /// not a multiple of the block size.
-    pub fn from_midstate(midstate: Midstate, length: usize) -> HashEngine {
-        assert!(length % BLOCK_SIZE == 0, "length is no multiple of the block size");
+    pub fn from_midstate(midstate: Midstate, length: u64) -> HashEngine {
+        assert_eq!(length % BLOCK_SIZE as u64, 0, "length is no multiple of the block size");
 
         let mut ret = [0; 8];
         for (ret_val, midstate_bytes) in ret.iter_mut().zip(midstate[..].chunks(4)) {
@@ -231,27 +401,88 @@ impl HashEngine {
         HashEngine {
             buffer: [0; BLOCK_SIZE],
             h: ret,
-            length: length,
+            length,
         }
     }
 
-    // Algorithm copied from libsecp256k1
-    fn process_block(&mut self) {
-        debug_assert_eq!(self.buffer.len(), BLOCK_SIZE);
+    /// Serializes the full state of the engine, including input buffered
+    /// since the last block boundary, for `from_bytes` to resume from, e.g.
+    /// after a process restart.
+    ///
+    /// The format is the eight state words and the byte count, all
+    /// big-endian, followed by the `length % 64` buffered bytes.
+    #[cfg(any(test, feature = "std"))]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let buffered = (self.length % BLOCK_SIZE as u64) as usize;
+        let mut ret = Vec::with_capacity(ENGINE_STATE_LEN + buffered);
+        for word in &self.h {
+            ret.extend_from_slice(&word.to_be_bytes());
+        }
+        ret.extend_from_slice(&self.length.to_be_bytes());
+        ret.extend_from_slice(&self.buffer[..buffered]);
+        ret
+    }
+
+    /// Restores an engine serialized by `to_bytes`.
+    ///
+    /// Fails with `InvalidLength` if `bytes` is not exactly as long as the
+    /// byte count it contains implies.
+    pub fn from_bytes(bytes: &[u8]) -> Result<HashEngine, Error> {
+        if bytes.len() < ENGINE_STATE_LEN {
+            return Err(Error::InvalidLength(ENGINE_STATE_LEN, bytes.len()));
+        }
+        let mut h = [0; 8];
+        for (word, word_bytes) in h.iter_mut().zip(bytes[..32].chunks(4)) {
+            *word = util::slice_to_u32_be(word_bytes);
+        }
+        let mut length_bytes = [0; 8];
+        length_bytes.copy_from_slice(&bytes[32..ENGINE_STATE_LEN]);
+        let length = u64::from_be_bytes(length_bytes);
+
+        let buffered = &bytes[ENGINE_STATE_LEN..];
+        let expected = ENGINE_STATE_LEN + (length % BLOCK_SIZE as u64) as usize;
+        if bytes.len() != expected {
+            return Err(Error::InvalidLength(expected, bytes.len()));
+        }
+        let mut buffer = [0; BLOCK_SIZE];
+        buffer[..buffered.len()].copy_from_slice(buffered);
+        Ok(HashEngine { buffer, h, length })
+    }
+
+    fn process_block(state: &mut [u32; 8], block: &[u8; BLOCK_SIZE]) {
+        #[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64", all(target_arch = "aarch64", feature = "aarch64-sha2"))))]
+        {
+            if accel::available() {
+                // The CPU has just been checked to support the extensions used.
+                unsafe { accel::compress(state, block) };
+                return;
+            }
+        }
+        HashEngine::process_block_portable(state, block);
+    }
 
+    fn process_block_portable(state: &mut [u32; 8], block: &[u8; BLOCK_SIZE]) {
+        *state = HashEngine::compress_portable(*state, block);
+    }
+
+    // Algorithm copied from libsecp256k1
+    #[cfg(not(feature = "small-hash"))]
+    const fn compress_portable(state: [u32; 8], block: &[u8; BLOCK_SIZE]) -> [u32; 8] {
         let mut w = [0u32; 16];
-        for (w_val, buff_bytes) in w.iter_mut().zip(self.buffer.chunks(4)) {
-            *w_val = util::slice_to_u32_be(buff_bytes);
+        let mut i = 0;
+        while i < 16 {
+            w[i] = u32::from_be_bytes([block[4 * i], block[4 * i + 1], block[4 * i + 2], block[4 * i + 3]]);
+            i += 1;
         }
 
-        let mut a = self.h[0];
-        let mut b = self.h[1];
-        let mut c = self.h[2];
-        let mut d = self.h[3];
-        let mut e = self.h[4];
-        let mut f = self.h[5];
-        let mut g = self.h[6];
-        let mut h = self.h[7];
+        let mut a = state[0];
+        let mut b = state[1];
+        let mut c = state[2];
+        let mut d = state[3];
+        let mut e = state[4];
+        let mut f = state[5];
+        let mut g = state[6];
+        let mut h = state[7];
 
         round!(a, b, c, d, e, f, g, h, 0x428a2f98, w[0]);
         round!(h, a, b, c, d, e, f, g, 0x71374491, w[1]);
@@ -321,23 +552,693 @@ impl HashEngine {
         round!(c, d, e, f, g, h, a, b, 0xbef9a3f7, w[14], w[12], w[7], w[15]);
         round!(b, c, d, e, f, g, h, a, 0xc67178f2, w[15], w[13], w[8], w[0]);
 
-        self.h[0] = self.h[0].wrapping_add(a);
-        self.h[1] = self.h[1].wrapping_add(b);
-        self.h[2] = self.h[2].wrapping_add(c);
-        self.h[3] = self.h[3].wrapping_add(d);
-        self.h[4] = self.h[4].wrapping_add(e);
-        self.h[5] = self.h[5].wrapping_add(f);
-        self.h[6] = self.h[6].wrapping_add(g);
-        self.h[7] = self.h[7].wrapping_add(h);
+        [
+            state[0].wrapping_add(a),
+            state[1].wrapping_add(b),
+            state[2].wrapping_add(c),
+            state[3].wrapping_add(d),
+            state[4].wrapping_add(e),
+            state[5].wrapping_add(f),
+            state[6].wrapping_add(g),
+            state[7].wrapping_add(h),
+        ]
+    }
+
+    // One loop iteration per round, for the `small-hash` feature
+    #[cfg(feature = "small-hash")]
+    const fn compress_portable(state: [u32; 8], block: &[u8; BLOCK_SIZE]) -> [u32; 8] {
+        let mut w = [0u32; 16];
+        let mut i = 0;
+        while i < 16 {
+            w[i] = u32::from_be_bytes([block[4 * i], block[4 * i + 1], block[4 * i + 2], block[4 * i + 3]]);
+            i += 1;
+        }
+
+        // The working variables a to h
+        let mut v = state;
+        let mut i = 0;
+        while i < 64 {
+            if i >= 16 {
+                w[i % 16] = w[i % 16]
+                    .wrapping_add(sigma1!(w[(i + 14) % 16]))
+                    .wrapping_add(w[(i + 9) % 16])
+                    .wrapping_add(sigma0!(w[(i + 1) % 16]));
+            }
+            let t1 = v[7].wrapping_add(Sigma1!(v[4])).wrapping_add(Ch!(v[4], v[5], v[6])).wrapping_add(K[i]).wrapping_add(w[i % 16]);
+            let t2 = Sigma0!(v[0]).wrapping_add(Maj!(v[0], v[1], v[2]));
+            v = [t1.wrapping_add(t2), v[0], v[1], v[2], v[3].wrapping_add(t1), v[4], v[5], v[6]];
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < 8 {
+            v[i] = state[i].wrapping_add(v[i]);
+            i += 1;
+        }
+        v
+    }
+}
+
+/// SHA256 of `data` as a final state, computed with the portable compression
+/// function only so that it can be evaluated at compile time
+const fn hash_const(data: &[u8]) -> [u32; 8] {
+    let mut state = IV;
+    let mut block = [0; BLOCK_SIZE];
+    let mut i = 0;
+    while i < data.len() {
+        block[i % BLOCK_SIZE] = data[i];
+        i += 1;
+        if i % BLOCK_SIZE == 0 {
+            state = HashEngine::compress_portable(state, &block);
+        }
+    }
+
+    // Pad with a 1 bit, zeros and the 64-bit length in bits, spilling into
+    // a second block if fewer than 9 bytes are left in this one
+    let mut pos = data.len() % BLOCK_SIZE;
+    block[pos] = 0x80;
+    pos += 1;
+    if pos > BLOCK_SIZE - 8 {
+        while pos < BLOCK_SIZE {
+            block[pos] = 0;
+            pos += 1;
+        }
+        state = HashEngine::compress_portable(state, &block);
+        pos = 0;
+    }
+    while pos < BLOCK_SIZE - 8 {
+        block[pos] = 0;
+        pos += 1;
+    }
+    let bits = (8 * data.len() as u64).to_be_bytes();
+    let mut i = 0;
+    while i < 8 {
+        block[BLOCK_SIZE - 8 + i] = bits[i];
+        i += 1;
+    }
+    HashEngine::compress_portable(state, &block)
+}
+
+/// Serializes a state as big-endian words, as `HashEngine::midstate` does
+const fn state_to_bytes(state: [u32; 8]) -> [u8; 32] {
+    let mut ret = [0; 32];
+    let mut i = 0;
+    while i < 8 {
+        let word = state[i].to_be_bytes();
+        ret[4 * i] = word[0];
+        ret[4 * i + 1] = word[1];
+        ret[4 * i + 2] = word[2];
+        ret[4 * i + 3] = word[3];
+        i += 1;
+    }
+    ret
+}
+
+/// Round constants of SHA256, used by the vectorized implementations and the
+/// compact `small-hash` rounds, which cannot have them inlined into unrolled
+/// rounds.
+#[cfg(any(
+    feature = "simd",
+    feature = "small-hash",
+    all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64", all(target_arch = "aarch64", feature = "aarch64-sha2"))),
+))]
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Compression using CPU extensions for SHA256, selected at runtime.
+///
+/// There is one backend per architecture: the SHA extensions on x86, and the
+/// ARMv8 SHA2 instructions on aarch64 (behind the `aarch64-sha2` feature,
+/// which needs Rust 1.72). Whether the CPU supports them is detected once and
+/// cached. Setting the `BITCOIN_HASHES_FORCE_PORTABLE` environment variable
+/// before the first hash is computed disables this path, so that both
+/// implementations can be tested on the same machine.
+#[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64", all(target_arch = "aarch64", feature = "aarch64-sha2"))))]
+mod accel {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    pub use self::backend::compress;
+
+    const UNKNOWN: usize = 0;
+    const PORTABLE: usize = 1;
+    const ACCELERATED: usize = 2;
+
+    static BACKEND: AtomicUsize = AtomicUsize::new(UNKNOWN);
+
+    /// Whether `compress` may be called on this CPU.
+    pub fn available() -> bool {
+        detect_cached(&BACKEND, backend::detect)
+    }
+
+    /// Runs `detect` the first time, unless the portable code is forced, and
+    /// then returns the result stored in `cache`.
+    fn detect_cached(cache: &AtomicUsize, detect: fn() -> bool) -> bool {
+        match cache.load(Ordering::Relaxed) {
+            PORTABLE => false,
+            ACCELERATED => true,
+            _ => {
+                let detected = ::std::env::var_os("BITCOIN_HASHES_FORCE_PORTABLE").is_none() && detect();
+                cache.store(if detected { ACCELERATED } else { PORTABLE }, Ordering::Relaxed);
+                detected
+            }
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    mod backend {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::*;
+
+        use super::super::{BLOCK_SIZE, K};
+
+        pub fn detect() -> bool {
+            is_x86_feature_detected!("sha")
+                && is_x86_feature_detected!("sse2")
+                && is_x86_feature_detected!("ssse3")
+                && is_x86_feature_detected!("sse4.1")
+        }
+
+        /// Next four words of the message schedule, from the previous sixteen.
+        #[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+        unsafe fn schedule(w0: __m128i, w1: __m128i, w2: __m128i, w3: __m128i) -> __m128i {
+            let t1 = _mm_sha256msg1_epu32(w0, w1);
+            let t2 = _mm_alignr_epi8(w3, w2, 4);
+            _mm_sha256msg2_epu32(_mm_add_epi32(t1, t2), w3)
+        }
+
+        macro_rules! rounds4(
+            ($abef:ident, $cdgh:ident, $w:expr, $i:expr) => (
+                let kw = _mm_add_epi32($w, _mm_loadu_si128(K[4 * $i..].as_ptr() as *const __m128i));
+                $cdgh = _mm_sha256rnds2_epu32($cdgh, $abef, kw);
+                $abef = _mm_sha256rnds2_epu32($abef, $cdgh, _mm_shuffle_epi32(kw, 0x0e));
+            )
+        );
+
+        /// Processes one block into the state `h`.
+        ///
+        /// Unsafe because the CPU must support the extensions checked by `detect`.
+        #[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+        pub unsafe fn compress(h: &mut [u32; 8], block: &[u8; BLOCK_SIZE]) {
+            // byte-swaps each 32-bit word
+            let mask = _mm_set_epi64x(0x0c0d0e0f08090a0bu64 as i64, 0x0405060700010203u64 as i64);
+
+            // the instructions want the state as (a, b, e, f) and (c, d, g, h)
+            let dcba = _mm_loadu_si128(h.as_ptr() as *const __m128i);
+            let hgfe = _mm_loadu_si128(h[4..].as_ptr() as *const __m128i);
+            let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+            let efgh = _mm_shuffle_epi32(hgfe, 0x1b);
+            let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+            let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xf0);
+            let abef_save = abef;
+            let cdgh_save = cdgh;
+
+            let data = block.as_ptr() as *const __m128i;
+            let mut w0 = _mm_shuffle_epi8(_mm_loadu_si128(data), mask);
+            let mut w1 = _mm_shuffle_epi8(_mm_loadu_si128(data.add(1)), mask);
+            let mut w2 = _mm_shuffle_epi8(_mm_loadu_si128(data.add(2)), mask);
+            let mut w3 = _mm_shuffle_epi8(_mm_loadu_si128(data.add(3)), mask);
+
+            rounds4!(abef, cdgh, w0, 0);
+            rounds4!(abef, cdgh, w1, 1);
+            rounds4!(abef, cdgh, w2, 2);
+            rounds4!(abef, cdgh, w3, 3);
+            for i in 1..4 {
+                w0 = schedule(w0, w1, w2, w3);
+                rounds4!(abef, cdgh, w0, 4 * i);
+                w1 = schedule(w1, w2, w3, w0);
+                rounds4!(abef, cdgh, w1, 4 * i + 1);
+                w2 = schedule(w2, w3, w0, w1);
+                rounds4!(abef, cdgh, w2, 4 * i + 2);
+                w3 = schedule(w3, w0, w1, w2);
+                rounds4!(abef, cdgh, w3, 4 * i + 3);
+            }
+
+            abef = _mm_add_epi32(abef, abef_save);
+            cdgh = _mm_add_epi32(cdgh, cdgh_save);
+
+            let feba = _mm_shuffle_epi32(abef, 0x1b);
+            let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+            let dcba = _mm_blend_epi16(feba, dchg, 0xf0);
+            let hgef = _mm_alignr_epi8(dchg, feba, 8);
+            _mm_storeu_si128(h.as_mut_ptr() as *mut __m128i, dcba);
+            _mm_storeu_si128(h[4..].as_mut_ptr() as *mut __m128i, hgef);
+        }
+    }
+
+    /// Eight messages at once in the lanes of AVX2 vectors, see `hash_batch`.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub mod x8 {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::*;
+        use core::sync::atomic::AtomicUsize;
+
+        use super::super::{padded_block, padded_block_count, Hash, HashEngine, BLOCK_SIZE, K};
+        use util;
+
+        static AVX2: AtomicUsize = AtomicUsize::new(super::UNKNOWN);
+
+        /// Whether `hash_8x` may be called on this CPU.
+        pub fn available() -> bool {
+            super::detect_cached(&AVX2, || is_x86_feature_detected!("avx2"))
+        }
+
+        macro_rules! ror8(
+            ($x:expr, $n:expr) => (_mm256_or_si256(_mm256_srli_epi32($x, $n), _mm256_slli_epi32($x, 32 - $n)))
+        );
+
+        /// Hashes eight messages of any lengths; lanes whose message is
+        /// exhausted are masked out of the remaining blocks.
+        ///
+        /// Unsafe because the CPU must support AVX2, see `available`.
+        pub unsafe fn hash_8x(inputs: [&[u8]; 8]) -> [Hash; 8] {
+            let mut blocks = [0; 8];
+            for (count, input) in blocks.iter_mut().zip(inputs.iter()) {
+                *count = padded_block_count(input.len());
+            }
+            let max_blocks = blocks.iter().cloned().max().unwrap_or(0);
+
+            let iv = HashEngine::default().h;
+            let mut state = [[0u32; 8]; 8];
+            for (lanes, init) in state.iter_mut().zip(iv.iter()) {
+                *lanes = [*init; 8];
+            }
+
+            let mut block = [0; BLOCK_SIZE];
+            for n in 0..max_blocks {
+                let mut words = [[0u32; 8]; 16];
+                let mut active = [0u32; 8];
+                for lane in 0..8 {
+                    if n < blocks[lane] {
+                        padded_block(inputs[lane], n, &mut block);
+                        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+                            word[lane] = util::slice_to_u32_be(bytes);
+                        }
+                        active[lane] = 0xffffffff;
+                    }
+                }
+                compress_8x(&mut state, &words, active);
+            }
+
+            let mut ret = [Hash::default(); 8];
+            for (lane, hash) in ret.iter_mut().enumerate() {
+                for (word, lanes) in state.iter().enumerate() {
+                    hash.0[4 * word..4 * word + 4].copy_from_slice(&util::u32_to_array_be(lanes[lane]));
+                }
+            }
+            ret
+        }
+
+        #[target_feature(enable = "avx2")]
+        unsafe fn compress_8x(state: &mut [[u32; 8]; 8], words: &[[u32; 8]; 16], active: [u32; 8]) {
+            let mask = _mm256_loadu_si256(active.as_ptr() as *const __m256i);
+            let mut h = [_mm256_setzero_si256(); 8];
+            for (v, lanes) in h.iter_mut().zip(state.iter()) {
+                *v = _mm256_loadu_si256(lanes.as_ptr() as *const __m256i);
+            }
+            let mut w = [_mm256_setzero_si256(); 16];
+            for (v, lanes) in w.iter_mut().zip(words.iter()) {
+                *v = _mm256_loadu_si256(lanes.as_ptr() as *const __m256i);
+            }
+
+            let (mut a, mut b, mut c, mut d) = (h[0], h[1], h[2], h[3]);
+            let (mut e, mut f, mut g, mut hh) = (h[4], h[5], h[6], h[7]);
+            for (t, k) in K.iter().enumerate() {
+                if t >= 16 {
+                    let w15 = w[(t + 1) % 16];
+                    let w2 = w[(t + 14) % 16];
+                    let s0 = _mm256_xor_si256(_mm256_xor_si256(ror8!(w15, 7), ror8!(w15, 18)), _mm256_srli_epi32(w15, 3));
+                    let s1 = _mm256_xor_si256(_mm256_xor_si256(ror8!(w2, 17), ror8!(w2, 19)), _mm256_srli_epi32(w2, 10));
+                    w[t % 16] = _mm256_add_epi32(_mm256_add_epi32(w[t % 16], s0), _mm256_add_epi32(w[(t + 9) % 16], s1));
+                }
+                let big_s1 = _mm256_xor_si256(_mm256_xor_si256(ror8!(e, 6), ror8!(e, 11)), ror8!(e, 25));
+                let ch = _mm256_xor_si256(_mm256_and_si256(e, f), _mm256_andnot_si256(e, g));
+                let t1 = _mm256_add_epi32(
+                    _mm256_add_epi32(_mm256_add_epi32(hh, big_s1), _mm256_add_epi32(ch, w[t % 16])),
+                    _mm256_set1_epi32(*k as i32),
+                );
+                let big_s0 = _mm256_xor_si256(_mm256_xor_si256(ror8!(a, 2), ror8!(a, 13)), ror8!(a, 22));
+                let maj = _mm256_or_si256(_mm256_and_si256(a, b), _mm256_and_si256(c, _mm256_or_si256(a, b)));
+                let t2 = _mm256_add_epi32(big_s0, maj);
+
+                hh = g;
+                g = f;
+                f = e;
+                e = _mm256_add_epi32(d, t1);
+                d = c;
+                c = b;
+                b = a;
+                a = _mm256_add_epi32(t1, t2);
+            }
+
+            let out = [a, b, c, d, e, f, g, hh];
+            for ((lanes, old), new) in state.iter_mut().zip(h.iter()).zip(out.iter()) {
+                let sum = _mm256_add_epi32(*old, *new);
+                let merged = _mm256_or_si256(_mm256_and_si256(mask, sum), _mm256_andnot_si256(mask, *old));
+                _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, merged);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    mod backend {
+        use core::arch::aarch64::*;
+
+        use super::super::{BLOCK_SIZE, K};
+
+        pub fn detect() -> bool {
+            ::std::arch::is_aarch64_feature_detected!("sha2")
+        }
+
+        macro_rules! rounds4(
+            ($abcd:ident, $efgh:ident, $w:expr, $i:expr) => (
+                let kw = vaddq_u32($w, vld1q_u32(K[4 * $i..].as_ptr()));
+                let abcd_prev = $abcd;
+                $abcd = vsha256hq_u32(abcd_prev, $efgh, kw);
+                $efgh = vsha256h2q_u32($efgh, abcd_prev, kw);
+            )
+        );
+
+        /// Processes one block into the state `h`.
+        ///
+        /// Unsafe because the CPU must support the extensions checked by `detect`.
+        #[target_feature(enable = "sha2")]
+        pub unsafe fn compress(h: &mut [u32; 8], block: &[u8; BLOCK_SIZE]) {
+            let mut abcd = vld1q_u32(h.as_ptr());
+            let mut efgh = vld1q_u32(h[4..].as_ptr());
+            let abcd_save = abcd;
+            let efgh_save = efgh;
+
+            // loads four big-endian words
+            let load = |n: usize| vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block[16 * n..].as_ptr())));
+            let mut w0 = load(0);
+            let mut w1 = load(1);
+            let mut w2 = load(2);
+            let mut w3 = load(3);
+
+            rounds4!(abcd, efgh, w0, 0);
+            rounds4!(abcd, efgh, w1, 1);
+            rounds4!(abcd, efgh, w2, 2);
+            rounds4!(abcd, efgh, w3, 3);
+            for i in 1..4 {
+                w0 = vsha256su1q_u32(vsha256su0q_u32(w0, w1), w2, w3);
+                rounds4!(abcd, efgh, w0, 4 * i);
+                w1 = vsha256su1q_u32(vsha256su0q_u32(w1, w2), w3, w0);
+                rounds4!(abcd, efgh, w1, 4 * i + 1);
+                w2 = vsha256su1q_u32(vsha256su0q_u32(w2, w3), w0, w1);
+                rounds4!(abcd, efgh, w2, 4 * i + 2);
+                w3 = vsha256su1q_u32(vsha256su0q_u32(w3, w0), w1, w2);
+                rounds4!(abcd, efgh, w3, 4 * i + 3);
+            }
+
+            vst1q_u32(h.as_mut_ptr(), vaddq_u32(abcd, abcd_save));
+            vst1q_u32(h[4..].as_mut_ptr(), vaddq_u32(efgh, efgh_save));
+        }
+    }
+}
+
+/// SHA256 implementation chosen at runtime, as reported by `active_backend`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    /// The x86 SHA extensions (SHA-NI), used for all hashing
+    Shani,
+    /// The ARMv8 SHA2 instructions, used for all hashing
+    Armv8Sha2,
+    /// AVX2 without the SHA extensions; `hash_batch` and `hash_8x_64b` hash
+    /// eight messages at a time, while single messages use portable code
+    Avx2,
+    /// Portable Rust code only
+    Portable,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Backend::Shani => "sha-ni",
+            Backend::Armv8Sha2 => "armv8-sha2",
+            Backend::Avx2 => "avx2",
+            Backend::Portable => "portable",
+        })
+    }
+}
+
+/// Returns the SHA256 implementation that the runtime dispatcher selected
+/// on this CPU, e.g. to log it when investigating performance.
+///
+/// This runs the same one-off CPU detection as the first hash would, so it
+/// also honours `BITCOIN_HASHES_FORCE_PORTABLE`. Without `std`, or on targets
+/// with no accelerated code, it is always `Backend::Portable`.
+pub fn active_backend() -> Backend {
+    #[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        if accel::available() {
+            return Backend::Shani;
+        }
+        if accel::x8::available() {
+            return Backend::Avx2;
+        }
+    }
+    #[cfg(all(feature = "std", not(feature = "fuzztarget"), target_arch = "aarch64", feature = "aarch64-sha2"))]
+    {
+        if accel::available() {
+            return Backend::Armv8Sha2;
+        }
+    }
+    Backend::Portable
+}
+
+/// Number of 64-byte blocks in the padded message of `len` bytes.
+#[cfg(all(not(feature = "fuzztarget"), any(feature = "simd", all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))))]
+fn padded_block_count(len: usize) -> usize {
+    (len + 8) / BLOCK_SIZE + 1
+}
+
+/// Writes the `n`th block of the padded message `data` into `block`.
+#[cfg(all(not(feature = "fuzztarget"), any(feature = "simd", all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))))]
+fn padded_block(data: &[u8], n: usize, block: &mut [u8; BLOCK_SIZE]) {
+    let start = n * BLOCK_SIZE;
+    *block = [0; BLOCK_SIZE];
+    if start < data.len() {
+        let end = cmp::min(start + BLOCK_SIZE, data.len());
+        block[..end - start].copy_from_slice(&data[start..end]);
+    }
+    if data.len() >= start && data.len() < start + BLOCK_SIZE {
+        block[data.len() - start] = 0x80;
+    }
+    if n + 1 == padded_block_count(data.len()) {
+        block[BLOCK_SIZE - 8..].copy_from_slice(&util::u64_to_array_be(8 * data.len() as u64));
+    }
+}
+
+/// Hashes many independent messages, equivalent to calling `Hash::hash` on
+/// each of them.
+///
+/// On x86 CPUs with AVX2 but without the SHA extensions, which are faster
+/// still, the messages are hashed eight at a time in the lanes of 256-bit
+/// vectors. This is much faster for many short messages such as merkle tree
+/// nodes. Any remainder of fewer than eight messages, or all of them on other
+/// CPUs, are hashed one by one.
+#[cfg(any(test, feature = "std"))]
+pub fn hash_batch(inputs: &[&[u8]]) -> Vec<Hash> {
+    let mut ret = Vec::with_capacity(inputs.len());
+    #[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        if accel::x8::available() && !accel::available() {
+            for chunk in inputs.chunks_exact(8) {
+                let mut eight = [&[][..]; 8];
+                eight.copy_from_slice(chunk);
+                // AVX2 support has just been checked.
+                ret.extend_from_slice(&unsafe { accel::x8::hash_8x(eight) });
+            }
+        }
+    }
+    let done = ret.len();
+    ret.extend(inputs[done..].iter().map(|input| Hash::hash(input)));
+    ret
+}
+
+/// Hashes eight 64-byte messages, such as pairs of merkle tree nodes; see
+/// `hash_batch`.
+pub fn hash_8x_64b(inputs: &[[u8; 64]; 8]) -> [Hash; 8] {
+    #[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        if accel::x8::available() && !accel::available() {
+            let mut eight = [&[][..]; 8];
+            for (slice, input) in eight.iter_mut().zip(inputs.iter()) {
+                *slice = &input[..];
+            }
+            // AVX2 support has just been checked.
+            return unsafe { accel::x8::hash_8x(eight) };
+        }
+    }
+    let mut ret = [Hash::default(); 8];
+    for (hash, input) in ret.iter_mut().zip(inputs.iter()) {
+        *hash = Hash::hash(input);
+    }
+    ret
+}
+
+/// Hashes four independent messages at once.
+///
+/// On x86 targets with SSE2 the four messages are processed in the lanes of
+/// 128-bit vectors; messages may have different lengths, in which case lanes
+/// whose message is exhausted are masked out of the remaining rounds. On other
+/// targets this falls back to hashing each message in turn.
+#[cfg(feature = "simd")]
+pub fn hash_4x(inputs: [&[u8]; 4]) -> [Hash; 4] {
+    x4::hash_4x(inputs)
+}
+
+#[cfg(all(
+    feature = "simd",
+    not(feature = "fuzztarget"),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2",
+))]
+mod x4 {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use super::{padded_block, padded_block_count, Hash, BLOCK_SIZE, K};
+    use util;
+
+    macro_rules! ror4(
+        ($x:expr, $n:expr) => (_mm_or_si128(_mm_srli_epi32($x, $n), _mm_slli_epi32($x, 32 - $n)))
+    );
+
+    pub fn hash_4x(inputs: [&[u8]; 4]) -> [Hash; 4] {
+        let mut blocks = [0; 4];
+        for (count, input) in blocks.iter_mut().zip(inputs.iter()) {
+            *count = padded_block_count(input.len());
+        }
+        let max_blocks = blocks.iter().cloned().max().unwrap_or(0);
+
+        let iv = super::HashEngine::default().h;
+        let mut state = [[0u32; 4]; 8];
+        for (lanes, init) in state.iter_mut().zip(iv.iter()) {
+            *lanes = [*init; 4];
+        }
+
+        let mut block = [0; BLOCK_SIZE];
+        for n in 0..max_blocks {
+            let mut words = [[0u32; 4]; 16];
+            let mut active = [0u32; 4];
+            for lane in 0..4 {
+                if n < blocks[lane] {
+                    padded_block(inputs[lane], n, &mut block);
+                    for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+                        word[lane] = util::slice_to_u32_be(bytes);
+                    }
+                    active[lane] = 0xffffffff;
+                }
+            }
+            // SSE2 is statically enabled for this target, see the cfg on this module.
+            unsafe { compress_4x(&mut state, &words, active) };
+        }
+
+        let mut ret = [Hash::default(); 4];
+        for (lane, hash) in ret.iter_mut().enumerate() {
+            for (word, lanes) in state.iter().enumerate() {
+                hash.0[4 * word..4 * word + 4].copy_from_slice(&util::u32_to_array_be(lanes[lane]));
+            }
+        }
+        ret
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn compress_4x(state: &mut [[u32; 4]; 8], words: &[[u32; 4]; 16], active: [u32; 4]) {
+        let mask = _mm_loadu_si128(active.as_ptr() as *const __m128i);
+        let mut h = [_mm_setzero_si128(); 8];
+        for (v, lanes) in h.iter_mut().zip(state.iter()) {
+            *v = _mm_loadu_si128(lanes.as_ptr() as *const __m128i);
+        }
+        let mut w = [_mm_setzero_si128(); 16];
+        for (v, lanes) in w.iter_mut().zip(words.iter()) {
+            *v = _mm_loadu_si128(lanes.as_ptr() as *const __m128i);
+        }
+
+        let (mut a, mut b, mut c, mut d) = (h[0], h[1], h[2], h[3]);
+        let (mut e, mut f, mut g, mut hh) = (h[4], h[5], h[6], h[7]);
+        for (t, k) in K.iter().enumerate() {
+            if t >= 16 {
+                let w15 = w[(t + 1) % 16];
+                let w2 = w[(t + 14) % 16];
+                let s0 = _mm_xor_si128(_mm_xor_si128(ror4!(w15, 7), ror4!(w15, 18)), _mm_srli_epi32(w15, 3));
+                let s1 = _mm_xor_si128(_mm_xor_si128(ror4!(w2, 17), ror4!(w2, 19)), _mm_srli_epi32(w2, 10));
+                w[t % 16] = _mm_add_epi32(_mm_add_epi32(w[t % 16], s0), _mm_add_epi32(w[(t + 9) % 16], s1));
+            }
+            let big_s1 = _mm_xor_si128(_mm_xor_si128(ror4!(e, 6), ror4!(e, 11)), ror4!(e, 25));
+            let ch = _mm_xor_si128(_mm_and_si128(e, f), _mm_andnot_si128(e, g));
+            let t1 = _mm_add_epi32(
+                _mm_add_epi32(_mm_add_epi32(hh, big_s1), _mm_add_epi32(ch, w[t % 16])),
+                _mm_set1_epi32(*k as i32),
+            );
+            let big_s0 = _mm_xor_si128(_mm_xor_si128(ror4!(a, 2), ror4!(a, 13)), ror4!(a, 22));
+            let maj = _mm_or_si128(_mm_and_si128(a, b), _mm_and_si128(c, _mm_or_si128(a, b)));
+            let t2 = _mm_add_epi32(big_s0, maj);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = _mm_add_epi32(d, t1);
+            d = c;
+            c = b;
+            b = a;
+            a = _mm_add_epi32(t1, t2);
+        }
+
+        let out = [a, b, c, d, e, f, g, hh];
+        for ((lanes, old), new) in state.iter_mut().zip(h.iter()).zip(out.iter()) {
+            let sum = _mm_add_epi32(*old, *new);
+            let merged = _mm_or_si128(_mm_and_si128(mask, sum), _mm_andnot_si128(mask, *old));
+            _mm_storeu_si128(lanes.as_mut_ptr() as *mut __m128i, merged);
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "simd",
+    any(
+        feature = "fuzztarget",
+        not(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2")),
+    ),
+))]
+mod x4 {
+    use super::Hash;
+    use Hash as HashTrait;
+
+    pub fn hash_4x(inputs: [&[u8]; 4]) -> [Hash; 4] {
+        [
+            Hash::hash(inputs[0]),
+            Hash::hash(inputs[1]),
+            Hash::hash(inputs[2]),
+            Hash::hash(inputs[3]),
+        ]
     }
 }
 
 #[cfg(test)]
 mod tests {
     use sha256;
+    #[cfg(feature = "hex")]
     use hex::{FromHex, ToHex};
     use {Hash, HashEngine};
 
+    #[cfg(feature = "hex")]
     #[derive(Clone)]
     struct Test {
         input: &'static str,
@@ -345,6 +1246,7 @@ mod tests {
         output_str: &'static str,
     }
 
+    #[cfg(feature = "hex")]
     #[test]
     fn test() {
         let tests = vec![
@@ -383,7 +1285,7 @@ mod tests {
 
         for test in tests {
             // Hash through high-level API, check hex encoding/decoding
-            let hash = sha256::Hash::hash(&test.input.as_bytes());
+            let hash = sha256::Hash::hash(test.input.as_bytes());
             assert_eq!(hash, sha256::Hash::from_hex(test.output_str).expect("parse hex"));
             assert_eq!(&hash[..], &test.output[..]);
             assert_eq!(&hash.to_hex(), &test.output_str);
@@ -425,6 +1327,202 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "hex")]
+    #[test]
+    fn taproot_tags() {
+        // BIP341 wallet test vectors: the leaf hash of scriptPubKey 1's only
+        // leaf, and the tweaks of scriptPubKeys 0 (key path only) and 1
+        let script = Vec::<u8>::from_hex(
+            "c02220d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac"
+        ).unwrap();
+        let leaf = sha256::Hash::tap_leaf(&script);
+        assert_eq!(leaf.to_hex(), "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21");
+
+        let key = Vec::<u8>::from_hex(
+            "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d"
+        ).unwrap();
+        assert_eq!(
+            sha256::Hash::tap_tweak(&key).to_hex(),
+            "b86e7be8f39bab32a6f2c0443abbc210f0edac0e2c53d501b36b64437d9c6c70",
+        );
+        let mut key_and_root = Vec::<u8>::from_hex(
+            "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27"
+        ).unwrap();
+        key_and_root.extend_from_slice(&leaf[..]);
+        assert_eq!(
+            sha256::Hash::tap_tweak(&key_and_root).to_hex(),
+            "cbd8679ba636c1110ea247542cfbd964131a6be84f873f7f3b62a777528ed001",
+        );
+
+        // No standalone vectors for these two; values from an independent
+        // implementation. The branch combines two BIP341 leaf hashes.
+        let mut children = leaf[..].to_vec();
+        children.extend_from_slice(&Vec::<u8>::from_hex(
+            "f224a923cd0021ab202ab139cc56802ddb92dcfc172b9212261a539df79a112a"
+        ).unwrap());
+        assert_eq!(
+            sha256::Hash::tap_branch(&children).to_hex(),
+            "fb029f857fa679da91e733d52783d4b9cf71aa6e06c20abac437d4f3388e3eba",
+        );
+        assert_eq!(
+            sha256::Hash::tap_sighash(b"\x00abc").to_hex(),
+            "793b80d00f38b56d41275904831dc7db81b869a5c450bc8f14942ea38f991fc2",
+        );
+
+        for msg in &[&b""[..], b"abc", &[0xaa; 200]] {
+            assert_eq!(sha256::Hash::tap_leaf(msg), sha256::Hash::hash_tagged(b"TapLeaf", msg));
+            assert_eq!(sha256::Hash::tap_branch(msg), sha256::Hash::hash_tagged(b"TapBranch", msg));
+            assert_eq!(sha256::Hash::tap_tweak(msg), sha256::Hash::hash_tagged(b"TapTweak", msg));
+            assert_eq!(sha256::Hash::tap_sighash(msg), sha256::Hash::hash_tagged(b"TapSighash", msg));
+        }
+    }
+
+    #[test]
+    fn hash_tagged() {
+        fn explicit(tag: &[u8], msg: &[u8]) -> sha256::Hash {
+            let tag_hash = sha256::Hash::hash(tag);
+            let mut engine = sha256::Hash::engine();
+            engine.input(&tag_hash[..]);
+            engine.input(&tag_hash[..]);
+            engine.input(msg);
+            sha256::Hash::from_engine(engine)
+        }
+
+        for _ in 0..3 {
+            for msg in &[&b""[..], b"abc", &[0xaa; 200]] {
+                assert_eq!(sha256::Hash::hash_tagged(b"TapLeaf", msg), explicit(b"TapLeaf", msg));
+                assert_eq!(sha256::Hash::hash_tagged(b"TapBranch", msg), explicit(b"TapBranch", msg));
+                assert_eq!(sha256::Hash::hash_tagged(&[], msg), explicit(&[], msg));
+            }
+        }
+        assert!(sha256::Hash::hash_tagged(b"TapLeaf", b"") != sha256::Hash::hash_tagged(b"TapBranch", b""));
+        // keyed on the exact bytes, so a prefix is a different tag
+        assert!(sha256::Hash::hash_tagged(b"TapLea", b"") != sha256::Hash::hash_tagged(b"TapLeaf", b""));
+
+        // past the cache limit results are still correct
+        for i in 0..sha256::TAG_CACHE_SIZE + 10 {
+            let tag = format!("tag {}", i);
+            assert_eq!(sha256::Hash::hash_tagged(tag.as_bytes(), b"x"), explicit(tag.as_bytes(), b"x"));
+        }
+        assert_eq!(sha256::Hash::hash_tagged(b"TapLeaf", b"abc"), explicit(b"TapLeaf", b"abc"));
+    }
+
+    #[test]
+    fn hash_tag() {
+        const TAP_LEAF: sha256::Midstate = sha256::Midstate::hash_tag(b"TapLeaf");
+
+        let tag = sha256::Hash::hash(b"TapLeaf");
+        let mut engine = sha256::Hash::engine();
+        engine.input(&tag[..]);
+        engine.input(&tag[..]);
+        assert_eq!(TAP_LEAF, engine.midstate());
+    }
+
+    #[test]
+    fn engine_to_from_bytes() {
+        use Error;
+
+        let data: Vec<u8> = (0..200).collect();
+        let mut engine = sha256::Hash::engine();
+        engine.input(&data[..70]);
+
+        // A partial second block: state, count and 6 buffered bytes
+        let bytes = engine.to_bytes();
+        assert_eq!(bytes.len(), 40 + 6);
+        assert_eq!(&bytes[32..40], &70u64.to_be_bytes());
+        assert_eq!(&bytes[40..], &data[64..70]);
+
+        // As if after a restart, with only the bytes to go on
+        let mut resumed = sha256::HashEngine::from_bytes(&bytes).unwrap();
+        resumed.input(&data[70..]);
+        assert_eq!(sha256::Hash::from_engine(resumed), sha256::Hash::hash(&data));
+
+        // Fresh and block-aligned engines carry no buffer
+        let fresh = sha256::Hash::engine().to_bytes();
+        assert_eq!(fresh.len(), 40);
+        let resumed = sha256::HashEngine::from_bytes(&fresh).unwrap();
+        assert_eq!(sha256::Hash::from_engine(resumed), sha256::Hash::hash(&[]));
+        let mut engine = sha256::Hash::engine();
+        engine.input(&data[..128]);
+        assert_eq!(engine.to_bytes().len(), 40);
+        assert_eq!(&engine.to_bytes()[..32], &engine.midstate()[..]);
+
+        assert_eq!(sha256::HashEngine::from_bytes(&bytes[..39]).err(), Some(Error::InvalidLength(40, 39)));
+        assert_eq!(sha256::HashEngine::from_bytes(&bytes[..45]).err(), Some(Error::InvalidLength(46, 45)));
+        let mut long = bytes.clone();
+        long.push(0);
+        assert_eq!(sha256::HashEngine::from_bytes(&long).err(), Some(Error::InvalidLength(46, 47)));
+    }
+
+    #[test]
+    fn engine_no_alloc() {
+        assert_eq!(::core::mem::size_of::<sha256::HashEngine>(), 64 + 32 + 8);
+
+        let data = [0x5a; 1000];
+        // the first hash may allocate while detecting CPU features
+        let expected = sha256::Hash::hash(&data);
+
+        let before = ::tests::allocations();
+        let mut engine = sha256::Hash::engine();
+        for chunk in data.chunks(7) {
+            engine.input(chunk);
+        }
+        let copy = engine.clone();
+        let hash = sha256::Hash::from_engine(engine);
+        let midstate = copy.midstate();
+        assert_eq!(::tests::allocations(), before);
+
+        assert_eq!(hash, expected);
+        assert!(midstate != sha256::HashEngine::default().midstate());
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn const_hash() {
+        const ABC: sha256::Hash = sha256::Hash::const_hash(b"abc");
+        const ABC_BYTES: [u8; 32] = ABC.to_byte_array();
+        assert_eq!(ABC, sha256::Hash::hash(b"abc"));
+        assert_eq!(ABC_BYTES, sha256::Hash::hash(b"abc").into_inner());
+        assert_eq!(
+            ABC.to_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        );
+
+        // the const padding agrees with the engine's at every block boundary
+        let data = [0x5a; 200];
+        for len in 0..data.len() {
+            assert_eq!(sha256::Hash::const_hash(&data[..len]), sha256::Hash::hash(&data[..len]));
+        }
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn portable_vectors() {
+        // `const_hash` always goes through `compress_portable`, which is the
+        // unrolled or, with `small-hash`, the rolled rounds, even on CPUs
+        // where `hash` takes the accelerated path; so running the tests in
+        // both modes checks both compression functions against these.
+        let million_a = vec![b'a'; 1_000_000];
+        let vectors: [(&[u8], &str); 5] = [
+            (b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            (b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+            (
+                b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+                  hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
+            ),
+            (&million_a, "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"),
+        ];
+        for &(data, hex) in vectors.iter() {
+            assert_eq!(sha256::Hash::const_hash(data).to_hex(), hex);
+            assert_eq!(sha256::Hash::hash(data).to_hex(), hex);
+        }
+    }
+
     #[test]
     fn engine_with_state() {
         let mut engine = sha256::Hash::engine();
@@ -474,6 +1572,279 @@ mod tests {
         assert_eq!(hash, sha256::Hash(HASH_EXPECTED));
     }
 
+    #[cfg(feature = "hex")]
+    #[test]
+    fn length_past_4gib() {
+        // Resume just below 4 GiB and cross it; the padding must encode the
+        // full 64-bit length whatever the pointer width. Expected value from
+        // an independent implementation.
+        let iv = sha256::HashEngine::default().midstate();
+        let mut engine = sha256::HashEngine::from_midstate(iv, (1 << 32) - 64);
+        engine.input(&[b'a'; 100]);
+        assert_eq!(engine.length, (1 << 32) + 36);
+        assert_eq!(
+            sha256::Hash::from_engine(engine).to_hex(),
+            "9366e6de39e99913e2d069f96ba5f01da29cccb953bed0ee400c40a7009c820f",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "message is longer than 2^61 - 1 bytes")]
+    fn length_limit() {
+        let iv = sha256::HashEngine::default().midstate();
+        sha256::Hash::from_engine(sha256::HashEngine::from_midstate(iv, 1 << 61));
+    }
+
+    #[test]
+    fn hasher() {
+        use std::collections::HashMap;
+        use std::hash::{BuildHasherDefault, Hasher};
+
+        let mut hasher = sha256::Hasher::default();
+        hasher.write(b"hello ");
+        hasher.write(b"world");
+
+        let mut engine = sha256::Hash::engine();
+        engine.input(b"hello world");
+        let hash = sha256::Hash::from_engine(engine);
+
+        assert_eq!(hasher.finish(), u64::from_le_bytes(hash.prefix()));
+        // `finish` does not consume the state
+        hasher.write(b"!");
+        assert_eq!(hasher.finish().to_le_bytes(), sha256::Hash::hash(b"hello world!").prefix());
+
+        let mut map = HashMap::<u32, &str, BuildHasherDefault<sha256::Hasher>>::default();
+        map.insert(1, "one");
+        assert_eq!(map.get(&1), Some(&"one"));
+    }
+
+    #[test]
+    fn extend() {
+        let a = [1u8; 70];
+        let b = b"scatter-gather";
+        let mut concat = a.to_vec();
+        concat.extend_from_slice(b);
+        let expected = sha256::Hash::hash(&concat);
+
+        let mut engine = sha256::Hash::engine();
+        engine.extend(vec![&a[..], &b[..]]);
+        assert_eq!(sha256::Hash::from_engine(engine), expected);
+
+        let mut engine = sha256::Hash::engine();
+        engine.extend(concat.iter().cloned());
+        assert_eq!(sha256::Hash::from_engine(engine), expected);
+    }
+
+    #[test]
+    fn finalize_into() {
+        let mut out = [0u8; 32];
+        for len in [0, 3, 55, 64, 1000].iter() {
+            let data = vec![0xa5; *len];
+            let mut engine = sha256::Hash::engine();
+            engine.input(&data);
+            sha256::Hash::finalize_into(engine.clone(), &mut out);
+            assert_eq!(out, sha256::Hash::from_engine(engine).into_inner());
+            assert_eq!(&out[..], &sha256::Hash::hash(&data)[..]);
+        }
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hash_and_double() {
+        use sha256d;
+
+        let inputs: [&[u8]; 4] = [b"", b"abc", &[0x42; 64], &[0x99; 1000]];
+        for data in inputs.iter() {
+            let (single, double) = sha256::Hash::hash_and_double(data);
+            assert_eq!(single, sha256::Hash::hash(data));
+            assert_eq!(double, sha256d::Hash::hash(data));
+        }
+
+        // The genesis block header hash
+        let header = Vec::<u8>::from_hex(
+            "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c"
+        ).unwrap();
+        let (_, double) = sha256::Hash::hash_and_double(&header);
+        assert_eq!(double.to_hex(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+    }
+
+    #[test]
+    fn active_backend() {
+        let backend = sha256::active_backend();
+        assert_eq!(sha256::active_backend(), backend);
+        let name = match backend {
+            sha256::Backend::Shani => "sha-ni",
+            sha256::Backend::Armv8Sha2 => "armv8-sha2",
+            sha256::Backend::Avx2 => "avx2",
+            sha256::Backend::Portable => "portable",
+        };
+        assert_eq!(backend.to_string(), name);
+
+        #[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64")))]
+        {
+            let expected = if ::std::env::var_os("BITCOIN_HASHES_FORCE_PORTABLE").is_some() {
+                sha256::Backend::Portable
+            } else if is_x86_feature_detected!("sha")
+                && is_x86_feature_detected!("sse2")
+                && is_x86_feature_detected!("ssse3")
+                && is_x86_feature_detected!("sse4.1")
+            {
+                sha256::Backend::Shani
+            } else if is_x86_feature_detected!("avx2") {
+                sha256::Backend::Avx2
+            } else {
+                sha256::Backend::Portable
+            };
+            assert_eq!(backend, expected);
+        }
+        #[cfg(not(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64", all(target_arch = "aarch64", feature = "aarch64-sha2")))))]
+        assert_eq!(backend, sha256::Backend::Portable);
+    }
+
+    #[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64", all(target_arch = "aarch64", feature = "aarch64-sha2"))))]
+    #[test]
+    fn accel_matches_portable() {
+        if !sha256::accel::available() {
+            return;
+        }
+
+        let mut state = 0x2545f491u32;
+        let mut engine = sha256::HashEngine::default();
+        for _ in 0..200 {
+            for byte in engine.buffer.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                *byte = state as u8;
+            }
+            let mut portable = engine.clone();
+            sha256::HashEngine::process_block_portable(&mut portable.h, &portable.buffer);
+            unsafe { sha256::accel::compress(&mut engine.h, &engine.buffer) };
+            assert_eq!(engine.h, portable.h);
+        }
+    }
+
+    #[test]
+    fn hash_batch() {
+        let mut state = 0x9e3779b9u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        for _ in 0..50 {
+            let count = next() as usize % 40;
+            let inputs: Vec<Vec<u8>> = (0..count).map(|_| {
+                let len = next() as usize % 200;
+                (0..len).map(|_| next() as u8).collect()
+            }).collect();
+            let refs: Vec<&[u8]> = inputs.iter().map(|i| &i[..]).collect();
+            let expected: Vec<sha256::Hash> = refs.iter().map(|i| sha256::Hash::hash(i)).collect();
+            assert_eq!(sha256::hash_batch(&refs), expected);
+        }
+
+        let mut blocks = [[0u8; 64]; 8];
+        for block in blocks.iter_mut() {
+            for byte in block.iter_mut() {
+                *byte = next() as u8;
+            }
+        }
+        let hashes = sha256::hash_8x_64b(&blocks);
+        for (hash, block) in hashes.iter().zip(blocks.iter()) {
+            assert_eq!(*hash, sha256::Hash::hash(block));
+        }
+    }
+
+    #[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64")))]
+    #[test]
+    fn avx2_matches_portable() {
+        if !sha256::accel::x8::available() {
+            return;
+        }
+
+        let mut state = 0x2545f491u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        for _ in 0..50 {
+            let inputs: Vec<Vec<u8>> = (0..8).map(|_| {
+                let len = next() as usize % 300;
+                (0..len).map(|_| next() as u8).collect()
+            }).collect();
+            let mut eight = [&[][..]; 8];
+            for (slice, input) in eight.iter_mut().zip(inputs.iter()) {
+                *slice = &input[..];
+            }
+            let hashes = unsafe { sha256::accel::x8::hash_8x(eight) };
+            for (hash, input) in hashes.iter().zip(inputs.iter()) {
+                assert_eq!(*hash, sha256::Hash::hash(input));
+            }
+        }
+    }
+
+    #[test]
+    fn progressive_digest() {
+        let data = [0x5au8; 300];
+
+        let mut engine = sha256::Hash::engine();
+        engine.input(&data[..100]);
+        let partial = sha256::Hash::from_engine(engine.clone());
+        engine.input(&data[100..]);
+
+        assert_eq!(partial, sha256::Hash::hash(&data[..100]));
+        assert_eq!(sha256::Hash::from_engine(engine), sha256::Hash::hash(&data));
+    }
+
+    #[test]
+    fn verify_stream() {
+        let data = [0xa5u8; 1000];
+        let expected = sha256::Hash::hash(&data);
+
+        let mut engine = sha256::Hash::engine();
+        for chunk in data.chunks(100) {
+            engine.input(chunk);
+        }
+        assert!(sha256::Hash::verify_stream(engine.clone(), &expected));
+
+        engine.input(&[0]);
+        assert!(!sha256::Hash::verify_stream(engine, &expected));
+        assert!(!sha256::Hash::verify_stream(sha256::Hash::engine(), &expected));
+    }
+
+    #[cfg(feature="subtle")]
+    #[test]
+    fn conditional_select() {
+        use subtle::{Choice, ConditionallySelectable};
+
+        let a = sha256::Hash::hash(b"a");
+        let b = sha256::Hash::hash(b"b");
+        assert_eq!(sha256::Hash::conditional_select(&a, &b, Choice::from(0)), a);
+        assert_eq!(sha256::Hash::conditional_select(&a, &b, Choice::from(1)), b);
+    }
+
+    #[cfg(feature="simd")]
+    #[test]
+    fn hash_4x() {
+        // Lengths straddling the padding boundaries, so lanes finish at
+        // different blocks.
+        let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let lens = [0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 200, 300];
+        for a in lens.iter() {
+            for b in lens.iter() {
+                let inputs = [&data[..*a], &data[..*b], &data[*a..], &data[..*a / 2]];
+                let hashes = sha256::hash_4x(inputs);
+                for (hash, input) in hashes.iter().zip(inputs.iter()) {
+                    assert_eq!(*hash, sha256::Hash::hash(input));
+                }
+            }
+        }
+    }
+
     #[cfg(feature="serde")]
     #[test]
     fn sha256_serde() {
@@ -490,6 +1861,35 @@ mod tests {
         assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
         assert_tokens(&hash.readable(), &[Token::Str("ef537f25c895bfa782526529a9b63d97aa631564d5d789c2b765448c8635fb6c")]);
     }
+
+    #[cfg(feature="serde")]
+    #[test]
+    fn midstate_serde_round_trip() {
+        use {bincode, serde_json};
+
+        let midstate = sha256::Midstate::hash_tag(b"TapLeaf");
+
+        // Human-readable: the same hex as `Display`
+        let json = serde_json::to_string(&midstate).unwrap();
+        assert_eq!(json, format!("\"{}\"", midstate));
+        assert_eq!(serde_json::from_str::<sha256::Midstate>(&json).unwrap(), midstate);
+
+        // Binary: the raw 32 bytes, after bincode's length prefix
+        let bin = bincode::serialize(&midstate).unwrap();
+        assert_eq!(&bin[..8], &32u64.to_le_bytes());
+        assert_eq!(&bin[8..], &midstate[..]);
+        assert_eq!(bincode::deserialize::<sha256::Midstate>(&bin).unwrap(), midstate);
+
+        // A persisted midstate resumes hashing where it left off
+        let resumed: sha256::Midstate = serde_json::from_str(&json).unwrap();
+        let engine = sha256::HashEngine::from_midstate(resumed, 64);
+        assert_eq!(sha256::Hash::from_engine(engine), sha256::Hash::from_engine(
+            sha256::HashEngine::from_midstate(midstate, 64),
+        ));
+
+        assert!(serde_json::from_str::<sha256::Midstate>("\"00\"").is_err());
+        assert!(bincode::deserialize::<sha256::Midstate>(&bin[..39]).is_err());
+    }
 }
 
 #[cfg(all(test, feature="unstable"))]
@@ -530,4 +1930,127 @@ mod benches {
         bh.bytes = bytes.len() as u64;
     }
 
+    #[bench]
+    pub fn sha256_1k_x4_sequential(bh: & mut Bencher) {
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            [
+                sha256::Hash::hash(&bytes),
+                sha256::Hash::hash(&bytes),
+                sha256::Hash::hash(&bytes),
+                sha256::Hash::hash(&bytes),
+            ]
+        });
+        bh.bytes = 4 * bytes.len() as u64;
+    }
+
+    /// Bitcoin merkle root of 10k leaves, hashing each level in one batch
+    fn merkle_root(leaves: &[sha256::Hash], batch: bool) -> sha256::Hash {
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            if level.len() % 2 == 1 {
+                let last = level[level.len() - 1];
+                level.push(last);
+            }
+            let pairs: Vec<[u8; 64]> = level.chunks(2).map(|pair| {
+                let mut concat = [0; 64];
+                concat[..32].copy_from_slice(&pair[0][..]);
+                concat[32..].copy_from_slice(&pair[1][..]);
+                concat
+            }).collect();
+            if batch {
+                let refs: Vec<&[u8]> = pairs.iter().map(|p| &p[..]).collect();
+                let once = sha256::hash_batch(&refs);
+                let refs: Vec<&[u8]> = once.iter().map(|h| &h[..]).collect();
+                level = sha256::hash_batch(&refs);
+            } else {
+                level = pairs.iter().map(|p| {
+                    sha256::Hash::hash(&sha256::Hash::hash(p)[..])
+                }).collect();
+            }
+        }
+        level[0]
+    }
+
+    #[bench]
+    pub fn sha256_merkle_10k_sequential(bh: & mut Bencher) {
+        let leaves: Vec<sha256::Hash> = (0..10_000u32).map(|i| sha256::Hash::hash(&i.to_le_bytes())).collect();
+        bh.iter( || {
+            merkle_root(&leaves, false)
+        });
+        assert_eq!(merkle_root(&leaves, false), merkle_root(&leaves, true));
+    }
+
+    #[bench]
+    pub fn sha256_merkle_10k_batch(bh: & mut Bencher) {
+        let leaves: Vec<sha256::Hash> = (0..10_000u32).map(|i| sha256::Hash::hash(&i.to_le_bytes())).collect();
+        bh.iter( || {
+            merkle_root(&leaves, true)
+        });
+    }
+
+    #[cfg(all(feature = "std", not(feature = "fuzztarget"), any(target_arch = "x86", target_arch = "x86_64")))]
+    #[bench]
+    pub fn sha256_8x_64b_avx2(bh: & mut Bencher) {
+        if !sha256::accel::x8::available() {
+            return;
+        }
+        let block = [1u8; 64];
+        bh.iter( || {
+            unsafe { sha256::accel::x8::hash_8x([&block[..]; 8]) }
+        });
+        bh.bytes = 8 * 64;
+    }
+
+    #[bench]
+    pub fn sha256_8x_64b_portable(bh: & mut Bencher) {
+        bh.iter( || {
+            let mut engine = sha256::Hash::engine();
+            for _ in 0..8 * 2 {
+                sha256::HashEngine::process_block_portable(&mut engine.h, &engine.buffer);
+            }
+            engine
+        });
+        bh.bytes = 8 * 64;
+    }
+
+    #[bench]
+    pub fn sha256_64k_portable(bh: & mut Bencher) {
+        let mut engine = sha256::Hash::engine();
+        bh.iter( || {
+            for _ in 0..65536 / 64 {
+                sha256::HashEngine::process_block_portable(&mut engine.h, &engine.buffer);
+            }
+        });
+        bh.bytes = 65536;
+    }
+
+    #[bench]
+    pub fn sha256_compress(bh: & mut Bencher) {
+        let mut engine = sha256::Hash::engine();
+        bh.iter( || {
+            sha256::HashEngine::process_block(&mut engine.h, &engine.buffer);
+        });
+        bh.bytes = 64;
+    }
+
+    #[bench]
+    pub fn sha256_compress_portable(bh: & mut Bencher) {
+        let mut engine = sha256::Hash::engine();
+        bh.iter( || {
+            sha256::HashEngine::process_block_portable(&mut engine.h, &engine.buffer);
+        });
+        bh.bytes = 64;
+    }
+
+    #[cfg(feature="simd")]
+    #[bench]
+    pub fn sha256_1k_x4_simd(bh: & mut Bencher) {
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            sha256::hash_4x([&bytes, &bytes, &bytes, &bytes])
+        });
+        bh.bytes = 4 * bytes.len() as u64;
+    }
+
 }
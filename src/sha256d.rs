@@ -14,8 +14,6 @@
 
 //! # SHA256d
 
-use core::str;
-
 use sha256;
 use Hash as HashTrait;
 use Error;
@@ -29,14 +27,23 @@ hex_fmt_impl!(Display, Hash);
 hex_fmt_impl!(LowerHex, Hash);
 index_impl!(Hash);
 serde_impl!(Hash, 32);
+schemars_impl!(Hash, 32);
 borrow_slice_impl!(Hash);
-
-impl str::FromStr for Hash {
-    type Err = ::hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        ::hex::FromHex::from_hex(s)
-    }
-}
+as_ref_array_impl!(Hash, 32);
+bytes_eq_impl!(Hash, 32);
+from_array_impl!(Hash, 32);
+arbitrary_impl!(Hash, 32);
+quickcheck_impl!(Hash, 32);
+borsh_impl!(Hash, 32);
+defmt_impl!(Hash);
+rand_impl!(Hash, 32);
+bitxor_impl!(Hash, 32);
+try_from_impl!(Hash);
+hex_from_str_impl!(Hash);
+subtle_impl!(Hash);
+all_bytes_impl!(Hash, 32);
+const_array_impl!(Hash, 32);
+reversed_array_impl!(Hash, 32);
 
 impl HashTrait for Hash {
     type Engine = sha256::HashEngine;
@@ -78,13 +85,46 @@ impl HashTrait for Hash {
     }
 }
 
+impl Hash {
+    /// Combines two child nodes into their parent, i.e. the SHA256d of the
+    /// 64-byte concatenation `left || right`, as done in Bitcoin merkle trees.
+    pub fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+        use HashEngine;
+
+        let mut engine = Hash::engine();
+        engine.input(&left.0);
+        engine.input(&right.0);
+        Hash::from_engine(engine)
+    }
+}
+
+/// Computes the Base58Check checksum of `data`, i.e. the first four bytes of
+/// its SHA256d hash.
+pub fn checksum(data: &[u8]) -> [u8; 4] {
+    Hash::hash(data).prefix()
+}
+
+/// Checks the trailing four-byte Base58Check checksum of `data_with_checksum`
+/// against the SHA256d of the bytes preceding it. Returns `false` for inputs
+/// shorter than four bytes.
+pub fn verify_checksum(data_with_checksum: &[u8]) -> bool {
+    if data_with_checksum.len() < 4 {
+        return false;
+    }
+    let (data, check) = data_with_checksum.split_at(data_with_checksum.len() - 4);
+    checksum(data)[..] == *check
+}
+
 #[cfg(test)]
 mod tests {
     use sha256d;
+    #[cfg(feature = "hex")]
     use hex::{FromHex, ToHex};
     use Hash;
+    #[cfg(feature = "hex")]
     use HashEngine;
 
+#[cfg(feature = "hex")]
 #[derive(Clone)]
     struct Test {
 input: &'static str,
@@ -93,6 +133,7 @@ input: &'static str,
     }
 
 #[test]
+    #[cfg(feature = "hex")]
     fn test() {
         let tests = vec![
             // Test vector copied out of rust-bitcoin
@@ -110,7 +151,7 @@ input: &'static str,
 
         for test in tests {
             // Hash through high-level API, check hex encoding/decoding
-            let hash = sha256d::Hash::hash(&test.input.as_bytes());
+            let hash = sha256d::Hash::hash(test.input.as_bytes());
             assert_eq!(hash, sha256d::Hash::from_hex(test.output_str).expect("parse hex"));
             assert_eq!(&hash[..], &test.output[..]);
             assert_eq!(&hash.to_hex(), &test.output_str);
@@ -126,6 +167,86 @@ input: &'static str,
         }
     }
 
+    #[cfg(feature = "hex")]
+    #[test]
+    fn checksum() {
+        // Decoded address 1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH
+        let mut data = Vec::<u8>::from_hex(
+            "00751e76e8199196d454941c45d1b3a323f1433bd6510d1634"
+        ).unwrap();
+        assert_eq!(sha256d::checksum(&data[..21]), [0x51, 0x0d, 0x16, 0x34]);
+        assert!(sha256d::verify_checksum(&data));
+
+        data[1] ^= 1;
+        assert!(!sha256d::verify_checksum(&data));
+        assert!(!sha256d::verify_checksum(&data[..3]));
+        assert!(!sha256d::verify_checksum(&[]));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn reversed_byte_array() {
+        // The genesis block coinbase txid, as displayed
+        let display = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let txid = sha256d::Hash::from_hex(display).unwrap();
+
+        let reversed = txid.to_reversed_byte_array();
+        assert_eq!(reversed.to_hex(), display);
+        assert_eq!(reversed[0], 0x4a);
+        assert_eq!(txid.into_inner()[0], 0x3b);
+
+        assert_eq!(sha256d::Hash::from_reversed_byte_array(reversed), txid);
+        assert_eq!(sha256d::Hash::from_reversed_byte_array(txid.into_inner()).to_reversed_byte_array(), txid.into_inner());
+    }
+
+    #[test]
+    fn inner_array() {
+        let hash = sha256d::Hash::hash(b"block header");
+        let bytes: &[u8; 32] = hash.as_inner();
+        let owned: [u8; 32] = hash.into_inner();
+        assert_eq!(bytes, &owned);
+        assert_eq!(bytes, hash.as_byte_array());
+        for i in 0..32 {
+            assert_eq!(bytes[i], hash[i]);
+        }
+        assert_eq!(sha256d::Hash::from_inner(owned), hash);
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hash_pair() {
+        // Block 170, the first block with a transaction other than the coinbase
+        let coinbase = sha256d::Hash::from_hex(
+            "b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082"
+        ).unwrap();
+        let spend = sha256d::Hash::from_hex(
+            "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16"
+        ).unwrap();
+        let root = sha256d::Hash::hash_pair(&coinbase, &spend);
+        assert_eq!(
+            root.to_hex(),
+            "7dac2c5666815c17a3b36427de37bb9d2e2c5ccec3f8633eb91a4205cb4c10ff",
+        );
+
+        let mut concat = coinbase[..].to_vec();
+        concat.extend_from_slice(&spend[..]);
+        assert_eq!(root, sha256d::Hash::hash(&concat));
+        assert!(root != sha256d::Hash::hash_pair(&spend, &coinbase));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn all_bytes() {
+        const MAX: sha256d::Hash = sha256d::Hash::all_ones();
+        assert_eq!(MAX.to_hex(), "f".repeat(64));
+        assert_eq!(sha256d::Hash::all_zeros().to_hex(), "0".repeat(64));
+        assert_eq!(sha256d::Hash::all_zeros(), sha256d::Hash::default());
+
+        let hash = sha256d::Hash::hash(b"block header");
+        assert!(hash <= MAX);
+        assert!(sha256d::Hash::all_zeros() <= hash);
+    }
+
     #[cfg(feature="serde")]
     #[test]
     fn sha256_serde() {
@@ -142,6 +263,32 @@ input: &'static str,
         assert_tokens(&hash.compact(), &[Token::BorrowedBytes(&HASH_BYTES[..])]);
         assert_tokens(&hash.readable(), &[Token::Str("6cfb35868c4465b7c289d7d5641563aa973db6a929655282a7bf95c8257f53ef")]);
     }
+
+    #[cfg(all(feature="schemars", feature="serde"))]
+    #[test]
+    fn sha256d_json_schema() {
+        use schemars::schema_for;
+        use serde_json::{self, Value};
+
+        let schema = serde_json::to_value(schema_for!(sha256d::Hash)).unwrap();
+        assert_eq!(schema["type"], "string");
+        assert_eq!(schema["minLength"], 64);
+        assert_eq!(schema["maxLength"], 64);
+        assert_eq!(schema["pattern"], "^[0-9a-f]{64}$");
+        assert_eq!(schema["description"], "32 bytes as lowercase hex, in reverse byte order");
+
+        // The serialized hash satisfies the constraints, and is the reversed hex
+        let hash = sha256d::Hash::hash(b"abc");
+        let value = serde_json::to_value(hash).unwrap();
+        let hex = match value {
+            Value::String(ref s) => s.clone(),
+            _ => panic!("not a string: {}", value),
+        };
+        assert_eq!(hex.len(), 64);
+        assert!(hex.bytes().all(|c| c.is_ascii_digit() || (b'a'..=b'f').contains(&c)));
+        assert_eq!(hex, hash.to_hex());
+        assert_eq!(hex, hash.to_reversed_byte_array().to_hex());
+    }
 }
 
 #[cfg(all(test, feature="unstable"))]
@@ -22,6 +22,9 @@ use Hash as HashTrait;
 use Error;
 
 /// Trait representing a tag that can be used as a context for SHA256t hashes.
+///
+/// The engine for a BIP340 tag can start from a midstate computed at compile
+/// time by `sha256::Midstate::hash_tag`.
 pub trait Tag: Copy + Ord + Default + ::core::hash::Hash {
     /// Returns a hash engine that is pre-tagged and is ready
     /// to be used for the data.
@@ -35,8 +38,22 @@ pub struct Hash<T: Tag>([u8; 32], PhantomData<T>);
 hex_fmt_impl!(Debug, Hash, T:Tag);
 hex_fmt_impl!(Display, Hash, T:Tag);
 hex_fmt_impl!(LowerHex, Hash, T:Tag);
+hex_from_str_impl!(Hash, T:Tag);
 index_impl!(Hash, T:Tag);
 borrow_slice_impl!(Hash, T:Tag);
+as_ref_array_impl!(Hash, 32, T:Tag);
+bytes_eq_impl!(Hash, 32, T:Tag);
+from_array_impl!(Hash, 32, T:Tag);
+arbitrary_impl!(Hash, 32, T:Tag);
+quickcheck_impl!(Hash, 32, T:Tag);
+borsh_impl!(Hash, 32, T:Tag);
+defmt_impl!(Hash, T:Tag);
+schemars_impl!(Hash, 32, T:Tag);
+rand_impl!(Hash, 32, T:Tag);
+bitxor_impl!(Hash, 32, T:Tag);
+try_from_impl!(Hash, T:Tag);
+subtle_impl!(Hash, T:Tag);
+reversed_array_impl!(Hash, 32, T:Tag);
 
 impl<T: Tag> HashTrait for Hash<T> {
     type Engine = sha256::HashEngine;
@@ -106,7 +123,7 @@ impl<'de, T: Tag> ::serde::de::Visitor<'de> for HexVisitor<T> {
         if let Ok(hex) = ::std::str::from_utf8(v) {
             Hash::<T>::from_hex(hex).map_err(E::custom)
         } else {
-            return Err(E::invalid_value(::serde::de::Unexpected::Bytes(v), &self));
+            Err(E::invalid_value(::serde::de::Unexpected::Bytes(v), &self))
         }
     }
 
@@ -156,6 +173,7 @@ impl<'de, T: Tag> ::serde::Deserialize<'de> for Hash<T> {
 #[cfg(test)]
 mod tests {
     use ::{Hash, sha256, sha256t};
+    #[cfg(feature = "hex")]
     use ::hex::ToHex;
 
     #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
@@ -175,6 +193,17 @@ mod tests {
     /// A hash tagged with `$name`.
     pub type TestHash = sha256t::Hash<TestHashTag>;
 
+    #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+    pub struct ConstTag;
+
+    impl sha256t::Tag for ConstTag {
+        fn engine() -> sha256::HashEngine {
+            const MIDSTATE: sha256::Midstate = sha256::Midstate::hash_tag(b"TapLeaf");
+            sha256::HashEngine::from_midstate(MIDSTATE, 64)
+        }
+    }
+
+    #[cfg(feature = "hex")]
     #[test]
     fn test_sha256t() {
        assert_eq!(
@@ -182,4 +211,47 @@ mod tests {
            "29589d5122ec666ab5b4695070b6debc63881a4f85d88d93ddc90078038213ed"
        );
     }
+
+    #[test]
+    fn const_midstate() {
+        assert_eq!(
+            sha256t::Hash::<ConstTag>::hash(&[0]).into_inner(),
+            TestHash::hash(&[0]).into_inner(),
+        );
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn reversed_byte_array() {
+        let hash = TestHash::hash(&[0]);
+        let reversed = hash.to_reversed_byte_array();
+        assert_eq!(reversed.to_hex(), hash.to_hex());
+        assert_eq!(TestHash::from_reversed_byte_array(reversed), hash);
+    }
+
+    #[test]
+    fn bytes_eq() {
+        let hash = TestHash::hash(&[0]);
+        let array = hash.into_inner();
+        assert_eq!(array, hash);
+        assert_eq!(&array[..], hash);
+        assert!(&array[1..] != hash);
+    }
+
+    #[cfg(all(feature="schemars", feature="serde"))]
+    #[test]
+    fn json_schema() {
+        use schemars::schema_for;
+        use serde_json;
+
+        let schema = serde_json::to_value(schema_for!(TestHash)).unwrap();
+        assert_eq!(schema["type"], "string");
+        assert_eq!(schema["minLength"], 64);
+        assert_eq!(schema["maxLength"], 64);
+        assert_eq!(schema["pattern"], "^[0-9a-f]{64}$");
+        assert_eq!(schema["description"], "32 bytes as lowercase hex, in reverse byte order");
+
+        let hash = TestHash::hash(&[0]);
+        assert_eq!(serde_json::to_value(hash).unwrap(), hash.to_hex());
+    }
 }
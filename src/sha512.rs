@@ -19,7 +19,7 @@
 
 //! # SHA512
 
-use core::{cmp, hash, str};
+use core::{cmp, hash};
 
 use HashEngine as EngineTrait;
 use Hash as HashTrait;
@@ -32,7 +32,7 @@ const BLOCK_SIZE: usize = 128;
 #[derive(Clone)]
 pub struct HashEngine {
     h: [u64; 8],
-    length: usize,
+    length: u64, // bytes processed so far
     buffer: [u8; BLOCK_SIZE],
 }
 
@@ -49,6 +49,21 @@ impl Default for HashEngine {
     }
 }
 
+impl HashEngine {
+    /// Returns an engine with the SHA-512/256 initial state, for use by
+    /// the `sha512_256` module
+    pub(crate) fn sha512_256() -> HashEngine {
+        HashEngine {
+            h: [
+                0x22312194fc2bf72c, 0x9f555fa3c84c64c2, 0x2393b86b6f53b151, 0x963877195940eabd,
+                0x96283ee2a88effe3, 0xbe5e1e2553863992, 0x2b0199fc2c85b8aa, 0x0eb72ddc81c52ca2,
+            ],
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+}
+
 impl EngineTrait for HashEngine {
     type MidState = [u8; 64];
 
@@ -73,6 +88,8 @@ impl EngineTrait for HashEngine {
     engine_input_impl!();
 }
 
+engine_extend_impl!(HashEngine);
+
 /// Output of the SHA256 hash function
 pub struct Hash([u8; 64]);
 
@@ -80,9 +97,7 @@ impl Copy for Hash {}
 
 impl Clone for Hash {
     fn clone(&self) -> Hash {
-        let mut ret = [0; 64];
-        ret.copy_from_slice(&self.0);
-        Hash(ret)
+        *self
     }
 }
 
@@ -102,26 +117,19 @@ impl Default for Hash {
 
 impl PartialOrd for Hash {
     fn partial_cmp(&self, other: &Hash) -> Option<cmp::Ordering> {
-        (&self.0).partial_cmp(&other.0)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Hash {
     fn cmp(&self, other: &Hash) -> cmp::Ordering {
-        (&self.0).cmp(&other.0)
+        self.0[..].cmp(&other.0[..])
     }
 }
 
 impl hash::Hash for Hash {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        (&self.0).hash(state)
-    }
-}
-
-impl str::FromStr for Hash {
-    type Err = ::hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        ::hex::FromHex::from_hex(s)
+        self.0[..].hash(state)
     }
 }
 
@@ -130,7 +138,22 @@ hex_fmt_impl!(Display, Hash);
 hex_fmt_impl!(LowerHex, Hash);
 index_impl!(Hash);
 serde_impl!(Hash, 64);
+schemars_impl!(Hash, 64);
 borrow_slice_impl!(Hash);
+as_ref_array_impl!(Hash, 64);
+bytes_eq_impl!(Hash, 64);
+from_array_impl!(Hash, 64);
+arbitrary_impl!(Hash, 64);
+quickcheck_impl!(Hash, 64);
+borsh_impl!(Hash, 64);
+defmt_impl!(Hash);
+rand_impl!(Hash, 64);
+bitxor_impl!(Hash, 64);
+try_from_impl!(Hash);
+hex_from_str_impl!(Hash);
+subtle_impl!(Hash);
+all_bytes_impl!(Hash, 64);
+const_array_impl!(Hash, 64);
 
 impl HashTrait for Hash {
     type Engine = HashEngine;
@@ -139,20 +162,21 @@ impl HashTrait for Hash {
     #[cfg(not(feature = "fuzztarget"))]
     fn from_engine(mut e: HashEngine) -> Hash {
         // pad buffer with a single 1-bit then all 0s, until there are exactly 16 bytes remaining
-        let data_len = e.length as u64;
+        let data_len = e.length;
 
         let zeroes = [0; BLOCK_SIZE - 16];
         e.input(&[0x80]);
-        if e.length % BLOCK_SIZE > zeroes.len() {
+        if e.length % BLOCK_SIZE as u64 > zeroes.len() as u64 {
             e.input(&zeroes);
         }
-        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE);
+        let pad_length = zeroes.len() - (e.length % BLOCK_SIZE as u64) as usize;
         e.input(&zeroes[..pad_length]);
-        debug_assert_eq!(e.length % BLOCK_SIZE, zeroes.len());
+        debug_assert_eq!(e.length % BLOCK_SIZE as u64, zeroes.len() as u64);
 
-        e.input(&[0; 8]);
-        e.input(&util::u64_to_array_be(8 * data_len));
-        debug_assert_eq!(e.length % BLOCK_SIZE, 0);
+        // The 128-bit bit length, of which only the low 67 bits can be set
+        e.input(&util::u64_to_array_be(data_len >> 61));
+        e.input(&util::u64_to_array_be(data_len << 3));
+        debug_assert_eq!(e.length % BLOCK_SIZE as u64, 0);
 
         Hash(e.midstate())
     }
@@ -209,22 +233,20 @@ macro_rules! round(
 
 impl HashEngine {
     // Algorithm copied from libsecp256k1
-    fn process_block(&mut self) {
-        debug_assert_eq!(self.buffer.len(), BLOCK_SIZE);
-
+    fn process_block(state: &mut [u64; 8], block: &[u8; BLOCK_SIZE]) {
         let mut w = [0u64; 16];
-        for (w_val, buff_bytes) in w.iter_mut().zip(self.buffer.chunks(8)) {
+        for (w_val, buff_bytes) in w.iter_mut().zip(block.chunks(8)) {
             *w_val = util::slice_to_u64_be(buff_bytes);
         }
 
-        let mut a = self.h[0];
-        let mut b = self.h[1];
-        let mut c = self.h[2];
-        let mut d = self.h[3];
-        let mut e = self.h[4];
-        let mut f = self.h[5];
-        let mut g = self.h[6];
-        let mut h = self.h[7];
+        let mut a = state[0];
+        let mut b = state[1];
+        let mut c = state[2];
+        let mut d = state[3];
+        let mut e = state[4];
+        let mut f = state[5];
+        let mut g = state[6];
+        let mut h = state[7];
 
         round!(a, b, c, d, e, f, g, h, 0x428a2f98d728ae22, w[0]);
         round!(h, a, b, c, d, e, f, g, 0x7137449123ef65cd, w[1]);
@@ -311,18 +333,18 @@ impl HashEngine {
         round!(c, d, e, f, g, h, a, b, 0x5fcb6fab3ad6faec, w[14], w[12], w[7], w[15]);
         round!(b, c, d, e, f, g, h, a, 0x6c44198c4a475817, w[15], w[13], w[8], w[0]);
 
-        self.h[0] = self.h[0].wrapping_add(a);
-        self.h[1] = self.h[1].wrapping_add(b);
-        self.h[2] = self.h[2].wrapping_add(c);
-        self.h[3] = self.h[3].wrapping_add(d);
-        self.h[4] = self.h[4].wrapping_add(e);
-        self.h[5] = self.h[5].wrapping_add(f);
-        self.h[6] = self.h[6].wrapping_add(g);
-        self.h[7] = self.h[7].wrapping_add(h);
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+        state[4] = state[4].wrapping_add(e);
+        state[5] = state[5].wrapping_add(f);
+        state[6] = state[6].wrapping_add(g);
+        state[7] = state[7].wrapping_add(h);
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "hex"))]
 mod tests {
     use sha512;
     use hex::{FromHex, ToHex};
@@ -386,7 +408,7 @@ mod tests {
 
         for test in tests {
             // Hash through high-level API, check hex encoding/decoding
-            let hash = sha512::Hash::hash(&test.input.as_bytes());
+            let hash = sha512::Hash::hash(test.input.as_bytes());
             assert_eq!(hash, sha512::Hash::from_hex(test.output_str).expect("parse hex"));
             assert_eq!(&hash[..], &test.output[..]);
             assert_eq!(&hash.to_hex(), &test.output_str);
@@ -0,0 +1,229 @@
+// Bitcoin Hashes Library
+// Written in 2020 by
+//   The rust-bitcoin developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # SHA512/256
+//!
+//! SHA512/256 is SHA512 with a different initial state, truncated to 256
+//! bits (FIPS 180-4, section 6.7). It is *not* the first 32 bytes of a
+//! plain SHA512 hash.
+
+use sha512;
+use HashEngine as EngineTrait;
+use Hash as HashTrait;
+use Error;
+
+/// Engine to compute the SHA512/256 hash function
+#[derive(Clone)]
+pub struct HashEngine(sha512::HashEngine);
+
+impl Default for HashEngine {
+    fn default() -> Self {
+        HashEngine(sha512::HashEngine::sha512_256())
+    }
+}
+
+impl EngineTrait for HashEngine {
+    type MidState = [u8; 64];
+
+    fn midstate(&self) -> [u8; 64] {
+        self.0.midstate()
+    }
+
+    const BLOCK_SIZE: usize = 128;
+
+    fn input(&mut self, inp: &[u8]) {
+        self.0.input(inp)
+    }
+}
+
+engine_extend_impl!(HashEngine);
+
+/// Output of the SHA512/256 hash function
+#[derive(Copy, Clone, PartialEq, Eq, Default, PartialOrd, Ord, Hash)]
+pub struct Hash([u8; 32]);
+
+hex_fmt_impl!(Debug, Hash);
+hex_fmt_impl!(Display, Hash);
+hex_fmt_impl!(LowerHex, Hash);
+index_impl!(Hash);
+serde_impl!(Hash, 32);
+schemars_impl!(Hash, 32);
+borrow_slice_impl!(Hash);
+as_ref_array_impl!(Hash, 32);
+bytes_eq_impl!(Hash, 32);
+from_array_impl!(Hash, 32);
+arbitrary_impl!(Hash, 32);
+quickcheck_impl!(Hash, 32);
+borsh_impl!(Hash, 32);
+defmt_impl!(Hash);
+rand_impl!(Hash, 32);
+bitxor_impl!(Hash, 32);
+try_from_impl!(Hash);
+hex_from_str_impl!(Hash);
+subtle_impl!(Hash);
+all_bytes_impl!(Hash, 32);
+const_array_impl!(Hash, 32);
+
+impl HashTrait for Hash {
+    type Engine = HashEngine;
+    type Inner = [u8; 32];
+
+    fn from_engine(e: HashEngine) -> Hash {
+        let full = sha512::Hash::from_engine(e.0);
+
+        let mut ret = [0; 32];
+        ret.copy_from_slice(&full[..32]);
+        Hash(ret)
+    }
+
+    const LEN: usize = 32;
+
+    fn from_slice(sl: &[u8]) -> Result<Hash, Error> {
+        if sl.len() != 32 {
+            Err(Error::InvalidLength(Self::LEN, sl.len()))
+        } else {
+            let mut ret = [0; 32];
+            ret.copy_from_slice(sl);
+            Ok(Hash(ret))
+        }
+    }
+
+    fn into_inner(self) -> Self::Inner {
+        self.0
+    }
+
+    fn from_inner(inner: Self::Inner) -> Self {
+        Hash(inner)
+    }
+}
+
+#[cfg(all(test, feature = "hex"))]
+mod tests {
+    use {sha512, sha512_256};
+    use hex::{FromHex, ToHex};
+    use Hash;
+    use HashEngine;
+
+    #[derive(Clone)]
+    struct Test {
+        input: &'static str,
+        output_str: &'static str,
+    }
+
+    #[test]
+    fn test() {
+        let tests = vec![
+            // NIST SHA512/256 examples, one and two blocks
+            Test {
+                input: "abc",
+                output_str: "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+            },
+            Test {
+                input: "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+                        hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                output_str: "3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a",
+            },
+            // Computed with Python's hashlib
+            Test {
+                input: "",
+                output_str: "c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a",
+            },
+            Test {
+                input: "The quick brown fox jumps over the lazy dog",
+                output_str: "dd9d67b371519c339ed8dbd25af90e976a1eeefd4ad3d889005e532fc5bef04d",
+            },
+        ];
+
+        for test in tests {
+            // Hash through high-level API, check hex encoding/decoding
+            let hash = sha512_256::Hash::hash(test.input.as_bytes());
+            assert_eq!(hash, sha512_256::Hash::from_hex(test.output_str).expect("parse hex"));
+            assert_eq!(&hash.to_hex(), &test.output_str);
+
+            // Hash through engine, checking that we can input byte by byte
+            let mut engine = sha512_256::Hash::engine();
+            for ch in test.input.as_bytes() {
+                engine.input(&[*ch]);
+            }
+            let manual_hash = sha512_256::Hash::from_engine(engine);
+            assert_eq!(hash, manual_hash);
+
+            // Not a truncated SHA512
+            assert!(hash[..] != sha512::Hash::hash(test.input.as_bytes())[..32]);
+        }
+    }
+
+    #[test]
+    fn million_a() {
+        let mut engine = sha512_256::Hash::engine();
+        let chunk = [b'a'; 1000];
+        for _ in 0..1000 {
+            engine.input(&chunk);
+        }
+        assert_eq!(
+            sha512_256::Hash::from_engine(engine).to_hex(),
+            "9a59a052930187a97038cae692f30708aa6491923ef5194394dc68d56c74fb21",
+        );
+    }
+
+    #[test]
+    fn initial_state() {
+        let iv = sha512_256::Hash::engine().midstate();
+        assert!(iv[..] != sha512::Hash::engine().midstate()[..]);
+        assert_eq!(
+            iv[..].to_hex(),
+            "22312194fc2bf72c9f555fa3c84c64c22393b86b6f53b151963877195940eabd\
+             96283ee2a88effe3be5e1e25538639922b0199fc2c85b8aa0eb72ddc81c52ca2",
+        );
+    }
+}
+
+#[cfg(all(test, feature="unstable"))]
+mod benches {
+    use test::Bencher;
+
+    use sha512_256;
+    use Hash;
+    use HashEngine;
+
+    #[bench]
+    pub fn sha512_256_10(bh: & mut Bencher) {
+        let mut engine = sha512_256::Hash::engine();
+        let bytes = [1u8; 10];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha512_256_1k(bh: & mut Bencher) {
+        let mut engine = sha512_256::Hash::engine();
+        let bytes = [1u8; 1024];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha512_256_64k(bh: & mut Bencher) {
+        let mut engine = sha512_256::Hash::engine();
+        let bytes = [1u8; 65536];
+        bh.iter( || {
+            engine.input(&bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
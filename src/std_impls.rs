@@ -18,7 +18,8 @@
 
 use std::{error, io};
 
-use {hex, sha1, sha256, sha512, ripemd160, siphash24};
+#[cfg(feature = "hex")] use hex;
+use {sha1, sha256, sha512, sha512_256, ripemd160, siphash24};
 use HashEngine;
 use Error;
 
@@ -27,62 +28,118 @@ impl error::Error for Error {
     fn description(&self) -> &str { "`std::error::description` is deprecated" }
 }
 
+#[cfg(feature = "hex")]
 impl error::Error for hex::Error {
     fn cause(&self) -> Option<&error::Error> { None }
     fn description(&self) -> &str { "`std::error::description` is deprecated" }
 }
 
-impl io::Write for sha1::HashEngine {
-    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+macro_rules! write_impl(
+    ($hash:ident) => (
+        impl io::Write for $hash::HashEngine {
+            fn flush(&mut self) -> io::Result<()> { Ok(()) }
 
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.input(buf);
-        Ok(buf.len())
-    }
-}
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.input(buf);
+                Ok(buf.len())
+            }
 
-impl io::Write for sha256::HashEngine {
-    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+            fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+                let mut len = 0;
+                for buf in bufs {
+                    self.input(buf);
+                    len += buf.len();
+                }
+                Ok(len)
+            }
+        }
+    )
+);
 
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.input(buf);
-        Ok(buf.len())
-    }
-}
+write_impl!(sha1);
+write_impl!(sha256);
+write_impl!(sha512);
+write_impl!(sha512_256);
+write_impl!(ripemd160);
+write_impl!(siphash24);
 
-impl io::Write for sha512::HashEngine {
-    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+#[cfg(test)]
+mod tests {
+    use std::io::{IoSlice, Write};
 
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.input(buf);
-        Ok(buf.len())
-    }
-}
+    use {sha1, sha256, sha256d, sha512, sha512_256, ripemd160, hash160, siphash24};
+    use {Hash, HashEngine};
 
-impl io::Write for ripemd160::HashEngine {
-    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    fn input_matches_write<T: Hash>() where T::Engine: Write {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
 
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.input(buf);
-        Ok(buf.len())
+        let mut input = T::engine();
+        let mut write = T::engine();
+        for chunk in data.chunks(37) {
+            input.input(chunk);
+            write.write_all(chunk).unwrap();
+        }
+        assert_eq!(T::from_engine(input), T::from_engine(write));
     }
-}
 
-impl io::Write for siphash24::HashEngine {
-    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    fn fork_engine<T: Hash>() where T::Engine: Write {
+        let mut engine = T::engine();
+        engine.write_all(&[7; 30]).unwrap();
+        let mut fork = engine.clone();
+        engine.write_all(b"left").unwrap();
+        fork.write_all(b"right side").unwrap();
+
+        let mut left = vec![7; 30];
+        left.extend_from_slice(b"left");
+        let mut right = vec![7; 30];
+        right.extend_from_slice(b"right side");
+        assert_eq!(T::from_engine(engine), <T as Hash>::hash(&left));
+        assert_eq!(T::from_engine(fork), <T as Hash>::hash(&right));
+    }
+
+    fn write_vectored<T: Hash>() where T::Engine: Write {
+        let parts: [&[u8]; 4] = [&[1; 40], b"", &[2; 100], b"tail"];
+        let slices: Vec<IoSlice> = parts.iter().map(|p| IoSlice::new(p)).collect();
 
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.input(buf);
-        Ok(buf.len())
+        let mut engine = T::engine();
+        assert_eq!(engine.write_vectored(&slices).unwrap(), 144);
+        assert_eq!(T::from_engine(engine), <T as Hash>::hash(&parts.concat()));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::io::Write;
+    #[test]
+    fn input_engines() {
+        input_matches_write::<sha1::Hash>();
+        input_matches_write::<sha256::Hash>();
+        input_matches_write::<sha256d::Hash>();
+        input_matches_write::<sha512::Hash>();
+        input_matches_write::<sha512_256::Hash>();
+        input_matches_write::<ripemd160::Hash>();
+        input_matches_write::<hash160::Hash>();
+        input_matches_write::<siphash24::Hash>();
+    }
 
-    use {sha1, sha256, sha256d, sha512, ripemd160, hash160, siphash24};
-    use Hash;
+    #[test]
+    fn vectored_engines() {
+        write_vectored::<sha1::Hash>();
+        write_vectored::<sha256::Hash>();
+        write_vectored::<sha512::Hash>();
+        write_vectored::<sha512_256::Hash>();
+        write_vectored::<ripemd160::Hash>();
+        write_vectored::<hash160::Hash>();
+        write_vectored::<siphash24::Hash>();
+    }
+
+    #[test]
+    fn clone_engines() {
+        fork_engine::<sha1::Hash>();
+        fork_engine::<sha256::Hash>();
+        fork_engine::<sha256d::Hash>();
+        fork_engine::<sha512::Hash>();
+        fork_engine::<sha512_256::Hash>();
+        fork_engine::<ripemd160::Hash>();
+        fork_engine::<hash160::Hash>();
+        fork_engine::<siphash24::Hash>();
+    }
 
     macro_rules! write_test {
         ($mod:ident, $exp_empty:expr, $exp_256:expr, $exp_64k:expr,) => {
@@ -143,6 +200,13 @@ mod tests {
          4761df8d04ed04bb734ba48dd2106bb9ea54524f1394cdd18e6da3166e71c3ee",
     );
 
+    write_test!(
+        sha512_256,
+        "c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a",
+        "8d4bb96e7956cf5f08bf5c45f7982630c46b0b022f25cbaf722ae97c06a6e7a2",
+        "3367646f3e264653f7dd664ac2cb6d3b96329e86ffb7a29a1082e2a4ddc9ee7a",
+    );
+
     write_test!(
         ripemd160,
         "9c1185a5c5e9fc54612808977ee8f548b2258d31",
@@ -14,16 +14,18 @@
 
 /// Circular left-shift a 32-bit word
 macro_rules! circular_lshift32 (
-    ($shift:expr, $w:expr) => (($w << $shift) | ($w >> (32 - $shift)))
+    ($shift:expr, $w:expr) => ($w.rotate_left($shift))
 );
 
 /// Circular left-shift a 64-bit word
 macro_rules! circular_lshift64 (
-    ($shift:expr, $w:expr) => (($w << $shift) | ($w >> (64 - $shift)))
+    ($shift:expr, $w:expr) => ($w.rotate_left($shift))
 );
 
 #[macro_export]
 /// Adds hexadecimal formatting implementation of a trait `$imp` to a given type `$ty`
+///
+/// The hex digits are written straight to the formatter, without allocating.
 macro_rules! hex_fmt_impl(
     ($imp:ident, $ty:ident) => (
         hex_fmt_impl!($imp, $ty, );
@@ -33,9 +35,9 @@ macro_rules! hex_fmt_impl(
             fn fmt(&self, f: &mut $crate::core::fmt::Formatter) -> $crate::core::fmt::Result {
                 use $crate::hex::{format_hex, format_hex_reverse};
                 if $ty::<$($gen),*>::DISPLAY_BACKWARD {
-                    format_hex_reverse(&self.0, f)
+                    format_hex_reverse(&self.0[..], f)
                 } else {
-                    format_hex(&self.0, f)
+                    format_hex(&self.0[..], f)
                 }
             }
         }
@@ -43,74 +45,581 @@ macro_rules! hex_fmt_impl(
 );
 
 /// Adds `core::ops::Index` trait implementation to a given type `$ty`
+///
+/// All integer and range indices are supported. Out-of-bounds indices panic
+/// with a message naming the type being indexed.
 #[macro_export]
 macro_rules! index_impl(
     ($ty:ident) => (
         index_impl!($ty, );
     );
     ($ty:ident, $($gen:ident: $gent:ident),*) => (
-        impl<$($gen: $gent),*> $crate::core::ops::Index<usize> for $ty<$($gen),*> {
-            type Output = u8;
-            fn index(&self, index: usize) -> &u8 {
-                &self.0[index]
+        index_impl!(@impl $ty, usize, u8, [$($gen: $gent),*]);
+        index_impl!(@impl $ty, $crate::core::ops::Range<usize>, [u8], [$($gen: $gent),*]);
+        index_impl!(@impl $ty, $crate::core::ops::RangeFrom<usize>, [u8], [$($gen: $gent),*]);
+        index_impl!(@impl $ty, $crate::core::ops::RangeTo<usize>, [u8], [$($gen: $gent),*]);
+        index_impl!(@impl $ty, $crate::core::ops::RangeFull, [u8], [$($gen: $gent),*]);
+        index_impl!(@impl $ty, $crate::core::ops::RangeInclusive<usize>, [u8], [$($gen: $gent),*]);
+        index_impl!(@impl $ty, $crate::core::ops::RangeToInclusive<usize>, [u8], [$($gen: $gent),*]);
+    );
+    (@impl $ty:ident, $idx:ty, $out:ty, [$($gen:ident: $gent:ident),*]) => (
+        impl<$($gen: $gent),*> $crate::core::ops::Index<$idx> for $ty<$($gen),*> {
+            type Output = $out;
+            fn index(&self, index: $idx) -> &$out {
+                match self.0[..].get(index.clone()) {
+                    Some(ret) => ret,
+                    None => panic!(
+                        "index {:?} out of range for {} of length {}",
+                        index, concat!(module_path!(), "::", stringify!($ty)), self.0[..].len(),
+                    ),
+                }
+            }
+        }
+    );
+);
+
+/// Adds slicing traits implementations to a given type `$ty`
+#[macro_export]
+macro_rules! borrow_slice_impl(
+    ($ty:ident) => (
+        borrow_slice_impl!($ty, );
+    );
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::core::borrow::Borrow<[u8]> for $ty<$($gen),*>  {
+            fn borrow(&self) -> &[u8] {
+                &self[..]
+            }
+        }
+
+        impl<$($gen: $gent),*> $crate::core::convert::AsRef<[u8]> for $ty<$($gen),*>  {
+            fn as_ref(&self) -> &[u8] {
+                &self[..]
             }
         }
 
-        impl<$($gen: $gent),*> $crate::core::ops::Index<$crate::core::ops::Range<usize>> for $ty<$($gen),*> {
-            type Output = [u8];
-            fn index(&self, index: $crate::core::ops::Range<usize>) -> &[u8] {
-                &self.0[index]
+        impl<$($gen: $gent),*> $crate::core::ops::Deref for $ty<$($gen),*> {
+            type Target = [u8];
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
             }
         }
+    )
+);
 
-        impl<$($gen: $gent),*> $crate::core::ops::Index<$crate::core::ops::RangeFrom<usize>> for $ty<$($gen),*> {
-            type Output = [u8];
-            fn index(&self, index: $crate::core::ops::RangeFrom<usize>) -> &[u8] {
-                &self.0[index]
+/// Adds `AsRef` to the underlying fixed-size byte array for a given type `$ty`
+#[macro_export]
+macro_rules! as_ref_array_impl(
+    ($ty:ident, $len:expr) => (
+        as_ref_array_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::core::convert::AsRef<[u8; $len]> for $ty<$($gen),*>  {
+            fn as_ref(&self) -> &[u8; $len] {
+                &self.0
             }
         }
+    )
+);
 
-        impl<$($gen: $gent),*> $crate::core::ops::Index<$crate::core::ops::RangeTo<usize>> for $ty<$($gen),*> {
-            type Output = [u8];
-            fn index(&self, index: $crate::core::ops::RangeTo<usize>) -> &[u8] {
-                &self.0[index]
+/// Adds `PartialEq<$ty>` for byte arrays and slices, so that they can be
+/// compared against a given hash type `$ty`, e.g. `[0; 32] == hash`
+///
+/// `$ty` itself only compares with `Self`, so that `hash == Hash::from_engine(e)`,
+/// where the right-hand type is only fixed by the comparison, still infers.
+#[macro_export]
+macro_rules! bytes_eq_impl(
+    ($ty:ident, $len:expr) => (
+        bytes_eq_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::core::cmp::PartialEq<$ty<$($gen),*>> for [u8; $len] {
+            fn eq(&self, other: &$ty<$($gen),*>) -> bool {
+                self[..] == other[..]
             }
         }
 
-        impl<$($gen: $gent),*> $crate::core::ops::Index<$crate::core::ops::RangeFull> for $ty<$($gen),*> {
-            type Output = [u8];
-            fn index(&self, index: $crate::core::ops::RangeFull) -> &[u8] {
-                &self.0[index]
+        impl<'a, $($gen: $gent),*> $crate::core::cmp::PartialEq<$ty<$($gen),*>> for &'a [u8] {
+            fn eq(&self, other: &$ty<$($gen),*>) -> bool {
+                **self == other[..]
             }
         }
     )
 );
 
-/// Adds slicing traits implementations to a given type `$ty`
+/// Adds infallible `From` conversions between a given hash type `$ty` and its
+/// underlying byte array
 #[macro_export]
-macro_rules! borrow_slice_impl(
+macro_rules! from_array_impl(
+    ($ty:ident, $len:expr) => (
+        from_array_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::core::convert::From<[u8; $len]> for $ty<$($gen),*> {
+            fn from(inner: [u8; $len]) -> Self {
+                <$ty<$($gen),*> as $crate::Hash>::from_inner(inner)
+            }
+        }
+
+        impl<$($gen: $gent),*> $crate::core::convert::From<$ty<$($gen),*>> for [u8; $len] {
+            fn from(hash: $ty<$($gen),*>) -> Self {
+                <$ty<$($gen),*> as $crate::Hash>::into_inner(hash)
+            }
+        }
+
+        impl<'a, $($gen: $gent),*> $crate::core::convert::From<&'a $ty<$($gen),*>> for &'a [u8; $len] {
+            fn from(hash: &'a $ty<$($gen),*>) -> Self {
+                <$ty<$($gen),*> as $crate::core::convert::AsRef<[u8; $len]>>::as_ref(hash)
+            }
+        }
+    )
+);
+
+/// Adds `TryFrom` byte slice (and, with `std`, byte vector) implementations to
+/// a given hash type `$ty`, delegating to `Hash::from_slice`
+#[macro_export]
+macro_rules! try_from_impl(
     ($ty:ident) => (
-        borrow_slice_impl!($ty, );
+        try_from_impl!($ty, );
     );
     ($ty:ident, $($gen:ident: $gent:ident),*) => (
-        impl<$($gen: $gent),*> $crate::core::borrow::Borrow<[u8]> for $ty<$($gen),*>  {
-            fn borrow(&self) -> &[u8] {
-                &self[..]
+        impl<'a, $($gen: $gent),*> $crate::core::convert::TryFrom<&'a [u8]> for $ty<$($gen),*> {
+            type Error = $crate::Error;
+
+            fn try_from(sl: &'a [u8]) -> Result<Self, $crate::Error> {
+                <$ty<$($gen),*> as $crate::Hash>::from_slice(sl)
             }
         }
 
-        impl<$($gen: $gent),*> $crate::core::convert::AsRef<[u8]> for $ty<$($gen),*>  {
-            fn as_ref(&self) -> &[u8] {
-                &self[..]
+        try_from_vec_impl!($ty, $($gen: $gent),*);
+    )
+);
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(any(test, feature = "std"))]
+macro_rules! try_from_vec_impl(
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::core::convert::TryFrom<::std::vec::Vec<u8>> for $ty<$($gen),*> {
+            type Error = $crate::Error;
+
+            fn try_from(vec: ::std::vec::Vec<u8>) -> Result<Self, $crate::Error> {
+                <$ty<$($gen),*> as $crate::Hash>::from_slice(&vec)
             }
         }
+    )
+);
 
-        impl<$($gen: $gent),*> $crate::core::ops::Deref for $ty<$($gen),*> {
-            type Target = [u8];
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(any(test, feature = "std")))]
+macro_rules! try_from_vec_impl(
+    ($ty:ident, $($gen:ident: $gent:ident),*) => ();
+);
 
-            fn deref(&self) -> &Self::Target {
+/// Adds hex-parsing `FromStr` and `TryFrom<&str>` implementations to a given type `$ty`
+#[macro_export]
+#[cfg(feature = "hex")]
+macro_rules! hex_from_str_impl(
+    ($ty:ident) => (
+        hex_from_str_impl!($ty, );
+    );
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::core::str::FromStr for $ty<$($gen),*> {
+            type Err = $crate::hex::Error;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $crate::hex::FromHex::from_hex(s)
+            }
+        }
+
+        impl<'a, $($gen: $gent),*> $crate::core::convert::TryFrom<&'a str> for $ty<$($gen),*> {
+            type Error = $crate::Error;
+            fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+                $crate::hex::FromHex::from_hex(s).map_err($crate::Error::from)
+            }
+        }
+    )
+);
+
+/// Does an "empty" `FromStr` and `TryFrom<&str>` implementation for the configuration without hex feature
+#[macro_export]
+#[cfg(not(feature = "hex"))]
+macro_rules! hex_from_str_impl(
+    ($ty:ident) => ();
+    ($ty:ident, $($gen:ident: $gent:ident),*) => ();
+);
+
+/// Adds `arbitrary::Arbitrary` implementation to a given hash type `$ty`,
+/// consuming exactly `$len` bytes of the unstructured input
+#[macro_export]
+#[cfg(feature = "arbitrary")]
+macro_rules! arbitrary_impl(
+    ($ty:ident, $len:expr) => (
+        arbitrary_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<'a, $($gen: $gent),*> $crate::arbitrary::Arbitrary<'a> for $ty<$($gen),*> {
+            fn arbitrary(u: &mut $crate::arbitrary::Unstructured<'a>) -> $crate::arbitrary::Result<Self> {
+                let mut inner = [0; $len];
+                inner.copy_from_slice(u.bytes($len)?);
+                Ok(<$ty<$($gen),*> as $crate::Hash>::from_inner(inner))
+            }
+
+            fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+                ($len, Some($len))
+            }
+        }
+    )
+);
+
+/// Does an "empty" arbitrary implementation for the configuration without arbitrary feature
+#[macro_export]
+#[cfg(not(feature = "arbitrary"))]
+macro_rules! arbitrary_impl(
+    ($ty:ident, $len:expr) => ();
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => ();
+);
+
+/// Adds `quickcheck::Arbitrary` implementation to a given hash type `$ty`,
+/// generating uniformly random bytes and shrinking toward the all-zeros hash
+#[macro_export]
+#[cfg(feature = "quickcheck")]
+macro_rules! quickcheck_impl(
+    ($ty:ident, $len:expr) => (
+        quickcheck_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent + 'static),*> $crate::quickcheck::Arbitrary for $ty<$($gen),*> {
+            fn arbitrary(g: &mut $crate::quickcheck::Gen) -> Self {
+                // `u8::arbitrary` favours "problem values" such as 0 and 255
+                const BYTES: [u8; 256] = {
+                    let mut bytes = [0; 256];
+                    let mut i = 0;
+                    while i < 256 {
+                        bytes[i] = i as u8;
+                        i += 1;
+                    }
+                    bytes
+                };
+                let mut inner = [0; $len];
+                for byte in inner.iter_mut() {
+                    *byte = *g.choose(&BYTES).expect("non-empty");
+                }
+                <$ty<$($gen),*> as $crate::Hash>::from_inner(inner)
+            }
+
+            fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+                // The all-zeros hash, then each non-zero byte cleared in turn
+                let inner = <$ty<$($gen),*> as $crate::Hash>::into_inner(*self);
+                let nonzero = inner.iter().filter(|b| **b != 0).count();
+                let zeros = if nonzero > 1 { Some([0; $len]) } else { None };
+                let cleared = (0..$len).filter(move |&i| inner[i] != 0).map(move |i| {
+                    let mut shrunk = inner;
+                    shrunk[i] = 0;
+                    shrunk
+                });
+                Box::new(zeros.into_iter().chain(cleared).map(<$ty<$($gen),*> as $crate::Hash>::from_inner))
+            }
+        }
+    )
+);
+
+/// Does an "empty" quickcheck implementation for the configuration without quickcheck feature
+#[macro_export]
+#[cfg(not(feature = "quickcheck"))]
+macro_rules! quickcheck_impl(
+    ($ty:ident, $len:expr) => ();
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => ();
+);
+
+/// Adds `borsh` serialization to a given hash type `$ty`, as the raw
+/// `$len` bytes with no length prefix, like a borsh fixed-size array
+#[macro_export]
+#[cfg(feature = "borsh")]
+macro_rules! borsh_impl(
+    ($ty:ident, $len:expr) => (
+        borsh_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::borsh::BorshSerialize for $ty<$($gen),*> {
+            fn serialize<W: $crate::borsh::io::Write>(&self, writer: &mut W) -> $crate::borsh::io::Result<()> {
+                writer.write_all(&self[..])
+            }
+        }
+
+        impl<$($gen: $gent),*> $crate::borsh::BorshDeserialize for $ty<$($gen),*> {
+            fn deserialize_reader<R: $crate::borsh::io::Read>(reader: &mut R) -> $crate::borsh::io::Result<Self> {
+                <[u8; $len] as $crate::borsh::BorshDeserialize>::deserialize_reader(reader)
+                    .map(<$ty<$($gen),*> as $crate::Hash>::from_inner)
+            }
+        }
+    )
+);
+
+/// Does an "empty" borsh implementation for the configuration without borsh feature
+#[macro_export]
+#[cfg(not(feature = "borsh"))]
+macro_rules! borsh_impl(
+    ($ty:ident, $len:expr) => ();
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => ();
+);
+
+/// Adds a `defmt::Format` implementation to a given type `$ty`, which logs
+/// it as hex in the same byte order as its `Display` output
+#[macro_export]
+#[cfg(feature = "defmt")]
+macro_rules! defmt_impl(
+    ($ty:ident) => (
+        defmt_impl!($ty, );
+    );
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::defmt::Format for $ty<$($gen),*> {
+            fn format(&self, f: $crate::defmt::Formatter) {
+                use $crate::hex::{format_defmt, format_defmt_reverse};
+                if $ty::<$($gen),*>::DISPLAY_BACKWARD {
+                    format_defmt_reverse(&self.0[..], f)
+                } else {
+                    format_defmt(&self.0[..], f)
+                }
+            }
+        }
+    )
+);
+
+/// Does an "empty" defmt implementation for the configuration without defmt feature
+#[macro_export]
+#[cfg(not(feature = "defmt"))]
+macro_rules! defmt_impl(
+    ($ty:ident) => ();
+    ($ty:ident, $($gen:ident: $gent:ident),*) => ();
+);
+
+/// Adds byte-wise XOR, as an inherent method and through `BitXor`, to a
+/// given hash type `$ty`
+#[macro_export]
+macro_rules! bitxor_impl(
+    ($ty:ident, $len:expr) => (
+        bitxor_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $ty<$($gen),*> {
+            /// Byte-wise XOR of two hashes, e.g. for set-reconciliation sketches
+            pub fn bitxor(&self, other: &Self) -> Self {
+                let mut inner = [0; $len];
+                for (out, (a, b)) in inner.iter_mut().zip(self[..].iter().zip(other[..].iter())) {
+                    *out = a ^ b;
+                }
+                <$ty<$($gen),*> as $crate::Hash>::from_inner(inner)
+            }
+        }
+
+        impl<$($gen: $gent),*> $crate::core::ops::BitXor for $ty<$($gen),*> {
+            type Output = Self;
+
+            fn bitxor(self, other: Self) -> Self {
+                $ty::<$($gen),*>::bitxor(&self, &other)
+            }
+        }
+    )
+);
+
+/// Adds constructors from random bytes to a given hash type `$ty`
+#[macro_export]
+#[cfg(feature = "rand")]
+macro_rules! rand_impl(
+    ($ty:ident, $len:expr) => (
+        rand_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $ty<$($gen),*> {
+            /// Creates a hash with random bytes, e.g. for tests or placeholders.
+            ///
+            /// This is not a hash of anything, and not meant for deriving keys.
+            pub fn random<R: $crate::rand::RngCore + ?Sized>(rng: &mut R) -> Self {
+                let mut inner = [0; $len];
+                rng.fill_bytes(&mut inner);
+                <$ty<$($gen),*> as $crate::Hash>::from_inner(inner)
+            }
+        }
+
+        /// Random hashes from `Rng::gen`, the same as `random`
+        impl<$($gen: $gent),*> $crate::rand::distributions::Distribution<$ty<$($gen),*>> for $crate::rand::distributions::Standard {
+            fn sample<R: $crate::rand::Rng + ?Sized>(&self, rng: &mut R) -> $ty<$($gen),*> {
+                <$ty<$($gen),*>>::random(rng)
+            }
+        }
+
+        rand_os_impl!($ty, $($gen: $gent),*);
+    )
+);
+
+/// Does an "empty" rand implementation for the configuration without rand feature
+#[macro_export]
+#[cfg(not(feature = "rand"))]
+macro_rules! rand_impl(
+    ($ty:ident, $len:expr) => ();
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => ();
+);
+
+/// Adds a constructor from the operating system's random number generator
+/// to a given hash type `$ty`; used by `rand_impl!`
+#[macro_export]
+#[cfg(feature = "rand-std")]
+macro_rules! rand_os_impl(
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $ty<$($gen),*> {
+            /// Creates a hash with random bytes from the operating system's
+            /// random number generator; see `random`.
+            pub fn from_os_rng() -> Self {
+                Self::random(&mut $crate::rand::rngs::OsRng)
+            }
+        }
+    )
+);
+
+/// Does an "empty" OS rng implementation for the configuration without rand-std feature
+#[macro_export]
+#[cfg(not(feature = "rand-std"))]
+macro_rules! rand_os_impl(
+    ($ty:ident, $($gen:ident: $gent:ident),*) => ();
+);
+
+/// Adds `subtle::ConditionallySelectable` implementation to a given hash type `$ty`
+#[macro_export]
+#[cfg(feature = "subtle")]
+macro_rules! subtle_impl(
+    ($ty:ident) => (
+        subtle_impl!($ty, );
+    );
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $crate::subtle::ConditionallySelectable for $ty<$($gen),*> {
+            fn conditional_select(a: &Self, b: &Self, choice: $crate::subtle::Choice) -> Self {
+                let mut ret = <$ty<$($gen),*> as $crate::Hash>::into_inner(*a);
+                let other = <$ty<$($gen),*> as $crate::Hash>::into_inner(*b);
+                for (r, o) in ret.iter_mut().zip(other.iter()) {
+                    $crate::subtle::ConditionallySelectable::conditional_assign(r, o, choice);
+                }
+                <$ty<$($gen),*> as $crate::Hash>::from_inner(ret)
+            }
+        }
+    )
+);
+
+/// Does an "empty" subtle implementation for the configuration without subtle feature
+#[macro_export]
+#[cfg(not(feature = "subtle"))]
+macro_rules! subtle_impl(
+    ($ty:ident) => ();
+    ($ty:ident, $($gen:ident: $gent:ident),*) => ();
+);
+
+/// Adds `const fn` constructors for the all-zero and all-one values to a
+/// hash type `$ty` which is a tuple struct around a `[u8; $len]`
+macro_rules! all_bytes_impl(
+    ($ty:ident, $len:expr) => (
+        impl $ty {
+            /// The hash whose bytes are all zero
+            pub const fn all_zeros() -> $ty {
+                $ty([0x00; $len])
+            }
+
+            /// The hash whose bytes are all `0xff`, i.e. the maximum value
+            /// when read as an integer
+            pub const fn all_ones() -> $ty {
+                $ty([0xff; $len])
+            }
+        }
+    )
+);
+
+/// Adds `const fn` conversions to and from the byte array of a hash type
+/// `$ty` which is a tuple struct around a `[u8; $len]`. Trait methods cannot
+/// be `const`, so these are inherent counterparts of `Hash::from_inner` and
+/// `Hash::into_inner` for use in constant expressions.
+macro_rules! const_array_impl(
+    ($ty:ident, $len:expr) => (
+        impl $ty {
+            /// Constructs a hash from its byte array; usable in constants
+            pub const fn from_byte_array(bytes: [u8; $len]) -> $ty {
+                $ty(bytes)
+            }
+
+            /// Returns the byte array of the hash; usable in constants
+            pub const fn to_byte_array(self) -> [u8; $len] {
+                self.0
+            }
+
+            /// Borrows the byte array of the hash; usable in constants
+            pub const fn as_byte_array(&self) -> &[u8; $len] {
                 &self.0
             }
+
+            /// Alias of `as_byte_array`, named as the borrowing counterpart
+            /// of `Hash::into_inner`
+            pub const fn as_inner(&self) -> &[u8; $len] {
+                self.as_byte_array()
+            }
+        }
+    )
+);
+
+/// Adds conversions to and from the byte-reversed array to a hash type `$ty`
+/// of `$len` bytes whose hex is displayed backward
+macro_rules! reversed_array_impl(
+    ($ty:ident, $len:expr) => (
+        reversed_array_impl!($ty, $len, );
+    );
+    ($ty:ident, $len:expr, $($gen:ident: $gent:ident),*) => (
+        impl<$($gen: $gent),*> $ty<$($gen),*> {
+            /// Returns the bytes of the hash in reverse order, i.e. in the
+            /// order its hex is displayed.
+            ///
+            /// This is not the order used when serializing the hash inside
+            /// transactions and blocks, which is the internal one returned
+            /// by `Hash::into_inner`.
+            pub fn to_reversed_byte_array(&self) -> [u8; $len] {
+                let mut ret = <Self as $crate::Hash>::into_inner(*self);
+                ret.reverse();
+                ret
+            }
+
+            /// Constructs a hash from its bytes in reverse order, i.e. in the
+            /// order its hex is displayed; the inverse of
+            /// `to_reversed_byte_array`
+            pub fn from_reversed_byte_array(mut bytes: [u8; $len]) -> Self {
+                bytes.reverse();
+                <Self as $crate::Hash>::from_inner(bytes)
+            }
+        }
+    )
+);
+
+/// Adds `Extend` implementations for byte slices and single bytes to an
+/// engine type `$ty`; all of them are equivalent to calling `input` on the
+/// same bytes
+macro_rules! engine_extend_impl(
+    ($ty:ident) => (
+        engine_extend_impl!($ty, );
+    );
+    ($ty:ident, $($gen:ident: $gent:ident),*) => (
+        impl<'a, $($gen: $gent),*> $crate::core::iter::Extend<&'a [u8]> for $ty<$($gen),*> {
+            fn extend<I: IntoIterator<Item = &'a [u8]>>(&mut self, iter: I) {
+                for chunk in iter {
+                    $crate::HashEngine::input(self, chunk);
+                }
+            }
+        }
+
+        impl<$($gen: $gent),*> $crate::core::iter::Extend<u8> for $ty<$($gen),*> {
+            fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+                $crate::input_iter(self, iter);
+            }
+        }
+
+        impl<'a, $($gen: $gent),*> $crate::core::iter::Extend<&'a u8> for $ty<$($gen),*> {
+            fn extend<I: IntoIterator<Item = &'a u8>>(&mut self, iter: I) {
+                $crate::core::iter::Extend::<u8>::extend(self, iter.into_iter().cloned());
+            }
         }
     )
 );
@@ -119,19 +628,33 @@ macro_rules! engine_input_impl(
     () => (
         #[cfg(not(feature = "fuzztarget"))]
         fn input(&mut self, mut inp: &[u8]) {
-            while !inp.is_empty() {
-                let buf_idx = self.length % <Self as EngineTrait>::BLOCK_SIZE;
-                let rem_len = <Self as EngineTrait>::BLOCK_SIZE - buf_idx;
-                let write_len = cmp::min(rem_len, inp.len());
+            const BLOCK_SIZE: usize = <HashEngine as EngineTrait>::BLOCK_SIZE;
+
+            let buf_idx = (self.length % BLOCK_SIZE as u64) as usize;
+            self.length = self.length.checked_add(inp.len() as u64)
+                .expect("hash engine length counter overflowed");
 
+            // Top up a partially filled buffer first
+            if buf_idx != 0 {
+                let write_len = cmp::min(BLOCK_SIZE - buf_idx, inp.len());
                 self.buffer[buf_idx..buf_idx + write_len]
                     .copy_from_slice(&inp[..write_len]);
-                self.length += write_len;
-                if self.length % <Self as EngineTrait>::BLOCK_SIZE == 0 {
-                    self.process_block();
+                if buf_idx + write_len == BLOCK_SIZE {
+                    HashEngine::process_block(&mut self.h, &self.buffer);
                 }
                 inp = &inp[write_len..];
             }
+
+            // The buffer is now empty, or `inp` is; compress whole blocks
+            // straight from the input and buffer only the remainder
+            let mut blocks = inp.chunks_exact(BLOCK_SIZE);
+            for block in &mut blocks {
+                let block: &[u8; BLOCK_SIZE] = $crate::core::convert::TryFrom::try_from(block)
+                    .expect("exact chunk");
+                HashEngine::process_block(&mut self.h, block);
+            }
+            let rest = blocks.remainder();
+            self.buffer[..rest.len()].copy_from_slice(rest);
         }
 
         #[cfg(feature = "fuzztarget")]
@@ -139,7 +662,7 @@ macro_rules! engine_input_impl(
             for c in inp {
                 self.buffer[0] ^= *c;
             }
-            self.length += inp.len();
+            self.length += inp.len() as u64;
         }
     )
 );
@@ -204,11 +727,19 @@ define_slice_to_be!(slice_to_u64_be, u64);
 define_be_to_array!(u32_to_array_be, u32, 4);
 define_be_to_array!(u64_to_array_be, u64, 8);
 
-define_slice_to_le!(slice_to_u32_le, u32);
 define_slice_to_le!(slice_to_u64_le, u64);
 define_le_to_array!(u32_to_array_le, u32, 4);
 define_le_to_array!(u64_to_array_le, u64, 8);
 
+/// Returns the message length in bits, as encoded in the final padding of
+/// the hashes with a 64-bit length field (SHA1, SHA256 and RIPEMD160).
+///
+/// Panics, rather than silently wrapping, if the message is longer than the
+/// 2^64 - 1 bits such a field can represent.
+pub fn bit_length(bytes: u64) -> u64 {
+    bytes.checked_mul(8).expect("message is longer than 2^61 - 1 bytes")
+}
+
 #[cfg(test)]
 mod test {
     use Hash;
@@ -222,6 +753,431 @@ mod test {
         assert_eq!(hash.to_vec().len(), sha256::Hash::LEN);
     }
 
+    #[test]
+    fn as_ref_and_borrow() {
+        use std::collections::HashMap;
+        use sha256d;
+
+        fn takes<B: AsRef<[u8]>>(b: B) -> usize {
+            b.as_ref().len()
+        }
+
+        let hash = sha256d::Hash::hash(&[3, 50]);
+        assert_eq!(takes(hash), sha256d::Hash::LEN);
+
+        let array: &[u8; 32] = hash.as_ref();
+        assert_eq!(&array[..], &hash[..]);
+
+        // `Borrow<[u8]>` allows map lookups by plain byte slice
+        let mut map = HashMap::new();
+        map.insert(hash, "value");
+        let bytes = hash.into_inner();
+        assert_eq!(map.get(&bytes[..]), Some(&"value"));
+        assert_eq!(map.get(&[0u8; 32][..]), None);
+    }
+
+    #[test]
+    fn bytes_eq_impl() {
+        use sha256d;
+
+        let hash = sha256d::Hash::hash(&[3, 50]);
+        let array = hash.into_inner();
+        let mut other = array;
+        other[31] ^= 1;
+
+        assert_eq!(array, hash);
+        assert!(other != hash);
+
+        assert_eq!(&array[..], hash);
+        assert!(&array[..31] != hash);
+        assert!(&array[..0] != hash);
+
+        assert_eq!(hash, sha256d::Hash::from_inner(array));
+    }
+
+    #[test]
+    fn from_array_impl() {
+        use hash160;
+
+        fn to_array<H: Into<[u8; 20]>>(h: H) -> [u8; 20] {
+            h.into()
+        }
+        fn to_hash<A: Into<hash160::Hash>>(a: A) -> hash160::Hash {
+            a.into()
+        }
+
+        let array = [7u8; 20];
+        let hash = hash160::Hash::from(array);
+        assert_eq!(hash, hash160::Hash::from_slice(&array).unwrap());
+        assert_eq!(to_array(hash), array);
+        assert_eq!(to_array(to_hash(array)), array);
+
+        let borrowed: &[u8; 20] = (&hash).into();
+        assert_eq!(borrowed, &array);
+    }
+
+    #[test]
+    fn try_from_impl() {
+        use std::convert::TryFrom;
+        use std::fmt::Debug;
+        use {hash160, Error};
+
+        fn parse<H>(bytes: &[u8]) -> Result<H, Error>
+        where
+            H: for<'a> TryFrom<&'a [u8], Error = Error> + Debug,
+        {
+            H::try_from(bytes)
+        }
+
+        let bytes = [9u8; 21];
+        let hash = hash160::Hash::try_from(&bytes[..20]).unwrap();
+        assert_eq!(hash, hash160::Hash::from_slice(&bytes[..20]).unwrap());
+        assert_eq!(parse::<hash160::Hash>(&bytes[..20]), Ok(hash));
+        assert_eq!(hash160::Hash::try_from(bytes[..20].to_vec()), Ok(hash));
+
+        assert_eq!(hash160::Hash::try_from(&bytes[..19]), Err(Error::InvalidLength(20, 19)));
+        assert_eq!(parse::<hash160::Hash>(&bytes[..]), Err(Error::InvalidLength(20, 21)));
+        assert_eq!(hash160::Hash::try_from(Vec::new()), Err(Error::InvalidLength(20, 0)));
+        assert_eq!(parse::<sha256::Hash>(&bytes[..20]), Err(Error::InvalidLength(32, 20)));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn try_from_str() {
+        use std::convert::TryFrom;
+        use std::fmt::Debug;
+        use {hash160, sha256d, sha256t, Error};
+
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+        struct TestTag;
+
+        impl sha256t::Tag for TestTag {
+            fn engine() -> sha256::HashEngine {
+                sha256::HashEngine::default()
+            }
+        }
+
+        fn parse<H>(s: &str) -> Result<H, Error>
+        where
+            H: for<'a> TryFrom<&'a str, Error = Error> + Debug,
+        {
+            H::try_from(s)
+        }
+
+        let hex = "6cfb35868c4465b7c289d7d5641563aa973db6a929655282a7bf95c8257f53ef";
+        assert_eq!(sha256::Hash::try_from(hex), Ok(hex.parse::<sha256::Hash>().unwrap()));
+        assert_eq!(parse::<sha256d::Hash>(hex), Ok(hex.parse::<sha256d::Hash>().unwrap()));
+        assert_eq!(parse::<sha256d::Hash>(hex).unwrap().to_string(), hex);
+        assert_eq!(parse::<hash160::Hash>(&hex[..40]).unwrap().to_string(), &hex[..40]);
+
+        assert_eq!(parse::<sha256t::Hash<TestTag>>(hex).unwrap().to_string(), hex);
+
+        assert_eq!(parse::<hash160::Hash>(hex), Err(Error::InvalidLength(20, 32)));
+        assert_eq!(parse::<sha256::Hash>(&hex[1..]), Err(Error::InvalidHexLength(63)));
+        assert_eq!(
+            parse::<sha256::Hash>(&hex.replace('c', "g")),
+            Err(Error::InvalidHexChar('g', 1))
+        );
+    }
+
+    #[test]
+    fn engine_extend_impl() {
+        use {hmac, ripemd160, sha1, sha512, siphash24, HashEngine};
+
+        fn check<H: Hash>(data: &[u8])
+        where
+            H::Engine: Extend<u8> + for<'a> Extend<&'a u8> + for<'a> Extend<&'a [u8]>,
+        {
+            let expected = <H as Hash>::hash(data);
+
+            let mut engine = H::engine();
+            engine.extend(data.iter().copied());
+            assert_eq!(H::from_engine(engine), expected);
+
+            let mut engine = H::engine();
+            engine.extend(data.iter());
+            assert_eq!(H::from_engine(engine), expected);
+
+            let mut engine = H::engine();
+            engine.extend(data.chunks(7));
+            assert_eq!(H::from_engine(engine), expected);
+        }
+
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 7) as u8).collect();
+        for &len in &[0, 1, 63, 64, 65, 128, 300] {
+            check::<sha1::Hash>(&data[..len]);
+            check::<sha256::Hash>(&data[..len]);
+            check::<sha512::Hash>(&data[..len]);
+            check::<ripemd160::Hash>(&data[..len]);
+            check::<siphash24::Hash>(&data[..len]);
+        }
+
+        let mut engine = hmac::HmacEngine::<sha256::Hash>::new(b"key");
+        engine.extend(data.iter());
+        let mut expected = hmac::HmacEngine::<sha256::Hash>::new(b"key");
+        expected.input(&data);
+        assert_eq!(
+            hmac::Hmac::<sha256::Hash>::from_engine(engine),
+            hmac::Hmac::<sha256::Hash>::from_engine(expected)
+        );
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_impl() {
+        use arbitrary::{Arbitrary, Unstructured};
+        use {hash160, ripemd160};
+
+        let data: Vec<u8> = (0..52).collect();
+        let mut u = Unstructured::new(&data);
+        let sha = sha256::Hash::arbitrary(&mut u).unwrap();
+        assert_eq!(&data[..32], sha);
+        let rmd = ripemd160::Hash::arbitrary(&mut u).unwrap();
+        assert_eq!(&data[32..], rmd);
+
+        // 19 bytes left, one short of a HASH160
+        let mut u = Unstructured::new(&data[33..]);
+        assert_eq!(hash160::Hash::arbitrary(&mut u), Err(arbitrary::Error::NotEnoughData));
+        assert_eq!(<hash160::Hash as Arbitrary>::size_hint(0), (20, Some(20)));
+    }
+
+    #[cfg(all(feature = "quickcheck", feature = "hex"))]
+    #[test]
+    fn quickcheck_impl() {
+        use std::str::FromStr;
+        use quickcheck::{quickcheck, Arbitrary};
+        use hex::ToHex;
+        use {hash160, sha256d, sha512, Hmac};
+
+        fn round_trips<H: Hash + FromStr>(hash: H) -> bool {
+            H::from_str(&hash.to_hex()).ok() == Some(hash)
+                && H::from_slice(&hash[..]) == Ok(hash)
+                && H::from_inner(hash.into_inner()) == hash
+        }
+        quickcheck(round_trips::<sha256::Hash> as fn(sha256::Hash) -> bool);
+        quickcheck(round_trips::<sha256d::Hash> as fn(sha256d::Hash) -> bool);
+        quickcheck(round_trips::<hash160::Hash> as fn(hash160::Hash) -> bool);
+        quickcheck(round_trips::<sha512::Hash> as fn(sha512::Hash) -> bool);
+        quickcheck(round_trips::<Hmac<sha256::Hash>> as fn(Hmac<sha256::Hash>) -> bool);
+
+        fn nonzero(hash: &sha256::Hash) -> usize {
+            hash[..].iter().filter(|b| **b != 0).count()
+        }
+        fn shrinks_toward_zero(hash: sha256::Hash) -> bool {
+            let shrunk: Vec<_> = hash.shrink().collect();
+            shrunk.iter().all(|s| nonzero(s) < nonzero(&hash))
+                && (nonzero(&hash) < 2 || shrunk[0] == sha256::Hash::all_zeros())
+        }
+        quickcheck(shrinks_toward_zero as fn(sha256::Hash) -> bool);
+        assert_eq!(sha256::Hash::all_zeros().shrink().count(), 0);
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn borsh_impl() {
+        use borsh::{self, BorshDeserialize, BorshSerialize};
+        use {hash160, hash160_sha512, ripemd160, sha1, sha256d, sha256t, sha512, sha512_256, siphash24, Hmac};
+
+        fn round_trip<H: Hash + BorshSerialize + BorshDeserialize>() {
+            let hash = <H as Hash>::hash(b"borsh");
+            let encoded = borsh::to_vec(&hash).unwrap();
+            assert_eq!(encoded.len(), H::LEN);
+            assert_eq!(&encoded[..], &hash[..]);
+            assert_eq!(H::try_from_slice(&encoded).unwrap(), hash);
+
+            // Truncated input is borsh's usual error, and so are trailing bytes
+            let err = H::try_from_slice(&encoded[..H::LEN - 1]).unwrap_err();
+            assert_eq!(err.kind(), borsh::io::ErrorKind::InvalidData);
+            assert_eq!(err.to_string(), <[u8; 4]>::try_from_slice(&[0; 3]).unwrap_err().to_string());
+            let mut long = encoded.clone();
+            long.push(0);
+            assert!(H::try_from_slice(&long).is_err());
+        }
+
+        #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        struct TestTag;
+        impl sha256t::Tag for TestTag {
+            fn engine() -> sha256::HashEngine {
+                sha256::HashEngine::default()
+            }
+        }
+
+        round_trip::<sha1::Hash>();
+        round_trip::<sha256::Hash>();
+        round_trip::<sha256d::Hash>();
+        round_trip::<sha256t::Hash<TestTag>>();
+        round_trip::<sha512::Hash>();
+        round_trip::<sha512_256::Hash>();
+        round_trip::<ripemd160::Hash>();
+        round_trip::<hash160::Hash>();
+        round_trip::<hash160_sha512::Hash>();
+        round_trip::<siphash24::Hash>();
+        round_trip::<Hmac<sha256::Hash>>();
+        round_trip::<Hmac<sha512::Hash>>();
+
+        // Exact layout: raw bytes in internal order, even for hashes displayed
+        // backward, with no length prefix; inside a struct they are simply
+        // concatenated
+        let hash = sha256d::Hash::from_inner([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+        ]);
+        let short = siphash24::Hash::from_inner([0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7]);
+        let encoded = borsh::to_vec(&(hash, 7u8, short)).unwrap();
+        let mut expected: Vec<u8> = (0..32).collect();
+        expected.push(7);
+        expected.extend_from_slice(&[0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7]);
+        assert_eq!(encoded, expected);
+        assert_eq!(<(sha256d::Hash, u8, siphash24::Hash)>::try_from_slice(&encoded).unwrap(), (hash, 7, short));
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn defmt_impl() {
+        use defmt::Format;
+        use {hash160, hash160_sha512, ripemd160, sha1, sha256d, sha256t, sha512, sha512_256, siphash24, Hmac};
+
+        #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        struct TestTag;
+        impl sha256t::Tag for TestTag {
+            fn engine() -> sha256::HashEngine {
+                sha256::HashEngine::default()
+            }
+        }
+
+        // Formatting needs a global logger, so on the host we can only check
+        // that the impls are there
+        fn assert_format<T: Format>() {}
+
+        assert_format::<sha1::Hash>();
+        assert_format::<sha256::Hash>();
+        assert_format::<sha256::Midstate>();
+        assert_format::<sha256d::Hash>();
+        assert_format::<sha256t::Hash<TestTag>>();
+        assert_format::<sha512::Hash>();
+        assert_format::<sha512_256::Hash>();
+        assert_format::<ripemd160::Hash>();
+        assert_format::<hash160::Hash>();
+        assert_format::<hash160_sha512::Hash>();
+        assert_format::<siphash24::Hash>();
+        assert_format::<Hmac<sha256::Hash>>();
+        assert_format::<Hmac<sha512::Hash>>();
+        assert_format::<::tests::TestNewtype>();
+    }
+
+    #[test]
+    fn bitxor_impl() {
+        use sha256d;
+
+        let a = sha256d::Hash::hash(b"a");
+        let b = sha256d::Hash::hash(b"b");
+        let zero = sha256d::Hash::all_zeros();
+        assert_eq!(a.bitxor(&a), zero);
+        assert_eq!(a ^ a, zero);
+        assert_eq!(a.bitxor(&zero), a);
+        assert_eq!(zero ^ a, a);
+        assert_eq!(a ^ b, b ^ a);
+        assert_eq!((a ^ b) ^ b, a);
+        assert_eq!((a ^ sha256d::Hash::all_ones())[0], !a[0]);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn rand_impl() {
+        use rand::rngs::mock::StepRng;
+        use {hash160, sha256t};
+
+        let mut rng = StepRng::new(1, 1);
+        let first = sha256::Hash::random(&mut rng);
+        let second = sha256::Hash::random(&mut rng);
+        assert_ne!(first, second);
+        assert_eq!(first[..8], [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(hash160::Hash::random(&mut rng)[..4], [9, 0, 0, 0]);
+
+        #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        struct TestTag;
+        impl sha256t::Tag for TestTag {
+            fn engine() -> sha256::HashEngine {
+                sha256::HashEngine::default()
+            }
+        }
+        let tagged = sha256t::Hash::<TestTag>::random(&mut rng);
+        assert_eq!(tagged[..4], [12, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn rand_distribution() {
+        use rand::{Rng, SeedableRng};
+        use rand::rngs::StdRng;
+        use {hash160, sha256d};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let first: sha256d::Hash = rng.gen();
+        let second: sha256d::Hash = rng.gen();
+        assert_ne!(first, second);
+        assert_eq!(first.into_inner(), [
+            0xa2, 0x24, 0x27, 0x22, 0x63, 0x77, 0xcc, 0x86, 0x7d, 0x51, 0xad, 0x3f, 0x13, 0x0a, 0xf0, 0x8a,
+            0xd1, 0x34, 0x51, 0xde, 0x71, 0x60, 0xef, 0xa2, 0xb2, 0x30, 0x76, 0xfd, 0x78, 0x2d, 0xe9, 0x67,
+        ]);
+        let short: hash160::Hash = rng.gen();
+        assert_ne!(short[..], first[..20]);
+
+        // Same as `random` from the same seed, for any hash type
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(sha256d::Hash::random(&mut rng), first);
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(rng.gen::<sha256::Hash>()[..], first[..]);
+    }
+
+    #[cfg(feature = "rand-std")]
+    #[test]
+    fn rand_os_impl() {
+        use rand::rngs::OsRng;
+
+        assert_ne!(sha256::Hash::from_os_rng(), sha256::Hash::from_os_rng());
+        assert_ne!(sha256::Hash::random(&mut OsRng), sha256::Hash::random(&mut OsRng));
+    }
+
+    #[test]
+    fn index_impl_ranges() {
+        use hash160;
+
+        let long = sha256::Hash::hash(&[3, 50]);
+        let short = hash160::Hash::hash(&[3, 50]);
+        let long_bytes = long.into_inner();
+        let short_bytes = short.into_inner();
+
+        assert_eq!(&long[..], &long_bytes[..]);
+        assert_eq!(&long[0..4], &long_bytes[0..4]);
+        assert_eq!(&long[4..], &long_bytes[4..]);
+        assert_eq!(&long[..4], &long_bytes[..4]);
+        assert_eq!(&long[0..=3], &long_bytes[0..=3]);
+        assert_eq!(&long[..=3], &long_bytes[..=3]);
+        assert_eq!(long[31], long_bytes[31]);
+
+        assert_eq!(&short[..], &short_bytes[..]);
+        assert_eq!(&short[0..4], &short_bytes[0..4]);
+        assert_eq!(&short[4..], &short_bytes[4..]);
+        assert_eq!(&short[..4], &short_bytes[..4]);
+        assert_eq!(&short[0..=3], &short_bytes[0..=3]);
+        assert_eq!(&short[..=3], &short_bytes[..=3]);
+        assert_eq!(short[19], short_bytes[19]);
+
+        assert_eq!(long.get(..4), Some(&long_bytes[..4]));
+        assert_eq!(long.get(30..34), None);
+        assert_eq!(short.get(20), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range for bitcoin_hashes::hash160::Hash of length 20")]
+    fn index_impl_out_of_bounds() {
+        use hash160;
+
+        let hash = hash160::Hash::hash(&[]);
+        let _ = &hash[16..24];
+    }
+
     #[test]
     fn endianness_test() {
         assert_eq!(slice_to_u32_be(&[0xde, 0xad, 0xbe, 0xef]), 0xdeadbeef);
@@ -229,7 +1185,6 @@ mod test {
         assert_eq!(u32_to_array_be(0xdeadbeef), [0xde, 0xad, 0xbe, 0xef]);
         assert_eq!(u64_to_array_be(0x1badcafedeadbeef), [0x1b, 0xad, 0xca, 0xfe, 0xde, 0xad, 0xbe, 0xef]);
 
-        assert_eq!(slice_to_u32_le(&[0xef, 0xbe, 0xad, 0xde]), 0xdeadbeef);
         assert_eq!(slice_to_u64_le(&[0xef, 0xbe, 0xad, 0xde, 0xfe, 0xca, 0xad, 0x1b]), 0x1badcafedeadbeef);
         assert_eq!(u32_to_array_le(0xdeadbeef), [0xef, 0xbe, 0xad, 0xde]);
         assert_eq!(u64_to_array_le(0x1badcafedeadbeef), [0xef, 0xbe, 0xad, 0xde, 0xfe, 0xca, 0xad, 0x1b]);